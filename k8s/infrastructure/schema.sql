@@ -74,3 +74,503 @@ CREATE TABLE IF NOT EXISTS notifications (
     created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
 );
 CREATE INDEX IF NOT EXISTS idx_notifications_user_id ON notifications(user_id);
+
+-- Login Lockout
+ALTER TABLE users ADD COLUMN IF NOT EXISTS failed_login_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS last_failed_login_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locked_until TIMESTAMP WITH TIME ZONE;
+
+CREATE TABLE IF NOT EXISTS login_attempts (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE, -- NULL for unknown emails
+    email VARCHAR(255) NOT NULL,
+    ip VARCHAR(64),
+    succeeded BOOLEAN NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_login_attempts_ip ON login_attempts(ip, created_at);
+CREATE INDEX IF NOT EXISTS idx_login_attempts_user_id ON login_attempts(user_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_login_attempts_email ON login_attempts(email, created_at);
+
+-- Email Verification
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'users' AND column_name = 'email_verified') THEN
+        ALTER TABLE users ADD COLUMN email_verified BOOLEAN NOT NULL DEFAULT FALSE;
+        -- Accounts created before verification existed are grandfathered in
+        UPDATE users SET email_verified = TRUE;
+    END IF;
+END $$;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS email_verified_at TIMESTAMP WITH TIME ZONE;
+
+-- Password Reset
+ALTER TABLE users ADD COLUMN IF NOT EXISTS session_version INTEGER NOT NULL DEFAULT 0; -- Bumped to revoke issued JWTs
+
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash CHAR(64) NOT NULL UNIQUE, -- SHA-256 of the emailed token
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_password_reset_tokens_user_id ON password_reset_tokens(user_id);
+
+-- Two-Factor Authentication (TOTP)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_secret VARCHAR(64); -- Base32; set on enrollment, enabled after confirmation
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_last_used_step BIGINT; -- Rejects replayed codes
+
+CREATE TABLE IF NOT EXISTS totp_recovery_codes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash CHAR(64) NOT NULL, -- SHA-256 of the normalized code
+    used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_totp_recovery_codes_user_id ON totp_recovery_codes(user_id);
+
+-- Social Login (OpenID Connect)
+CREATE TABLE IF NOT EXISTS user_identities (
+    provider VARCHAR(50) NOT NULL, -- Configured provider name, e.g. 'google'
+    subject VARCHAR(255) NOT NULL, -- The provider's stable `sub` claim
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    email VARCHAR(255), -- As reported by the provider at link time
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (provider, subject)
+);
+CREATE INDEX IF NOT EXISTS idx_user_identities_user_id ON user_identities(user_id);
+
+CREATE TABLE IF NOT EXISTS oidc_login_states (
+    state VARCHAR(64) PRIMARY KEY,
+    provider VARCHAR(50) NOT NULL,
+    nonce VARCHAR(64) NOT NULL,
+    code_verifier VARCHAR(128) NOT NULL, -- PKCE verifier; only its S256 challenge leaves the service
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+-- Profile Management
+ALTER TABLE users ADD COLUMN IF NOT EXISTS avatar_url TEXT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS links TEXT[] NOT NULL DEFAULT '{}'; -- Personal site, GitHub, LinkedIn...
+ALTER TABLE users ADD COLUMN IF NOT EXISTS pending_email VARCHAR(255); -- Requested new address, until confirmed
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE; -- Set when the account is anonymized
+
+CREATE TABLE IF NOT EXISTS user_skills (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    skill_name VARCHAR(50) NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0, -- Display order chosen by the user
+    PRIMARY KEY (user_id, skill_name)
+);
+
+-- Skills Catalog and Collaborator Matching
+-- Catalog keys are slugs; both services normalize through these functions so they can't drift apart.
+CREATE OR REPLACE FUNCTION skill_slug(name TEXT) RETURNS TEXT AS $$
+    SELECT lower(regexp_replace(trim(name), '\s+', ' ', 'g'))
+$$ LANGUAGE SQL IMMUTABLE;
+
+CREATE TABLE IF NOT EXISTS skills (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    slug VARCHAR(50) NOT NULL UNIQUE, -- skill_slug(name)
+    name VARCHAR(50) NOT NULL, -- Display spelling, from the first use
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Alternative spellings that resolve to a canonical skill (e.g. 'js' -> JavaScript).
+CREATE TABLE IF NOT EXISTS skill_aliases (
+    alias_slug VARCHAR(50) PRIMARY KEY,
+    skill_id UUID NOT NULL REFERENCES skills(id) ON DELETE CASCADE
+);
+
+INSERT INTO skills (slug, name) VALUES
+    ('javascript', 'JavaScript'), ('typescript', 'TypeScript'), ('python', 'Python'), ('rust', 'Rust'),
+    ('go', 'Go'), ('java', 'Java'), ('react', 'React'), ('node.js', 'Node.js'), ('postgresql', 'PostgreSQL'),
+    ('machine learning', 'Machine Learning'), ('ui design', 'UI Design'), ('product management', 'Product Management'),
+    ('marketing', 'Marketing'), ('sales', 'Sales'), ('finance', 'Finance'), ('devops', 'DevOps')
+ON CONFLICT (slug) DO NOTHING;
+
+INSERT INTO skill_aliases (alias_slug, skill_id)
+SELECT alias, (SELECT id FROM skills WHERE slug = canonical) FROM (VALUES
+    ('js', 'javascript'), ('ts', 'typescript'), ('golang', 'go'), ('nodejs', 'node.js'), ('node', 'node.js'),
+    ('postgres', 'postgresql'), ('ml', 'machine learning'), ('ux', 'ui design'), ('pm', 'product management')
+) AS a(alias, canonical)
+ON CONFLICT (alias_slug) DO NOTHING;
+
+-- Resolves free-form names to catalog ids, creating unknown skills. Rows keep input order (`ord`);
+-- names resolving to the same skill are returned once.
+CREATE OR REPLACE FUNCTION resolve_skills(names TEXT[]) RETURNS TABLE (ord BIGINT, skill_id UUID, name TEXT) AS $$
+BEGIN
+    INSERT INTO skills (slug, name)
+    SELECT DISTINCT ON (skill_slug(n)) skill_slug(n), regexp_replace(trim(n), '\s+', ' ', 'g')
+    FROM UNNEST(names) AS n
+    WHERE skill_slug(n) <> '' AND NOT EXISTS (SELECT 1 FROM skill_aliases WHERE alias_slug = skill_slug(n))
+    ON CONFLICT (slug) DO NOTHING;
+
+    RETURN QUERY
+    SELECT MIN(i.ord), s.id, s.name::TEXT
+    FROM UNNEST(names) WITH ORDINALITY AS i(n, ord)
+    JOIN skills s ON s.id = COALESCE(
+        (SELECT a.skill_id FROM skill_aliases a WHERE a.alias_slug = skill_slug(i.n)),
+        (SELECT s2.id FROM skills s2 WHERE s2.slug = skill_slug(i.n)))
+    GROUP BY s.id, s.name
+    ORDER BY MIN(i.ord);
+END
+$$ LANGUAGE plpgsql;
+
+-- Move the free-text skill columns onto the catalog.
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'user_skills' AND column_name = 'skill_name') THEN
+        ALTER TABLE user_skills ADD COLUMN skill_id UUID REFERENCES skills(id) ON DELETE CASCADE;
+        UPDATE user_skills us SET skill_id = r.skill_id
+            FROM (SELECT DISTINCT skill_name FROM user_skills) names, LATERAL resolve_skills(ARRAY[names.skill_name]) r
+            WHERE us.skill_name = names.skill_name;
+        DELETE FROM user_skills a USING user_skills b
+            WHERE a.user_id = b.user_id AND a.skill_id = b.skill_id AND a.position > b.position;
+        ALTER TABLE user_skills DROP CONSTRAINT user_skills_pkey;
+        ALTER TABLE user_skills DROP COLUMN skill_name;
+        ALTER TABLE user_skills ALTER COLUMN skill_id SET NOT NULL;
+        ALTER TABLE user_skills ADD PRIMARY KEY (user_id, skill_id);
+    END IF;
+
+    IF EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'idea_skills' AND column_name = 'skill_name') THEN
+        ALTER TABLE idea_skills ADD COLUMN skill_id UUID REFERENCES skills(id) ON DELETE CASCADE;
+        UPDATE idea_skills i SET skill_id = r.skill_id
+            FROM (SELECT DISTINCT skill_name FROM idea_skills) names, LATERAL resolve_skills(ARRAY[names.skill_name]) r
+            WHERE i.skill_name = names.skill_name;
+        DELETE FROM idea_skills a USING idea_skills b
+            WHERE a.idea_id = b.idea_id AND a.skill_id = b.skill_id AND a.id > b.id;
+        ALTER TABLE idea_skills DROP COLUMN skill_name;
+        ALTER TABLE idea_skills ALTER COLUMN skill_id SET NOT NULL;
+    END IF;
+END $$;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_idea_skills_idea_skill ON idea_skills(idea_id, skill_id);
+CREATE INDEX IF NOT EXISTS idx_user_skills_skill_id ON user_skills(skill_id);
+CREATE INDEX IF NOT EXISTS idx_idea_skills_skill_id ON idea_skills(skill_id);
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS open_to_collaborate BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS hours_per_week INTEGER; -- Self-reported availability; NULL = not stated
+
+-- Reputation
+-- Append-only ledger; users.reputation_score is recomputed from it (with decay and caps) by svc-brain-core.
+CREATE TABLE IF NOT EXISTS reputation_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE, -- Who earns the points
+    kind VARCHAR(40) NOT NULL, -- 'task_completed', 'idea_launched', 'endorsement', 'investor_rating'
+    points DOUBLE PRECISION NOT NULL, -- Before decay
+    source_user_id UUID REFERENCES users(id) ON DELETE SET NULL, -- Endorser or investor, for per-source caps
+    subject_id UUID, -- Task, idea, project or skill the event is about
+    dedupe_key VARCHAR(255) NOT NULL UNIQUE, -- One event per real-world action
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_reputation_events_user_id ON reputation_events(user_id, created_at);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS reputation_updated_at TIMESTAMP WITH TIME ZONE;
+
+-- Personal Access Tokens
+CREATE TABLE IF NOT EXISTS personal_access_tokens (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL, -- What the owner calls it, e.g. 'CI deploys'
+    token_hash CHAR(64) NOT NULL UNIQUE, -- SHA-256 of the full token; the token itself is shown once
+    token_prefix VARCHAR(20) NOT NULL, -- First characters, so owners can tell tokens apart
+    scopes TEXT[] NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    last_used_ip VARCHAR(45),
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_personal_access_tokens_user_id ON personal_access_tokens(user_id);
+
+-- Security Audit Log
+-- Append-only: the trigger below rejects DELETE and any UPDATE but one, so entries can't be rewritten
+-- after the fact. The exception is erasure: deleting an account pseudonymizes its entries, clearing the
+-- email, user agent and emails in details and replacing the IP with a salted hash (the salt is discarded,
+-- so entries from one address still correlate but the address can't be recovered).
+CREATE TABLE IF NOT EXISTS auth_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    event_type VARCHAR(50) NOT NULL, -- e.g. 'login', 'password_changed', 'role_changed'
+    outcome VARCHAR(10) NOT NULL, -- 'success' or 'failure'
+    user_id UUID, -- Account concerned; no FK so entries outlive the account row
+    actor_id UUID, -- Who acted, when not the user themselves (e.g. an admin)
+    email VARCHAR(255), -- Address tried, for failed logins to unknown accounts
+    ip VARCHAR(45),
+    user_agent VARCHAR(512),
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_auth_events_user_id ON auth_events(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_auth_events_created_at ON auth_events(created_at DESC);
+
+CREATE OR REPLACE FUNCTION auth_events_append_only() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'UPDATE'
+        AND (NEW.id, NEW.event_type, NEW.outcome, NEW.user_id, NEW.actor_id, NEW.created_at)
+            IS NOT DISTINCT FROM (OLD.id, OLD.event_type, OLD.outcome, OLD.user_id, OLD.actor_id, OLD.created_at)
+        AND NEW.email IS NULL AND NEW.user_agent IS NULL
+        AND (NEW.ip IS NULL OR NEW.ip LIKE 'erased:%')
+        AND NEW.details = OLD.details - 'new_email' THEN
+        RETURN NEW;
+    END IF;
+    RAISE EXCEPTION 'auth_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS auth_events_append_only ON auth_events;
+CREATE TRIGGER auth_events_append_only BEFORE UPDATE OR DELETE ON auth_events
+    FOR EACH ROW EXECUTE FUNCTION auth_events_append_only();
+
+-- Data Export and Erasure
+-- Processed in the background by svc-identity, which asks svc-brain-core for its side of the data.
+CREATE TABLE IF NOT EXISTS data_requests (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind VARCHAR(20) NOT NULL, -- 'export' or 'erasure'
+    status VARCHAR(20) NOT NULL DEFAULT 'pending', -- 'pending', 'processing', 'completed', 'failed', 'expired'
+    format VARCHAR(10), -- Exports: 'zip' or 'json'
+    delete_content BOOLEAN NOT NULL DEFAULT FALSE, -- Erasures: also delete public ideas and projects
+    status_token_hash CHAR(64), -- Erasures: SHA-256 of the token the (signed out) user polls with
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_at TIMESTAMP WITH TIME ZONE, -- Of the current attempt; stale ones are picked up again
+    error TEXT,
+    archive BYTEA, -- Exports: the finished file, until expires_at
+    size_bytes BIGINT,
+    expires_at TIMESTAMP WITH TIME ZONE,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_data_requests_user_id ON data_requests(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_data_requests_queue ON data_requests(next_attempt_at) WHERE status IN ('pending', 'processing');
+
+-- Organizations
+-- Workspaces that own ideas and projects. Rows with a NULL org_id live in the personal space:
+-- ideas there stay public, projects belong to their owner alone.
+CREATE TABLE IF NOT EXISTS organizations (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(100) NOT NULL,
+    slug VARCHAR(50) NOT NULL UNIQUE, -- Lowercase letters, digits and dashes
+    created_by UUID REFERENCES users(id),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS organization_members (
+    org_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role VARCHAR(20) NOT NULL, -- 'owner', 'admin' or 'member'
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (org_id, user_id)
+);
+CREATE INDEX IF NOT EXISTS idx_organization_members_user_id ON organization_members(user_id);
+
+ALTER TABLE ideas ADD COLUMN IF NOT EXISTS org_id UUID REFERENCES organizations(id);
+ALTER TABLE projects ADD COLUMN IF NOT EXISTS org_id UUID REFERENCES organizations(id);
+ALTER TABLE personal_access_tokens ADD COLUMN IF NOT EXISTS org_id UUID REFERENCES organizations(id) ON DELETE CASCADE; -- Workspace the token acts in
+CREATE INDEX IF NOT EXISTS idx_ideas_org_id ON ideas(org_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_projects_org_id ON projects(org_id);
+
+-- Project Teams and Invitations
+-- The owner is projects.owner_id; everyone else on a project's team has a row here.
+CREATE TABLE IF NOT EXISTS project_members (
+    project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role VARCHAR(30) NOT NULL, -- 'maintainer', 'contributor' or 'viewer'
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (project_id, user_id)
+);
+CREATE INDEX IF NOT EXISTS idx_project_members_user_id ON project_members(user_id);
+
+CREATE TABLE IF NOT EXISTS project_invitations (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    email VARCHAR(255) NOT NULL, -- Lowercased; need not belong to an account yet
+    role VARCHAR(30) NOT NULL,
+    token_hash VARCHAR(64) NOT NULL UNIQUE, -- SHA-256 of the token in the invite link
+    invited_by UUID REFERENCES users(id),
+    invitee_id UUID REFERENCES users(id), -- The account behind the email, once known
+    status VARCHAR(20) NOT NULL DEFAULT 'pending', -- 'pending', 'accepted', 'declined', 'revoked' or 'expired'
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    responded_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+-- One open invitation per address and project.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_project_invitations_pending ON project_invitations(project_id, email) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_project_invitations_email ON project_invitations(email) WHERE status = 'pending';
+
+-- Project Ownership Transfers
+-- The owner offers the project to someone on its team; nothing changes until they accept.
+CREATE TABLE IF NOT EXISTS project_ownership_transfers (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    from_user_id UUID NOT NULL REFERENCES users(id),
+    to_user_id UUID NOT NULL REFERENCES users(id),
+    previous_owner_role VARCHAR(30) NOT NULL, -- What the outgoing owner stays on as
+    status VARCHAR(20) NOT NULL DEFAULT 'pending', -- 'pending', 'accepted', 'declined', 'cancelled' or 'expired'
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    responded_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+-- One open offer per project.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_project_ownership_transfers_pending ON project_ownership_transfers(project_id) WHERE status = 'pending';
+CREATE INDEX IF NOT EXISTS idx_project_ownership_transfers_to_user_id ON project_ownership_transfers(to_user_id) WHERE status = 'pending';
+
+-- Append-only history of changes to who runs a project.
+CREATE TABLE IF NOT EXISTS project_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    project_id UUID NOT NULL, -- No FKs so entries outlive the project and the people involved
+    event_type VARCHAR(50) NOT NULL, -- e.g. 'ownership_transferred'
+    actor_id UUID,
+    user_id UUID, -- Who the change was about, when not the actor
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_project_events_project_id ON project_events(project_id, created_at DESC);
+
+CREATE OR REPLACE FUNCTION project_events_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'project_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS project_events_append_only ON project_events;
+CREATE TRIGGER project_events_append_only BEFORE UPDATE OR DELETE ON project_events
+    FOR EACH ROW EXECUTE FUNCTION project_events_append_only();
+
+-- Soft Deletion
+-- Deleted ideas and tasks and archived projects keep their rows until svc-brain-core's purge
+-- job removes them once the restore window has passed.
+ALTER TABLE ideas ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE projects ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE; -- Archived
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE;
+CREATE INDEX IF NOT EXISTS idx_ideas_deleted_at ON ideas(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_projects_deleted_at ON projects(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_tasks_deleted_at ON tasks(deleted_at) WHERE deleted_at IS NOT NULL;
+
+-- Idea Revisions
+-- A full snapshot of the idea after every change, numbered from 1 (as created).
+ALTER TABLE ideas ADD COLUMN IF NOT EXISTS revision INTEGER NOT NULL DEFAULT 1; -- Latest idea_revisions.revision
+CREATE TABLE IF NOT EXISTS idea_revisions (
+    idea_id UUID NOT NULL REFERENCES ideas(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    author_id UUID REFERENCES users(id),
+    title VARCHAR(255) NOT NULL,
+    problem TEXT NOT NULL,
+    solution TEXT NOT NULL,
+    required_skills TEXT[] NOT NULL DEFAULT '{}', -- Catalog display names, sorted
+    changed_fields TEXT[] NOT NULL DEFAULT '{}', -- Compared with the revision before
+    restored_from INTEGER, -- Set when the edit brought back an earlier revision
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (idea_id, revision)
+);
+-- Ideas from before revisions were kept start with their current state.
+INSERT INTO idea_revisions (idea_id, revision, author_id, title, problem, solution, required_skills, changed_fields, created_at)
+SELECT i.id, 1, i.creator_id, i.title, i.problem, i.solution,
+       ARRAY(SELECT s.name FROM idea_skills x JOIN skills s ON s.id = x.skill_id WHERE x.idea_id = i.id ORDER BY s.name)::TEXT[],
+       ARRAY['title', 'problem', 'solution', 'required_skills'], i.created_at
+FROM ideas i WHERE i.revision = 1
+ON CONFLICT DO NOTHING;
+
+-- Idea Votes and Bookmarks
+-- One upvote and one bookmark per user and idea. The counts on ideas are kept in step by
+-- triggers so feeds can sort on them without counting rows.
+CREATE TABLE IF NOT EXISTS idea_votes (
+    idea_id UUID NOT NULL REFERENCES ideas(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (idea_id, user_id)
+);
+CREATE TABLE IF NOT EXISTS idea_bookmarks (
+    idea_id UUID NOT NULL REFERENCES ideas(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (idea_id, user_id)
+);
+CREATE INDEX IF NOT EXISTS idx_idea_bookmarks_user ON idea_bookmarks(user_id, created_at DESC);
+ALTER TABLE ideas ADD COLUMN IF NOT EXISTS upvote_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE ideas ADD COLUMN IF NOT EXISTS bookmark_count INTEGER NOT NULL DEFAULT 0;
+CREATE INDEX IF NOT EXISTS idx_ideas_upvote_count ON ideas(upvote_count DESC, created_at DESC) WHERE deleted_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_ideas_created_at ON ideas(created_at DESC) WHERE deleted_at IS NULL;
+
+CREATE OR REPLACE FUNCTION idea_votes_count() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        UPDATE ideas SET upvote_count = upvote_count + 1 WHERE id = NEW.idea_id;
+    ELSE
+        UPDATE ideas SET upvote_count = upvote_count - 1 WHERE id = OLD.idea_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION idea_bookmarks_count() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        UPDATE ideas SET bookmark_count = bookmark_count + 1 WHERE id = NEW.idea_id;
+    ELSE
+        UPDATE ideas SET bookmark_count = bookmark_count - 1 WHERE id = OLD.idea_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS idea_votes_count ON idea_votes;
+CREATE TRIGGER idea_votes_count AFTER INSERT OR DELETE ON idea_votes
+    FOR EACH ROW EXECUTE FUNCTION idea_votes_count();
+DROP TRIGGER IF EXISTS idea_bookmarks_count ON idea_bookmarks;
+CREATE TRIGGER idea_bookmarks_count AFTER INSERT OR DELETE ON idea_bookmarks
+    FOR EACH ROW EXECUTE FUNCTION idea_bookmarks_count();
+
+-- Idea Comments
+-- Threaded discussion under an idea. Deleted comments keep their row so replies stay in
+-- place; reaction_count is kept in step by a trigger for the "top" ordering.
+CREATE TABLE IF NOT EXISTS idea_comments (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    idea_id UUID NOT NULL REFERENCES ideas(id) ON DELETE CASCADE,
+    parent_id UUID REFERENCES idea_comments(id) ON DELETE CASCADE,
+    author_id UUID NOT NULL REFERENCES users(id),
+    body TEXT NOT NULL,
+    depth INTEGER NOT NULL DEFAULT 0, -- 0 for a top-level comment
+    reaction_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    edited_at TIMESTAMP WITH TIME ZONE,
+    deleted_at TIMESTAMP WITH TIME ZONE
+);
+CREATE INDEX IF NOT EXISTS idx_idea_comments_idea ON idea_comments(idea_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_idea_comments_parent ON idea_comments(parent_id);
+CREATE INDEX IF NOT EXISTS idx_idea_comments_author ON idea_comments(author_id);
+
+CREATE TABLE IF NOT EXISTS idea_comment_reactions (
+    comment_id UUID NOT NULL REFERENCES idea_comments(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reaction VARCHAR(20) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (comment_id, user_id, reaction)
+);
+CREATE INDEX IF NOT EXISTS idx_idea_comment_reactions_user ON idea_comment_reactions(user_id);
+
+CREATE OR REPLACE FUNCTION idea_comment_reactions_count() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        UPDATE idea_comments SET reaction_count = reaction_count + 1 WHERE id = NEW.comment_id;
+    ELSE
+        UPDATE idea_comments SET reaction_count = reaction_count - 1 WHERE id = OLD.comment_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS idea_comment_reactions_count ON idea_comment_reactions;
+CREATE TRIGGER idea_comment_reactions_count AFTER INSERT OR DELETE ON idea_comment_reactions
+    FOR EACH ROW EXECUTE FUNCTION idea_comment_reactions_count();
+
+-- Idea Similarity
+-- A MinHash signature of each idea's text, split into bands for locality-sensitive lookup:
+-- ideas sharing any band bucket are duplicate candidates. Filled in by svc-brain-core.
+ALTER TABLE ideas ADD COLUMN IF NOT EXISTS minhash BIGINT[];
+ALTER TABLE ideas ADD COLUMN IF NOT EXISTS possible_duplicate_of UUID REFERENCES ideas(id) ON DELETE SET NULL;
+CREATE TABLE IF NOT EXISTS idea_minhash_bands (
+    idea_id UUID NOT NULL REFERENCES ideas(id) ON DELETE CASCADE,
+    band SMALLINT NOT NULL,
+    bucket BIGINT NOT NULL,
+    PRIMARY KEY (idea_id, band)
+);
+CREATE INDEX IF NOT EXISTS idx_idea_minhash_bands_bucket ON idea_minhash_bands(band, bucket);
@@ -0,0 +1,140 @@
+//! Threaded comments on ideas. Comments are stored flat with their parent and depth and put
+//! back into thread order on read. Deleting one only marks it, so its replies stay in place.
+
+use shared_proto::idea::{IdeaComment, ReactionCount};
+use sqlx::Row;
+use std::collections::HashMap;
+use tonic::Status;
+use uuid::Uuid;
+
+/// Replies nest at most this deep; a reply to a comment at the limit is refused.
+pub const MAX_DEPTH: i32 = 8;
+/// A thread is listed in one go, up to this many comments.
+pub const MAX_THREAD_COMMENTS: i64 = 1000;
+
+/// Columns for the comment `c`, where `param` holds the caller's user id.
+pub fn columns(param: &str) -> String {
+    format!(
+        "c.id, c.idea_id, c.parent_id, c.author_id, c.body, c.depth, c.created_at, c.edited_at, c.deleted_at, \
+         (SELECT COUNT(*)::INT FROM idea_comments r WHERE r.parent_id = c.id AND r.deleted_at IS NULL) AS reply_count, \
+         ARRAY(SELECT x.reaction::TEXT FROM idea_comment_reactions x WHERE x.comment_id = c.id GROUP BY x.reaction ORDER BY x.reaction) AS reaction_names, \
+         ARRAY(SELECT COUNT(*)::INT FROM idea_comment_reactions x WHERE x.comment_id = c.id GROUP BY x.reaction ORDER BY x.reaction) AS reaction_counts, \
+         ARRAY(SELECT x.reaction::TEXT FROM idea_comment_reactions x WHERE x.comment_id = c.id AND x.user_id = {} ORDER BY x.reaction) AS my_reactions",
+        param
+    )
+}
+
+/// Maps a row selected with `columns`. A deleted comment loses its author and text.
+pub fn from_row(row: &sqlx::postgres::PgRow) -> IdeaComment {
+    let deleted_at = crate::timestamp(row, "deleted_at");
+    let live = deleted_at.is_empty();
+    let names: Vec<String> = row.get("reaction_names");
+    let counts: Vec<i32> = row.get("reaction_counts");
+    IdeaComment {
+        id: row.get::<Uuid, _>("id").to_string(),
+        idea_id: row.get::<Uuid, _>("idea_id").to_string(),
+        parent_id: row.get::<Option<Uuid>, _>("parent_id").map(|u| u.to_string()).unwrap_or_default(),
+        author_id: if live { row.get::<Uuid, _>("author_id").to_string() } else { String::new() },
+        body: if live { row.get("body") } else { String::new() },
+        depth: row.get("depth"),
+        reply_count: row.get("reply_count"),
+        reactions: names.into_iter().zip(counts).map(|(reaction, count)| ReactionCount { reaction, count }).collect(),
+        my_reactions: row.get("my_reactions"),
+        created_at: crate::timestamp(row, "created_at"),
+        edited_at: crate::timestamp(row, "edited_at"),
+        deleted_at,
+    }
+}
+
+/// The ORDER BY for a `ListIdeaComments` sort mode, applied among siblings.
+pub fn order(sort: &str) -> Result<&'static str, Status> {
+    match sort {
+        "" | "oldest" => Ok("c.created_at ASC"),
+        "newest" => Ok("c.created_at DESC"),
+        "top" => Ok("c.reaction_count DESC, c.created_at ASC"),
+        _ => Err(Status::invalid_argument("sort must be oldest, newest or top")),
+    }
+}
+
+/// Puts sorted comments into thread order, each followed by its replies. Deleted comments
+/// are left out unless a live reply hangs under them.
+pub fn thread(comments: Vec<IdeaComment>) -> Vec<IdeaComment> {
+    let mut children: HashMap<String, Vec<IdeaComment>> = HashMap::new();
+    for comment in comments {
+        children.entry(comment.parent_id.clone()).or_default().push(comment);
+    }
+    let mut thread = Vec::new();
+    append(&mut children, "", &mut thread);
+    thread
+}
+
+fn append(children: &mut HashMap<String, Vec<IdeaComment>>, parent_id: &str, thread: &mut Vec<IdeaComment>) {
+    for comment in children.remove(parent_id).unwrap_or_default() {
+        let at = thread.len();
+        let id = comment.id.clone();
+        let deleted = !comment.deleted_at.is_empty();
+        thread.push(comment);
+        append(children, &id, thread);
+        if deleted && thread.len() == at + 1 {
+            thread.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(id: &str, parent_id: &str, deleted: bool) -> IdeaComment {
+        IdeaComment {
+            id: id.to_string(),
+            parent_id: parent_id.to_string(),
+            deleted_at: if deleted { "2026-01-01T00:00:00+00:00".to_string() } else { String::new() },
+            ..Default::default()
+        }
+    }
+
+    fn ids(thread: &[IdeaComment]) -> Vec<&str> {
+        thread.iter().map(|c| c.id.as_str()).collect()
+    }
+
+    #[test]
+    fn replies_follow_their_parent_in_sorted_order() {
+        let sorted = vec![
+            comment("a", "", false),
+            comment("b", "", false),
+            comment("a2", "a", false),
+            comment("a1", "a", false),
+            comment("a1x", "a1", false),
+            comment("b1", "b", false),
+        ];
+        assert_eq!(ids(&thread(sorted)), ["a", "a2", "a1", "a1x", "b", "b1"]);
+    }
+
+    #[test]
+    fn deleted_comment_without_live_replies_is_left_out() {
+        let sorted = vec![comment("a", "", true), comment("b", "", false)];
+        assert_eq!(ids(&thread(sorted)), ["b"]);
+    }
+
+    #[test]
+    fn deleted_comment_stays_to_hold_its_replies() {
+        let sorted = vec![comment("a", "", true), comment("a1", "a", false)];
+        assert_eq!(ids(&thread(sorted)), ["a", "a1"]);
+    }
+
+    #[test]
+    fn deleted_branch_with_only_deleted_replies_is_left_out() {
+        let sorted = vec![comment("a", "", true), comment("a1", "a", true), comment("b", "", false)];
+        assert_eq!(ids(&thread(sorted)), ["b"]);
+    }
+
+    #[test]
+    fn sort_modes() {
+        assert_eq!(order("").unwrap(), "c.created_at ASC");
+        assert_eq!(order("oldest").unwrap(), "c.created_at ASC");
+        assert_eq!(order("newest").unwrap(), "c.created_at DESC");
+        assert!(order("top").unwrap().starts_with("c.reaction_count DESC"));
+        assert_eq!(order("best").unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+}
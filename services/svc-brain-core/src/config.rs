@@ -6,6 +6,17 @@ use std::env;
 pub struct Config {
     pub database_url: String,
     pub server_addr: String,
+    pub reputation_half_life_days: f64,
+    pub reputation_recompute_secs: u64,
+    /// Public URL of the web app, for links handed out (e.g. invitations).
+    pub app_base_url: String,
+    /// How long deleted ideas and tasks and archived projects can be restored before they are purged.
+    pub trash_retention_days: i32,
+    pub trash_purge_secs: u64,
+    /// Estimated text similarity (0 to 1) from which a new idea is a likely duplicate.
+    pub duplicate_idea_threshold: f64,
+    /// Refuse likely duplicates unless the poster insists, instead of only flagging them.
+    pub block_duplicate_ideas: bool,
 }
 
 impl Config {
@@ -15,10 +26,46 @@ impl Config {
         // Manual fallback or use config crate if preferred, but for now simple env var
         let database_url = env::var("DATABASE_URL").map_err(|_| "DATABASE_URL must be set".to_string())?;
         let server_addr = env::var("SERVER_ADDR").unwrap_or_else(|_| "0.0.0.0:50052".to_string());
+        let reputation_half_life_days = env::var("REPUTATION_HALF_LIFE_DAYS")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v > 0.0)
+            .unwrap_or(180.0);
+        let reputation_recompute_secs = env::var("REPUTATION_RECOMPUTE_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(3600);
+        let trash_retention_days = env::var("TRASH_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse::<i32>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(30);
+        let trash_purge_secs = env::var("TRASH_PURGE_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(3600);
+        let duplicate_idea_threshold = env::var("DUPLICATE_IDEA_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v > 0.0 && *v <= 1.0)
+            .unwrap_or(0.5);
+        let block_duplicate_ideas = env::var("BLOCK_DUPLICATE_IDEAS")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let app_base_url = env::var("APP_BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
         
         Ok(Config {
             database_url,
             server_addr,
+            reputation_half_life_days,
+            reputation_recompute_secs,
+            app_base_url: app_base_url.trim_end_matches('/').to_string(),
+            trash_retention_days,
+            trash_purge_secs,
+            duplicate_idea_threshold,
+            block_duplicate_ideas,
         })
     }
 }
@@ -1,90 +1,740 @@
+// tonic::Status is large, but it is the error type every handler helper has to return.
+#![allow(clippy::result_large_err)]
+
+mod comments;
 mod config;
 mod db;
+mod matching;
+mod notifications;
+mod privacy;
+mod project_events;
+mod reputation;
+mod revisions;
+mod similarity;
+mod skills;
+mod teams;
+mod tenant;
+mod trash;
+mod verification;
+mod votes;
 
 use tonic::{transport::Server, Request, Response, Status};
 use tracing_subscriber::FmtSubscriber;
 use shared_proto::idea::idea_service_server::{IdeaService, IdeaServiceServer};
-use shared_proto::idea::{Idea, CreateIdeaRequest, GetIdeaRequest, ListIdeasRequest, ListIdeasResponse};
+use shared_proto::idea::{Idea, CreateIdeaRequest, GetIdeaRequest, ListIdeasRequest, ListIdeasResponse, DeleteIdeaRequest, DeleteIdeaResponse, RestoreIdeaRequest, UpdateIdeaRequest, ListIdeaRevisionsRequest, ListIdeaRevisionsResponse, GetIdeaRevisionRequest, IdeaRevision, RestoreIdeaRevisionRequest, SetIdeaUpvoteRequest, SetIdeaBookmarkRequest, IdeaComment, CreateIdeaCommentRequest, ListIdeaCommentsRequest, ListIdeaCommentsResponse, UpdateIdeaCommentRequest, DeleteIdeaCommentRequest, DeleteIdeaCommentResponse, SetCommentReactionRequest, FindSimilarIdeasRequest, FindSimilarIdeasResponse, Skill, SearchSkillsRequest, SearchSkillsResponse, RecommendCollaboratorsRequest, RecommendCollaboratorsResponse, CollaboratorMatch};
 use shared_proto::task::task_service_server::{TaskService, TaskServiceServer};
-use shared_proto::task::{Task, Project, CreateTaskRequest, ListTasksRequest, ListTasksResponse, CreateProjectRequest, ListProjectsRequest, ListProjectsResponse, UpdateTaskRequest, UpdateTaskResponse, UpdateProjectRequest, ListPublicProjectsRequest, CreateNotificationRequest, ListNotificationsRequest, ListNotificationsResponse, Notification, LaunchProjectRequest};
+use shared_proto::task::{Task, Project, CreateTaskRequest, ListTasksRequest, ListTasksResponse, CreateProjectRequest, ListProjectsRequest, ListProjectsResponse, UpdateTaskRequest, UpdateTaskResponse, UpdateProjectRequest, ListPublicProjectsRequest, CreateNotificationRequest, ListNotificationsRequest, ListNotificationsResponse, Notification, LaunchProjectRequest, ArchiveProjectRequest, RestoreProjectRequest, DeleteTaskRequest, DeleteTaskResponse, RestoreTaskRequest, TaskStatus, TaskPriority};
+use shared_proto::reputation::reputation_service_server::{ReputationService, ReputationServiceServer};
+use shared_proto::reputation::{EndorseUserRequest, EndorseUserResponse, RateProjectRequest, RateProjectResponse, GetReputationBreakdownRequest, ReputationBreakdown, ReputationComponent, ReputationEvent};
+use shared_proto::team::team_service_server::{TeamService, TeamServiceServer};
+use shared_proto::team::{Team, Member, Invitation, GetTeamRequest, UpdateMemberRoleRequest, RemoveMemberRequest, RemoveMemberResponse, InviteMemberRequest, InviteMemberResponse, ListInvitationsRequest, ListInvitationsResponse, RevokeInvitationRequest, RevokeInvitationResponse, ListMyInvitationsRequest, GetInvitationRequest, RespondToInvitationRequest, DeclineInvitationResponse, OwnershipTransfer, TransferProjectOwnershipRequest, RespondToOwnershipTransferRequest, ListMyOwnershipTransfersRequest, ListOwnershipTransfersResponse};
+use shared_proto::privacy::privacy_service_server::{PrivacyService, PrivacyServiceServer};
+use shared_proto::tokens;
+use shared_proto::validate::{Validate, WireEnum};
+use shared_proto::privacy::{ExportUserContentRequest, ExportUserContentResponse, ExportFile, EraseUserContentRequest, EraseUserContentResponse};
 use sqlx::{PgPool, Row};
-use sqlx::postgres::PgPoolOptions;
+use std::time::Duration;
+use tenant::Tenant;
 use uuid::Uuid;
 
 #[derive(Debug)]
 pub struct MyIdeaService {
     pool: PgPool,
+    trash: trash::Trash,
+    duplicates: similarity::Duplicates,
 }
 
 #[tonic::async_trait]
 impl IdeaService for MyIdeaService {
     async fn create_idea(&self, request: Request<CreateIdeaRequest>) -> Result<Response<Idea>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
         let req = request.into_inner();
         let idea_id = Uuid::new_v4();
-        let creator_id = Uuid::parse_str(&req.creator_id).unwrap_or(Uuid::nil());
+        let creator_id = if req.creator_id.is_empty() {
+            tenant.require_user()?
+        } else {
+            Uuid::parse_str(&req.creator_id).map_err(|_| Status::invalid_argument("Invalid Creator UUID"))?
+        };
+        tenant.require_self(creator_id)?;
+        verification::require_verified_email(&self.pool, creator_id).await?;
+        let required_skills = skills::validate(&req.required_skills)?;
+
+        let similar = self
+            .duplicates
+            .find(&similarity::text(&req.title, &req.problem, &req.solution), tenant.org_id, None, similarity::DEFAULT_LIMIT)
+            .await?;
+        if let Some(closest) = similar.first().filter(|_| self.duplicates.block && !req.allow_duplicate) {
+            return Err(Status::already_exists(format!(
+                "This looks like a duplicate of \"{}\" ({}); post it anyway to go ahead",
+                closest.title, closest.id
+            )));
+        }
+        let possible_duplicate_of = similar.first().and_then(|closest| Uuid::parse_str(&closest.id).ok());
+
+        let mut tx = self.pool.begin().await.map_err(|e| Status::internal(format!("DB: {}", e)))?;
 
-        sqlx::query("INSERT INTO ideas (id, creator_id, title, problem, solution, status) VALUES ($1, $2, $3, $4, $5, $6)")
+        sqlx::query("INSERT INTO ideas (id, creator_id, title, problem, solution, status, org_id, possible_duplicate_of) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)")
             .bind(idea_id)
             .bind(creator_id)
             .bind(&req.title)
             .bind(&req.problem)
             .bind(&req.solution)
             .bind("open")
-            .execute(&self.pool)
+            .bind(tenant.org_id)
+            .bind(possible_duplicate_of)
+            .execute(&mut *tx)
             .await
             .map_err(|e| Status::internal(format!("DB: {}", e)))?;
 
-        Ok(Response::new(Idea {
-            id: idea_id.to_string(),
-            title: req.title,
-            problem: req.problem,
-            solution: req.solution,
-            creator_id: creator_id.to_string(),
-            status: 1,
-        }))
+        skills::set_idea_skills(&mut tx, idea_id, &required_skills).await?;
+        revisions::record(&mut tx, idea_id, creator_id, None).await?;
+        similarity::index(&mut tx, idea_id).await?;
+
+        tx.commit().await.map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+        let mut idea = self.load_idea(idea_id, &tenant).await?;
+        idea.similar_ideas = similar;
+        Ok(Response::new(idea))
     }
 
     async fn get_idea(&self, request: Request<GetIdeaRequest>) -> Result<Response<Idea>, Status> {
+        request.get_ref().validate()?;
+       let tenant = Tenant::from_request(&self.pool, &request).await?;
        let req = request.into_inner();
        let idea_uuid = Uuid::parse_str(&req.id).map_err(|_| Status::invalid_argument("Invalid UUID"))?;
-       let row = sqlx::query("SELECT id, creator_id, title, problem, solution, status FROM ideas WHERE id = $1")
-            .bind(idea_uuid)
+       self.load_idea(idea_uuid, &tenant).await.map(Response::new)
+    }
+
+    /// The personal space's ideas form the public feed; an organization's own ideas stay inside it.
+    async fn list_ideas(&self, request: Request<ListIdeasRequest>) -> Result<Response<ListIdeasResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let req = request.get_ref();
+        let sort = if req.deleted || req.bookmarked { votes::Sort::New } else { votes::Sort::from_name(&req.sort)? };
+        let page_size = if req.page_size == 0 { DEFAULT_IDEAS_PAGE_SIZE } else { req.page_size.into() };
+        let filter = if req.deleted {
+            tenant.require_user()?;
+            "i.creator_id = $2 AND i.deleted_at > $3 ORDER BY i.deleted_at DESC".to_string()
+        } else if req.bookmarked {
+            tenant.require_user()?;
+            "i.deleted_at IS NULL AND EXISTS (SELECT 1 FROM idea_bookmarks b WHERE b.idea_id = i.id AND b.user_id = $2) \
+             ORDER BY (SELECT b.created_at FROM idea_bookmarks b WHERE b.idea_id = i.id AND b.user_id = $2) DESC".to_string()
+        } else {
+            sort.feed()
+        };
+        let rows = sqlx::query(&format!(
+            "SELECT {}, i.created_at, {}, {} FROM ideas i WHERE i.org_id IS NOT DISTINCT FROM $1 AND {} LIMIT $4",
+            IDEA_COLUMNS, skills::IDEA_SKILLS_COLUMN, votes::viewer_columns("$2"), filter
+        ))
+            .bind(tenant.org_id)
+            .bind(tenant.user_id)
+            .bind(self.trash.cutoff())
+            .bind(if sort == votes::Sort::Trending { votes::MAX_TRENDING_CANDIDATES } else { page_size })
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+        let ideas = if sort == votes::Sort::Trending {
+            let candidates = rows.iter().map(|row| (idea_from_row(row), row.get("created_at"))).collect();
+            votes::rank_trending(candidates, chrono::Utc::now()).into_iter().take(page_size as usize).collect()
+        } else {
+            rows.iter().map(idea_from_row).collect()
+        };
+
+        Ok(Response::new(ListIdeasResponse { ideas, next_page_token: "".into() }))
+    }
+
+    async fn delete_idea(&self, request: Request<DeleteIdeaRequest>) -> Result<Response<DeleteIdeaResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let idea_id = Uuid::parse_str(&request.get_ref().id).map_err(|_| Status::invalid_argument("Invalid Idea UUID"))?;
+
+        let creator_id: Uuid = sqlx::query("SELECT creator_id FROM ideas WHERE id = $1 AND org_id IS NOT DISTINCT FROM $2 AND deleted_at IS NULL")
+            .bind(idea_id)
+            .bind(tenant.org_id)
             .fetch_optional(&self.pool)
             .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?
+            .ok_or_else(|| Status::not_found("Idea not found"))?
+            .get("creator_id");
+        if creator_id != caller {
+            return Err(Status::permission_denied("Only the idea's creator can delete it"));
+        }
+
+        sqlx::query("UPDATE ideas SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
+            .bind(idea_id)
+            .execute(&self.pool)
+            .await
             .map_err(|e| Status::internal(format!("DB: {}", e)))?;
-        
-        if let Some(row) = row {
-             Ok(Response::new(Idea {
-                id: row.get::<Uuid, _>("id").to_string(),
-                title: row.get("title"),
-                problem: row.get("problem"),
-                solution: row.get("solution"),
-                creator_id: row.get::<Uuid, _>("creator_id").to_string(),
-                status: 1,
-            }))
+
+        Ok(Response::new(DeleteIdeaResponse {}))
+    }
+
+    async fn restore_idea(&self, request: Request<RestoreIdeaRequest>) -> Result<Response<Idea>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let idea_id = Uuid::parse_str(&request.get_ref().id).map_err(|_| Status::invalid_argument("Invalid Idea UUID"))?;
+
+        let restored = sqlx::query(
+            "UPDATE ideas SET deleted_at = NULL WHERE id = $1 AND creator_id = $2 AND org_id IS NOT DISTINCT FROM $3 \
+             AND deleted_at > $4",
+        )
+        .bind(idea_id)
+        .bind(caller)
+        .bind(tenant.org_id)
+        .bind(self.trash.cutoff())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .rows_affected();
+        if restored == 0 {
+            return Err(Status::not_found("Idea not found or past the restore window"));
+        }
+
+        self.load_idea(idea_id, &tenant).await.map(Response::new)
+    }
+
+    async fn update_idea(&self, request: Request<UpdateIdeaRequest>) -> Result<Response<Idea>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let req = request.into_inner();
+        let idea_id = Uuid::parse_str(&req.id).map_err(|_| Status::invalid_argument("Invalid Idea UUID"))?;
+        let title = req.title.as_deref().map(str::trim);
+        let required_skills = req.required_skills.as_ref().map(|list| skills::validate(&list.names)).transpose()?;
+
+        let db = |e: sqlx::Error| Status::internal(format!("DB: {}", e));
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        let caller = self.lock_own_idea(&mut tx, &tenant, idea_id).await?;
+        for (column, value) in [("title", title), ("problem", req.problem.as_deref()), ("solution", req.solution.as_deref())] {
+            if let Some(value) = value {
+                sqlx::query(&format!("UPDATE ideas SET {} = $2 WHERE id = $1", column))
+                    .bind(idea_id)
+                    .bind(value)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(db)?;
+            }
+        }
+        if let Some(required_skills) = required_skills {
+            skills::set_idea_skills(&mut tx, idea_id, &required_skills).await?;
+        }
+        revisions::record(&mut tx, idea_id, caller, None).await?;
+        similarity::index(&mut tx, idea_id).await?;
+        tx.commit().await.map_err(db)?;
+
+        self.load_idea(idea_id, &tenant).await.map(Response::new)
+    }
+
+    async fn list_idea_revisions(&self, request: Request<ListIdeaRevisionsRequest>) -> Result<Response<ListIdeaRevisionsResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let idea_id = Uuid::parse_str(&request.get_ref().idea_id).map_err(|_| Status::invalid_argument("Invalid Idea UUID"))?;
+        self.load_idea(idea_id, &tenant).await?;
+
+        let revisions = sqlx::query(&format!(
+            "SELECT {} FROM idea_revisions r WHERE r.idea_id = $1 ORDER BY r.revision DESC",
+            revisions::SUMMARY_COLUMNS
+        ))
+        .bind(idea_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .iter()
+        .map(revisions::summary_from_row)
+        .collect();
+
+        Ok(Response::new(ListIdeaRevisionsResponse { revisions }))
+    }
+
+    async fn get_idea_revision(&self, request: Request<GetIdeaRevisionRequest>) -> Result<Response<IdeaRevision>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let req = request.into_inner();
+        let idea_id = Uuid::parse_str(&req.idea_id).map_err(|_| Status::invalid_argument("Invalid Idea UUID"))?;
+        self.load_idea(idea_id, &tenant).await?;
+
+        let mut conn = self.pool.acquire().await.map_err(|e| Status::internal(format!("DB: {}", e)))?;
+        revisions::load(&mut conn, idea_id, req.revision).await.map(Response::new)
+    }
+
+    async fn restore_idea_revision(&self, request: Request<RestoreIdeaRevisionRequest>) -> Result<Response<Idea>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let req = request.into_inner();
+        let idea_id = Uuid::parse_str(&req.idea_id).map_err(|_| Status::invalid_argument("Invalid Idea UUID"))?;
+
+        let db = |e: sqlx::Error| Status::internal(format!("DB: {}", e));
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        let caller = self.lock_own_idea(&mut tx, &tenant, idea_id).await?;
+        let old = revisions::load(&mut tx, idea_id, req.revision).await?;
+        sqlx::query("UPDATE ideas SET title = $2, problem = $3, solution = $4 WHERE id = $1")
+            .bind(idea_id)
+            .bind(&old.title)
+            .bind(&old.problem)
+            .bind(&old.solution)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        skills::set_idea_skills(&mut tx, idea_id, &old.required_skills).await?;
+        if revisions::record(&mut tx, idea_id, caller, Some(req.revision)).await?.is_none() {
+            return Err(Status::failed_precondition("That revision matches the current version"));
+        }
+        similarity::index(&mut tx, idea_id).await?;
+        tx.commit().await.map_err(db)?;
+
+        self.load_idea(idea_id, &tenant).await.map(Response::new)
+    }
+
+    async fn set_idea_upvote(&self, request: Request<SetIdeaUpvoteRequest>) -> Result<Response<Idea>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let req = request.into_inner();
+        let idea_id = Uuid::parse_str(&req.id).map_err(|_| Status::invalid_argument("Invalid Idea UUID"))?;
+        let idea = self.load_idea(idea_id, &tenant).await?;
+        if req.upvoted {
+            if idea.creator_id == caller.to_string() {
+                return Err(Status::failed_precondition("You can't upvote your own idea"));
+            }
+            verification::require_verified_email(&self.pool, caller).await?;
+        }
+
+        votes::set(&self.pool, votes::VOTES, idea_id, caller, req.upvoted).await?;
+        self.load_idea(idea_id, &tenant).await.map(Response::new)
+    }
+
+    async fn set_idea_bookmark(&self, request: Request<SetIdeaBookmarkRequest>) -> Result<Response<Idea>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let req = request.into_inner();
+        let idea_id = Uuid::parse_str(&req.id).map_err(|_| Status::invalid_argument("Invalid Idea UUID"))?;
+        self.load_idea(idea_id, &tenant).await?;
+
+        votes::set(&self.pool, votes::BOOKMARKS, idea_id, caller, req.bookmarked).await?;
+        self.load_idea(idea_id, &tenant).await.map(Response::new)
+    }
+
+    async fn create_idea_comment(&self, request: Request<CreateIdeaCommentRequest>) -> Result<Response<IdeaComment>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let req = request.into_inner();
+        let idea_id = Uuid::parse_str(&req.idea_id).map_err(|_| Status::invalid_argument("Invalid Idea UUID"))?;
+        let body = req.body.trim();
+        let idea = self.load_idea(idea_id, &tenant).await?;
+        verification::require_verified_email(&self.pool, caller).await?;
+
+        let parent = if req.parent_id.is_empty() {
+            None
         } else {
-             Err(Status::not_found("Idea not found"))
+            let parent_id = Uuid::parse_str(&req.parent_id).map_err(|_| Status::invalid_argument("Invalid Comment UUID"))?;
+            let (parent, _) = self.load_comment(parent_id, &tenant).await?;
+            if parent.idea_id != idea.id {
+                return Err(Status::invalid_argument("That comment is on another idea"));
+            }
+            if parent.depth >= comments::MAX_DEPTH {
+                return Err(Status::failed_precondition("Replies can't nest any deeper here"));
+            }
+            Some((parent_id, parent))
+        };
+
+        let db = |e: sqlx::Error| Status::internal(format!("DB: {}", e));
+        let id = Uuid::new_v4();
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        sqlx::query("INSERT INTO idea_comments (id, idea_id, parent_id, author_id, body, depth) VALUES ($1, $2, $3, $4, $5, $6)")
+            .bind(id)
+            .bind(idea_id)
+            .bind(parent.as_ref().map(|(parent_id, _)| *parent_id))
+            .bind(caller)
+            .bind(body)
+            .bind(parent.as_ref().map(|(_, p)| p.depth + 1).unwrap_or(0))
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+
+        let payload = serde_json::json!({ "idea_id": idea_id, "comment_id": id });
+        let creator_id = Uuid::parse_str(&idea.creator_id).ok();
+        let parent_author = parent.as_ref().and_then(|(_, p)| Uuid::parse_str(&p.author_id).ok());
+        if let Some(author_id) = parent_author.filter(|a| *a != caller) {
+            notifications::notify(
+                &mut tx,
+                author_id,
+                notifications::COMMENT_REPLY,
+                &format!("New reply to your comment on {}", idea.title),
+                payload.clone(),
+            )
+            .await
+            .map_err(db)?;
+        }
+        // The creator hears about every comment, once, unless they wrote it.
+        if let Some(creator_id) = creator_id.filter(|c| *c != caller && Some(*c) != parent_author) {
+            notifications::notify(
+                &mut tx,
+                creator_id,
+                notifications::IDEA_COMMENT,
+                &format!("New comment on your idea {}", idea.title),
+                payload,
+            )
+            .await
+            .map_err(db)?;
         }
+        tx.commit().await.map_err(db)?;
+
+        self.load_comment(id, &tenant).await.map(|(comment, _)| Response::new(comment))
     }
 
-    async fn list_ideas(&self, _request: Request<ListIdeasRequest>) -> Result<Response<ListIdeasResponse>, Status> {
-        let rows = sqlx::query("SELECT id, creator_id, title, problem, solution, status FROM ideas ORDER BY created_at DESC LIMIT 20")
-            .fetch_all(&self.pool)
+    async fn list_idea_comments(&self, request: Request<ListIdeaCommentsRequest>) -> Result<Response<ListIdeaCommentsResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let req = request.get_ref();
+        let idea_id = Uuid::parse_str(&req.idea_id).map_err(|_| Status::invalid_argument("Invalid Idea UUID"))?;
+        let order = comments::order(&req.sort)?;
+        self.load_idea(idea_id, &tenant).await?;
+
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM idea_comments c WHERE c.idea_id = $1 ORDER BY {} LIMIT $3",
+            comments::columns("$2"),
+            order
+        ))
+        .bind(idea_id)
+        .bind(tenant.user_id)
+        .bind(comments::MAX_THREAD_COMMENTS)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+        let comments = comments::thread(rows.iter().map(comments::from_row).collect());
+        Ok(Response::new(ListIdeaCommentsResponse { comments }))
+    }
+
+    async fn update_idea_comment(&self, request: Request<UpdateIdeaCommentRequest>) -> Result<Response<IdeaComment>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let req = request.into_inner();
+        let id = Uuid::parse_str(&req.id).map_err(|_| Status::invalid_argument("Invalid Comment UUID"))?;
+        let body = req.body.trim();
+        let (comment, _) = self.load_comment(id, &tenant).await?;
+        if comment.author_id != caller.to_string() {
+            return Err(Status::permission_denied("Only the comment's author can edit it"));
+        }
+
+        sqlx::query("UPDATE idea_comments SET body = $2, edited_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .bind(body)
+            .execute(&self.pool)
             .await
             .map_err(|e| Status::internal(format!("DB: {}", e)))?;
 
-        let ideas = rows.into_iter().map(|row| {
-            Idea {
-                id: row.get::<Uuid, _>("id").to_string(),
-                title: row.get("title"),
-                problem: row.get("problem"),
-                solution: row.get("solution"),
-                creator_id: row.get::<Uuid, _>("creator_id").to_string(),
-                status: 1,
+        self.load_comment(id, &tenant).await.map(|(comment, _)| Response::new(comment))
+    }
+
+    async fn delete_idea_comment(&self, request: Request<DeleteIdeaCommentRequest>) -> Result<Response<DeleteIdeaCommentResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let id = Uuid::parse_str(&request.get_ref().id).map_err(|_| Status::invalid_argument("Invalid Comment UUID"))?;
+        let (comment, creator_id) = self.load_comment(id, &tenant).await?;
+        if comment.author_id != caller.to_string() && creator_id != caller {
+            return Err(Status::permission_denied("Only the comment's author or the idea's creator can delete it"));
+        }
+
+        sqlx::query("UPDATE idea_comments SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+        Ok(Response::new(DeleteIdeaCommentResponse {}))
+    }
+
+    async fn set_comment_reaction(&self, request: Request<SetCommentReactionRequest>) -> Result<Response<IdeaComment>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let req = request.into_inner();
+        let id = Uuid::parse_str(&req.comment_id).map_err(|_| Status::invalid_argument("Invalid Comment UUID"))?;
+        self.load_comment(id, &tenant).await?;
+
+        let query = if req.reacted {
+            "INSERT INTO idea_comment_reactions (comment_id, user_id, reaction) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING"
+        } else {
+            "DELETE FROM idea_comment_reactions WHERE comment_id = $1 AND user_id = $2 AND reaction = $3"
+        };
+        sqlx::query(query)
+            .bind(id)
+            .bind(caller)
+            .bind(&req.reaction)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+        self.load_comment(id, &tenant).await.map(|(comment, _)| Response::new(comment))
+    }
+
+    async fn find_similar_ideas(&self, request: Request<FindSimilarIdeasRequest>) -> Result<Response<FindSimilarIdeasResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let req = request.get_ref();
+        let idea_id = Uuid::parse_str(&req.idea_id).map_err(|_| Status::invalid_argument("Invalid Idea UUID"))?;
+        let limit = if req.limit <= 0 { similarity::DEFAULT_LIMIT } else { (req.limit as usize).min(similarity::MAX_LIMIT) };
+        let idea = self.load_idea(idea_id, &tenant).await?;
+
+        let ideas = self
+            .duplicates
+            .find(&similarity::text(&idea.title, &idea.problem, &idea.solution), tenant.org_id, Some(idea_id), limit)
+            .await?;
+        Ok(Response::new(FindSimilarIdeasResponse { ideas }))
+    }
+
+    async fn search_skills(&self, request: Request<SearchSkillsRequest>) -> Result<Response<SearchSkillsResponse>, Status> {
+        request.get_ref().validate()?;
+        let req = request.into_inner();
+        let limit = if req.limit <= 0 { 20 } else { req.limit.min(50) };
+
+        // Prefix match on the slug or any alias, most used first.
+        let rows = sqlx::query(
+            "SELECT s.id, s.name, (SELECT COUNT(*) FROM user_skills us WHERE us.skill_id = s.id) AS user_count \
+             FROM skills s \
+             WHERE $1 = '' OR s.slug LIKE skill_slug($1) || '%' \
+                OR EXISTS (SELECT 1 FROM skill_aliases a WHERE a.skill_id = s.id AND a.alias_slug LIKE skill_slug($1) || '%') \
+             ORDER BY user_count DESC, s.name \
+             LIMIT $2",
+        )
+        .bind(escape_like(req.query.trim()))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+        let skills = rows.into_iter().map(|row| Skill {
+            id: row.get::<Uuid, _>("id").to_string(),
+            name: row.get("name"),
+            user_count: row.get::<i64, _>("user_count") as i32,
+        }).collect();
+
+        Ok(Response::new(SearchSkillsResponse { skills }))
+    }
+
+    async fn recommend_collaborators(&self, request: Request<RecommendCollaboratorsRequest>) -> Result<Response<RecommendCollaboratorsResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let req = request.into_inner();
+        let idea_id = Uuid::parse_str(&req.idea_id).map_err(|_| Status::invalid_argument("Invalid Idea UUID"))?;
+        let limit = if req.limit <= 0 { 10 } else { req.limit.min(50) } as usize;
+
+        let idea = sqlx::query(&format!(
+            "SELECT i.creator_id, i.org_id, (SELECT COUNT(*) FROM idea_skills WHERE idea_id = i.id) AS required FROM ideas i WHERE i.id = $1 AND {}",
+            IDEA_VISIBLE
+        ))
+            .bind(idea_id)
+            .bind(tenant.org_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?
+            .ok_or_else(|| Status::not_found("Idea not found"))?;
+        let creator_id: Uuid = idea.get("creator_id");
+        let org_id: Option<Uuid> = idea.get("org_id");
+        let required = idea.get::<i64, _>("required") as usize;
+
+        // Anyone open to collaborating with at least one of the required skills, except the creator;
+        // for an organization's idea, only its members.
+        let rows = sqlx::query(
+            "SELECT u.id, COALESCE(u.full_name, '') AS full_name, COALESCE(u.avatar_url, '') AS avatar_url, \
+             COALESCE(u.reputation_score, 0) AS reputation_score, u.hours_per_week, \
+             ARRAY_AGG(s.name ORDER BY s.name)::TEXT[] AS matched_skills, \
+             (SELECT COUNT(*) FROM tasks t JOIN projects p ON p.id = t.project_id \
+              WHERE t.assignee_id = u.id AND t.status <> 'done' AND t.deleted_at IS NULL AND p.deleted_at IS NULL) AS open_tasks \
+             FROM idea_skills i \
+             JOIN user_skills us ON us.skill_id = i.skill_id \
+             JOIN skills s ON s.id = i.skill_id \
+             JOIN users u ON u.id = us.user_id \
+             WHERE i.idea_id = $1 AND u.id <> $2 AND u.open_to_collaborate AND u.deleted_at IS NULL \
+             AND ($3::UUID IS NULL OR EXISTS (SELECT 1 FROM organization_members m WHERE m.org_id = $3 AND m.user_id = u.id)) \
+             GROUP BY u.id",
+        )
+        .bind(idea_id)
+        .bind(creator_id)
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+        let mut matches: Vec<CollaboratorMatch> = rows.into_iter().map(|row| {
+            let matched_skills: Vec<String> = row.get("matched_skills");
+            let hours_per_week: Option<i32> = row.get("hours_per_week");
+            let candidate = matching::Candidate {
+                matched_skills: matched_skills.len(),
+                reputation: row.get("reputation_score"),
+                hours_per_week,
+                open_tasks: row.get("open_tasks"),
+            };
+            CollaboratorMatch {
+                user_id: row.get::<Uuid, _>("id").to_string(),
+                full_name: row.get("full_name"),
+                avatar_url: row.get("avatar_url"),
+                score: matching::score(&candidate, required),
+                matched_skills,
+                reputation_score: candidate.reputation,
+                hours_per_week: hours_per_week.unwrap_or(0),
+                open_tasks: candidate.open_tasks as i32,
             }
         }).collect();
 
-        Ok(Response::new(ListIdeasResponse { ideas, next_page_token: "".into() }))
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.user_id.cmp(&b.user_id)));
+        matches.truncate(limit);
+
+        Ok(Response::new(RecommendCollaboratorsResponse { matches }))
+    }
+}
+
+/// Public ideas `i`, plus the current organization's own, unless deleted; `$2` is the tenant's org id.
+const IDEA_VISIBLE: &str = "(i.org_id IS NULL OR i.org_id = $2) AND i.deleted_at IS NULL";
+
+/// `ListIdeasRequest.page_size` when left at 0.
+const DEFAULT_IDEAS_PAGE_SIZE: i64 = 20;
+
+const IDEA_COLUMNS: &str = "i.id, i.creator_id, i.title, i.problem, i.solution, i.status, i.deleted_at, i.revision, i.upvote_count, i.bookmark_count, i.possible_duplicate_of";
+
+/// Maps a row selected with `IDEA_COLUMNS`, `skills::IDEA_SKILLS_COLUMN` and `votes::viewer_columns`.
+fn idea_from_row(row: &sqlx::postgres::PgRow) -> Idea {
+    Idea {
+        id: row.get::<Uuid, _>("id").to_string(),
+        title: row.get("title"),
+        problem: row.get("problem"),
+        solution: row.get("solution"),
+        creator_id: row.get::<Uuid, _>("creator_id").to_string(),
+        status: 1,
+        required_skills: row.get("required_skills"),
+        deleted_at: timestamp(row, "deleted_at"),
+        revision: row.get("revision"),
+        upvote_count: row.get("upvote_count"),
+        bookmark_count: row.get("bookmark_count"),
+        upvoted: row.get("upvoted"),
+        bookmarked: row.get("bookmarked"),
+        similar_ideas: Vec::new(),
+        possible_duplicate_of: row.get::<Option<Uuid>, _>("possible_duplicate_of").map(|u| u.to_string()).unwrap_or_default(),
+    }
+}
+
+/// An optional timestamp column as RFC 3339, empty when NULL.
+fn timestamp(row: &sqlx::postgres::PgRow, column: &str) -> String {
+    row.get::<Option<chrono::DateTime<chrono::Utc>>, _>(column).map(|t| t.to_rfc3339()).unwrap_or_default()
+}
+
+impl MyIdeaService {
+    /// Locks a live idea in the caller's workspace for an edit, if the caller created it.
+    async fn lock_own_idea(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        tenant: &Tenant,
+        idea_id: Uuid,
+    ) -> Result<Uuid, Status> {
+        let caller = tenant.require_user()?;
+        let creator_id: Uuid = sqlx::query("SELECT creator_id FROM ideas WHERE id = $1 AND org_id IS NOT DISTINCT FROM $2 AND deleted_at IS NULL FOR UPDATE")
+            .bind(idea_id)
+            .bind(tenant.org_id)
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?
+            .ok_or_else(|| Status::not_found("Idea not found"))?
+            .get("creator_id");
+        if creator_id != caller {
+            return Err(Status::permission_denied("Only the idea's creator can edit it"));
+        }
+        Ok(caller)
+    }
+
+    async fn load_idea(&self, idea_id: Uuid, tenant: &Tenant) -> Result<Idea, Status> {
+        let row = sqlx::query(&format!(
+            "SELECT {}, {}, {} FROM ideas i WHERE i.id = $1 AND {}",
+            IDEA_COLUMNS, skills::IDEA_SKILLS_COLUMN, votes::viewer_columns("$3"), IDEA_VISIBLE
+        ))
+            .bind(idea_id)
+            .bind(tenant.org_id)
+            .bind(tenant.user_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?
+            .ok_or_else(|| Status::not_found("Idea not found"))?;
+
+        Ok(idea_from_row(&row))
+    }
+
+    /// A live comment on an idea the caller can see, with the idea's creator.
+    async fn load_comment(&self, comment_id: Uuid, tenant: &Tenant) -> Result<(IdeaComment, Uuid), Status> {
+        let row = sqlx::query(&format!(
+            "SELECT {}, i.creator_id FROM idea_comments c JOIN ideas i ON i.id = c.idea_id \
+             WHERE c.id = $1 AND c.deleted_at IS NULL AND {}",
+            comments::columns("$3"),
+            IDEA_VISIBLE
+        ))
+        .bind(comment_id)
+        .bind(tenant.org_id)
+        .bind(tenant.user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .ok_or_else(|| Status::not_found("Comment not found"))?;
+
+        Ok((comments::from_row(&row), row.get("creator_id")))
+    }
+}
+
+/// Escapes `%`, `_` and `\\` so user input matches literally in a LIKE pattern.
+fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+const PROJECT_COLUMNS: &str = "p.id, p.owner_id, p.org_id, p.name, p.description, p.status, p.funding_goal, p.equity_offered, p.is_public, p.industry, p.created_at, p.deleted_at";
+
+/// Maps a row selected with `PROJECT_COLUMNS` and `teams::member_role_column`, as seen by the tenant.
+fn project_from_row(row: &sqlx::postgres::PgRow, tenant: &Tenant) -> Project {
+    let owner_id: Uuid = row.get("owner_id");
+    Project {
+        id: row.get::<Uuid, _>("id").to_string(),
+        owner_id: owner_id.to_string(),
+        name: row.get("name"),
+        description: row.get::<Option<String>, _>("description").unwrap_or_default(),
+        status: row.get("status"),
+        funding_goal: row.get("funding_goal"),
+        equity_offered: row.get("equity_offered"),
+        is_public: row.get("is_public"),
+        industry: row.get::<Option<String>, _>("industry").unwrap_or_default(),
+        role: teams::resolve_role(tenant, owner_id, row.get("org_id"), row.get("member_role")).unwrap_or_default(),
+        archived_at: timestamp(row, "deleted_at"),
+    }
+}
+
+/// A project as seen by the tenant, archived or not.
+async fn load_project(pool: &PgPool, id: Uuid, tenant: &Tenant) -> Result<Project, Status> {
+    sqlx::query(&format!("SELECT {}, {} FROM projects p WHERE p.id = $1", PROJECT_COLUMNS, teams::member_role_column(2)))
+        .bind(id)
+        .bind(tenant.user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .map(|row| project_from_row(&row, tenant))
+        .ok_or_else(|| Status::not_found("Project not found"))
+}
+
+const TASK_COLUMNS: &str = "id, project_id, title, description, status, priority, assignee_id, position, deleted_at";
+
+fn task_from_row(row: &sqlx::postgres::PgRow) -> Task {
+    Task {
+        id: row.get::<Uuid, _>("id").to_string(),
+        project_id: row.get::<Uuid, _>("project_id").to_string(),
+        title: row.get("title"),
+        description: row.get::<Option<String>, _>("description").unwrap_or_default(),
+        status: row.get("status"),
+        priority: row.get("priority"),
+        assignee_id: row.get::<Option<Uuid>, _>("assignee_id").map(|u| u.to_string()).unwrap_or_default(),
+        position: row.get("position"),
+        deleted_at: timestamp(row, "deleted_at"),
     }
 }
 
@@ -92,28 +742,35 @@ impl IdeaService for MyIdeaService {
 #[derive(Debug)]
 pub struct MyTaskService {
     pool: PgPool,
+    reputation: reputation::Engine,
+    trash: trash::Trash,
 }
 
 #[tonic::async_trait]
 impl TaskService for MyTaskService {
     async fn create_project(&self, request: Request<CreateProjectRequest>) -> Result<Response<Project>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
         let req = request.into_inner();
         let id = Uuid::new_v4();
-        let owner_id = Uuid::parse_str(&req.owner_id).map_err(|_| Status::invalid_argument("Invalid Owner UUID"))?;
+        let owner_id = if req.owner_id.is_empty() { caller } else { Uuid::parse_str(&req.owner_id).map_err(|_| Status::invalid_argument("Invalid Owner UUID"))? };
+        tenant.require_self(owner_id)?;
 
-        sqlx::query("INSERT INTO projects (id, owner_id, name, description, status) VALUES ($1, $2, $3, $4, $5)")
+        sqlx::query("INSERT INTO projects (id, owner_id, name, description, status, org_id) VALUES ($1, $2, $3, $4, $5, $6)")
             .bind(id)
             .bind(owner_id)
             .bind(&req.name)
             .bind(&req.description)
             .bind("active")
+            .bind(tenant.org_id)
             .execute(&self.pool)
             .await
             .map_err(|e| Status::internal(format!("DB: {}", e)))?;
 
         Ok(Response::new(Project {
             id: id.to_string(),
-            owner_id: req.owner_id,
+            owner_id: owner_id.to_string(),
             name: req.name,
             description: req.description,
             status: "active".into(),
@@ -121,47 +778,73 @@ impl TaskService for MyTaskService {
             equity_offered: 0.0,
             is_public: false,
             industry: "".into(),
+            role: teams::ROLE_OWNER.into(),
+            archived_at: String::new(),
         }))
     }
 
+    /// Projects in the caller's current workspace that they own or are a member of.
     async fn list_projects(&self, request: Request<ListProjectsRequest>) -> Result<Response<ListProjectsResponse>, Status> {
+        request.get_ref().validate()?;
+         let tenant = Tenant::from_request(&self.pool, &request).await?;
+         let caller = tenant.require_user()?;
          let req = request.into_inner();
-         let owner_id = Uuid::parse_str(&req.owner_id).map_err(|_| Status::invalid_argument("Invalid Owner UUID"))?;
+         let owner_id = if req.owner_id.is_empty() {
+             None
+         } else {
+             Some(Uuid::parse_str(&req.owner_id).map_err(|_| Status::invalid_argument("Invalid Owner UUID"))?)
+         };
          
-         let rows = sqlx::query("SELECT id, owner_id, name, description, status, funding_goal, equity_offered, is_public, industry FROM projects WHERE owner_id = $1 ORDER BY created_at DESC")
+         let archived = if req.archived {
+             "p.owner_id = $3 AND p.deleted_at > $4"
+         } else {
+             "p.deleted_at IS NULL"
+         };
+         let rows = sqlx::query(&format!(
+             "SELECT * FROM (SELECT {}, {} FROM projects p WHERE ($1::UUID IS NULL OR p.owner_id = $1) AND p.org_id IS NOT DISTINCT FROM $2 AND {}) visible \
+              WHERE owner_id = $3 OR member_role IS NOT NULL ORDER BY created_at DESC",
+             PROJECT_COLUMNS, teams::member_role_column(3), archived
+         ))
             .bind(owner_id)
+            .bind(tenant.org_id)
+            .bind(caller)
+            .bind(self.trash.cutoff())
             .fetch_all(&self.pool)
             .await
             .map_err(|e| Status::internal(format!("DB: {}", e)))?;
             
-         let projects = rows.into_iter().map(|row| Project {
-            id: row.get::<Uuid, _>("id").to_string(),
-            owner_id: row.get::<Uuid, _>("owner_id").to_string(),
-            name: row.get("name"),
-            description: row.get::<Option<String>, _>("description").unwrap_or_default(),
-            status: row.get("status"), 
-            funding_goal: row.get("funding_goal"),
-            equity_offered: row.get("equity_offered"),
-            is_public: row.get("is_public"),
-            industry: row.get::<Option<String>, _>("industry").unwrap_or_default(),
-         }).collect();
+         let projects = rows.iter().map(|row| project_from_row(row, &tenant)).collect();
          
          Ok(Response::new(ListProjectsResponse { projects }))
     }
 
     async fn create_task(&self, request: Request<CreateTaskRequest>) -> Result<Response<Task>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
         let req = request.into_inner();
         let id = Uuid::new_v4();
         let project_id = Uuid::parse_str(&req.project_id).map_err(|_| Status::invalid_argument("Invalid Project UUID"))?;
-        let assignee_id = if req.assignee_id.is_empty() { None } else { Some(Uuid::parse_str(&req.assignee_id).unwrap_or(Uuid::nil())) };
+        let assignee_id = if req.assignee_id.is_empty() {
+            None
+        } else {
+            Some(Uuid::parse_str(&req.assignee_id).map_err(|_| Status::invalid_argument("Invalid Assignee UUID"))?)
+        };
+        let priority = if req.priority.is_empty() { TaskPriority::Medium.name() } else { req.priority.clone() };
+        teams::access(&self.pool, &tenant, project_id).await?.require_edit_tasks()?;
+        if let Some(assignee_id) = assignee_id {
+            if !teams::is_on_team(&self.pool, project_id, assignee_id).await? {
+                return Err(Status::invalid_argument("The assignee is not on the project team"));
+            }
+        }
 
-        sqlx::query("INSERT INTO tasks (id, project_id, title, description, priority, assignee_id, status) VALUES ($1, $2, $3, $4, $5, $6, 'todo')")
+        sqlx::query("INSERT INTO tasks (id, project_id, title, description, priority, assignee_id, status) VALUES ($1, $2, $3, $4, $5, $6, $7)")
             .bind(id)
             .bind(project_id)
             .bind(&req.title)
             .bind(&req.description)
-            .bind(&req.priority)
+            .bind(&priority)
             .bind(assignee_id)
+            .bind(TaskStatus::Todo.name())
             .execute(&self.pool)
             .await
             .map_err(|e| Status::internal(format!("DB: {}", e)))?;
@@ -171,46 +854,52 @@ impl TaskService for MyTaskService {
             project_id: req.project_id,
             title: req.title,
             description: req.description,
-            status: "todo".into(),
-            priority: req.priority,
+            status: TaskStatus::Todo.name(),
+            priority,
             assignee_id: req.assignee_id,
             position: 0,
+            deleted_at: String::new(),
         }))
     }
 
     async fn list_tasks(&self, request: Request<ListTasksRequest>) -> Result<Response<ListTasksResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
         let req = request.into_inner();
         let project_id = Uuid::parse_str(&req.project_id).map_err(|_| Status::invalid_argument("Invalid Project UUID"))?;
+        teams::access(&self.pool, &tenant, project_id).await?.require_view()?;
+        let filter = if req.deleted {
+            "deleted_at > $2 ORDER BY deleted_at DESC"
+        } else {
+            "deleted_at IS NULL ORDER BY position ASC, created_at DESC"
+        };
 
-        let rows = sqlx::query("SELECT id, project_id, title, description, status, priority, assignee_id, position FROM tasks WHERE project_id = $1 ORDER BY position ASC, created_at DESC")
+        let rows = sqlx::query(&format!("SELECT {} FROM tasks WHERE project_id = $1 AND {}", TASK_COLUMNS, filter))
              .bind(project_id)
+             .bind(self.trash.cutoff())
              .fetch_all(&self.pool)
              .await
              .map_err(|e| Status::internal(format!("DB: {}", e)))?;
 
-        let tasks = rows.into_iter().map(|row| Task {
-            id: row.get::<Uuid, _>("id").to_string(),
-            project_id: row.get::<Uuid, _>("project_id").to_string(),
-            title: row.get("title"),
-            description: row.get::<Option<String>, _>("description").unwrap_or_default(),
-            status: row.get("status"),
-            priority: row.get("priority"),
-            assignee_id: row.get::<Option<Uuid>, _>("assignee_id").map(|u| u.to_string()).unwrap_or_default(),
-            position: row.get("position"),
-        }).collect();
+        let tasks = rows.iter().map(task_from_row).collect();
 
         Ok(Response::new(ListTasksResponse { tasks }))
     }
 
     async fn update_task(&self, request: Request<UpdateTaskRequest>) -> Result<Response<UpdateTaskResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
         let req = request.into_inner();
         let id = Uuid::parse_str(&req.id).map_err(|_| Status::invalid_argument("Invalid Task UUID"))?;
+        teams::task_access(&self.pool, &tenant, id).await?.1.require_edit_tasks()?;
 
         if !req.status.is_empty() {
             sqlx::query("UPDATE tasks SET status = $1 WHERE id = $2")
                 .bind(&req.status)
                 .bind(id)
-                .execute(&self.pool).await.ok();
+                .execute(&self.pool)
+                .await
+                .map_err(|e| Status::internal(format!("DB: {}", e)))?;
         }
         if !req.priority.is_empty() {
              sqlx::query("UPDATE tasks SET priority = $1 WHERE id = $2")
@@ -225,29 +914,53 @@ impl TaskService for MyTaskService {
                 .execute(&self.pool).await.ok();
         }
 
-        let row = sqlx::query("SELECT id, project_id, title, description, status, priority, assignee_id, position FROM tasks WHERE id = $1")
+        let row = sqlx::query(&format!("SELECT {} FROM tasks WHERE id = $1", TASK_COLUMNS))
             .bind(id)
             .fetch_one(&self.pool)
             .await
             .map_err(|_| Status::not_found("Task not found"))?;
 
-        let task = Task {
-            id: row.get::<Uuid, _>("id").to_string(),
-            project_id: row.get::<Uuid, _>("project_id").to_string(),
-            title: row.get("title"),
-            description: row.get::<Option<String>, _>("description").unwrap_or_default(),
-            status: row.get("status"),
-            priority: row.get("priority"),
-            assignee_id: row.get::<Option<Uuid>, _>("assignee_id").map(|u| u.to_string()).unwrap_or_default(),
-            position: row.get("position"),
-        };
+        let task = task_from_row(&row);
+
+        // Completing a task earns the assignee reputation, judged by the status as stored; the
+        // dedupe key makes reopening and closing again a no-op.
+        if !req.status.is_empty() && TaskStatus::from_name(&task.status) == Some(TaskStatus::Done) {
+            if let Some(assignee_id) = row.get::<Option<Uuid>, _>("assignee_id") {
+                let owner_id: Option<Uuid> = sqlx::query("SELECT owner_id FROM projects WHERE id = $1")
+                    .bind(row.get::<Uuid, _>("project_id"))
+                    .fetch_optional(&self.pool)
+                    .await
+                    .map_err(|e| Status::internal(format!("DB: {}", e)))?
+                    .map(|p| p.get("owner_id"));
+                let points = if owner_id == Some(assignee_id) {
+                    reputation::OWN_TASK_COMPLETED_POINTS
+                } else {
+                    reputation::TASK_COMPLETED_POINTS
+                };
+                self.reputation.record_quietly(reputation::Event {
+                    user_id: assignee_id,
+                    kind: reputation::TASK_COMPLETED,
+                    points,
+                    source_user_id: None,
+                    subject_id: Some(id),
+                    dedupe_key: format!("{}:{}", reputation::TASK_COMPLETED, id),
+                    replace: false,
+                }).await;
+            }
+        }
 
         Ok(Response::new(UpdateTaskResponse { task: Some(task) }))
     }
 
     async fn update_project(&self, request: Request<UpdateProjectRequest>) -> Result<Response<Project>, Status> {
+        request.get_ref().validate()?;
+       let tenant = Tenant::from_request(&self.pool, &request).await?;
        let req = request.into_inner();
         let id = Uuid::parse_str(&req.id).map_err(|_| Status::invalid_argument("Invalid Project UUID"))?;
+        teams::access(&self.pool, &tenant, id).await?.require_manage()?;
+        if req.is_public {
+            verification::require_verified_email(&self.pool, tenant.require_user()?).await?;
+        }
 
         if !req.description.is_empty() {
              sqlx::query("UPDATE projects SET description = $1 WHERE id = $2").bind(&req.description).bind(id).execute(&self.pool).await.ok();
@@ -264,75 +977,145 @@ impl TaskService for MyTaskService {
              sqlx::query("UPDATE projects SET industry = $1 WHERE id = $2").bind(&req.industry).bind(id).execute(&self.pool).await.ok();
         }
 
-        let row = sqlx::query("SELECT id, owner_id, name, description, status, funding_goal, equity_offered, is_public, industry FROM projects WHERE id = $1")
+        load_project(&self.pool, id, &tenant).await.map(Response::new)
+    }
+
+    async fn archive_project(&self, request: Request<ArchiveProjectRequest>) -> Result<Response<Project>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let id = Uuid::parse_str(&request.get_ref().id).map_err(|_| Status::invalid_argument("Invalid Project UUID"))?;
+        let access = teams::access(&self.pool, &tenant, id).await?;
+        access.require_view()?;
+        if access.owner_id != caller {
+            return Err(Status::permission_denied("Only the project owner can archive it"));
+        }
+
+        sqlx::query("UPDATE projects SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+        load_project(&self.pool, id, &tenant).await.map(Response::new)
+    }
+
+    async fn restore_project(&self, request: Request<RestoreProjectRequest>) -> Result<Response<Project>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let id = Uuid::parse_str(&request.get_ref().id).map_err(|_| Status::invalid_argument("Invalid Project UUID"))?;
+
+        let restored = sqlx::query(
+            "UPDATE projects SET deleted_at = NULL WHERE id = $1 AND owner_id = $2 AND org_id IS NOT DISTINCT FROM $3 \
+             AND deleted_at > $4",
+        )
+        .bind(id)
+        .bind(caller)
+        .bind(tenant.org_id)
+        .bind(self.trash.cutoff())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .rows_affected();
+        if restored == 0 {
+            return Err(Status::not_found("Project not found or past the restore window"));
+        }
+
+        load_project(&self.pool, id, &tenant).await.map(Response::new)
+    }
+
+    async fn delete_task(&self, request: Request<DeleteTaskRequest>) -> Result<Response<DeleteTaskResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let id = Uuid::parse_str(&request.get_ref().id).map_err(|_| Status::invalid_argument("Invalid Task UUID"))?;
+        teams::task_access(&self.pool, &tenant, id).await?.1.require_edit_tasks()?;
+
+        sqlx::query("UPDATE tasks SET deleted_at = NOW() WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+        Ok(Response::new(DeleteTaskResponse {}))
+    }
+
+    async fn restore_task(&self, request: Request<RestoreTaskRequest>) -> Result<Response<Task>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let id = Uuid::parse_str(&request.get_ref().id).map_err(|_| Status::invalid_argument("Invalid Task UUID"))?;
+        let not_found = || Status::not_found("Task not found or past the restore window");
+
+        let project_id: Uuid = sqlx::query("SELECT project_id FROM tasks WHERE id = $1 AND deleted_at > $2")
+            .bind(id)
+            .bind(self.trash.cutoff())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?
+            .ok_or_else(not_found)?
+            .get("project_id");
+        let access = teams::access(&self.pool, &tenant, project_id).await.map_err(|_| not_found())?;
+        access.require_view().map_err(|_| not_found())?;
+        access.require_edit_tasks()?;
+
+        let row = sqlx::query(&format!("UPDATE tasks SET deleted_at = NULL WHERE id = $1 RETURNING {}", TASK_COLUMNS))
             .bind(id)
             .fetch_one(&self.pool)
             .await
-            .map_err(|_| Status::not_found("Project not found"))?;
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?;
 
-        Ok(Response::new(Project {
-            id: row.get::<Uuid, _>("id").to_string(),
-            owner_id: row.get::<Uuid, _>("owner_id").to_string(),
-            name: row.get("name"),
-            description: row.get::<Option<String>, _>("description").unwrap_or_default(),
-            status: row.get("status"), 
-            funding_goal: row.get("funding_goal"),
-            equity_offered: row.get("equity_offered"),
-            is_public: row.get("is_public"),
-            industry: row.get::<Option<String>, _>("industry").unwrap_or_default(),
-        }))
+        Ok(Response::new(task_from_row(&row)))
     }
 
     async fn list_public_projects(&self, request: Request<ListPublicProjectsRequest>) -> Result<Response<ListProjectsResponse>, Status> {
+        request.get_ref().validate()?;
+         let tenant = Tenant::from_request(&self.pool, &request).await?;
          let req = request.into_inner();
-         let query = if !req.industry_filter.is_empty() {
-             "SELECT id, owner_id, name, description, status, funding_goal, equity_offered, is_public, industry FROM projects WHERE is_public = true AND industry = $1 ORDER BY created_at DESC"
-         } else {
-             "SELECT id, owner_id, name, description, status, funding_goal, equity_offered, is_public, industry FROM projects WHERE is_public = true ORDER BY created_at DESC"
-         };
-         
-         let q = sqlx::query(query);
-         let rows = if !req.industry_filter.is_empty() {
-             q.bind(req.industry_filter).fetch_all(&self.pool).await
-         } else {
-             q.fetch_all(&self.pool).await
-         }.map_err(|e| Status::internal(format!("DB: {}", e)))?;
+         let rows = sqlx::query(&format!(
+             "SELECT {}, {} FROM projects p WHERE p.is_public = true AND p.deleted_at IS NULL AND ($1 = '' OR p.industry = $1) ORDER BY p.created_at DESC",
+             PROJECT_COLUMNS, teams::member_role_column(2)
+         ))
+             .bind(&req.industry_filter)
+             .bind(tenant.user_id)
+             .fetch_all(&self.pool)
+             .await
+             .map_err(|e| Status::internal(format!("DB: {}", e)))?;
 
-         let projects = rows.into_iter().map(|row: sqlx::postgres::PgRow| Project {
-            id: row.get::<Uuid, _>("id").to_string(),
-            owner_id: row.get::<Uuid, _>("owner_id").to_string(),
-            name: row.get("name"),
-            description: row.get::<Option<String>, _>("description").unwrap_or_default(),
-            status: row.get("status"), 
-            funding_goal: row.get("funding_goal"),
-            equity_offered: row.get("equity_offered"),
-            is_public: row.get("is_public"),
-            industry: row.get::<Option<String>, _>("industry").unwrap_or_default(),
-         }).collect();
+         let projects = rows.iter().map(|row| project_from_row(row, &tenant)).collect();
          Ok(Response::new(ListProjectsResponse { projects }))
     }
 
     async fn launch_project(&self, request: Request<LaunchProjectRequest>) -> Result<Response<Project>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        verification::require_verified_email(&self.pool, caller).await?;
         let req = request.into_inner();
         let idea_uuid = Uuid::parse_str(&req.idea_id).map_err(|_| Status::invalid_argument("Invalid Idea UUID"))?;
 
-        // 1. Fetch Idea info (owner)
-        let idea_row = sqlx::query("SELECT creator_id FROM ideas WHERE id = $1")
+        // 1. Fetch Idea info (owner); only the current workspace's own ideas can be launched
+        let idea_row = sqlx::query("SELECT creator_id, org_id FROM ideas WHERE id = $1 AND org_id IS NOT DISTINCT FROM $2 AND deleted_at IS NULL")
             .bind(idea_uuid)
+            .bind(tenant.org_id)
             .fetch_one(&self.pool)
             .await
             .map_err(|_| Status::not_found("Idea not found"))?;
         
         let owner_id: Uuid = idea_row.get("creator_id");
+        if owner_id != caller {
+            return Err(Status::permission_denied("Only the idea's creator can launch it"));
+        }
+        let org_id: Option<Uuid> = idea_row.get("org_id");
         let project_id = Uuid::new_v4();
 
-        // 2. Create Project
-        sqlx::query("INSERT INTO projects (id, owner_id, name, description, industry, status) VALUES ($1, $2, $3, $4, $5, 'active')")
+        // 2. Create Project, in the idea's workspace
+        sqlx::query("INSERT INTO projects (id, owner_id, name, description, industry, status, org_id) VALUES ($1, $2, $3, $4, $5, 'active', $6)")
             .bind(project_id)
             .bind(owner_id)
             .bind(&req.title)
             .bind(&req.description)
             .bind(&req.industry)
+            .bind(org_id)
             .execute(&self.pool)
             .await
             .map_err(|e| Status::internal(format!("DB Project Create: {}", e)))?;
@@ -360,6 +1143,16 @@ impl TaskService for MyTaskService {
             .execute(&self.pool)
             .await.ok();
 
+        self.reputation.record_quietly(reputation::Event {
+            user_id: owner_id,
+            kind: reputation::IDEA_LAUNCHED,
+            points: reputation::IDEA_LAUNCHED_POINTS,
+            source_user_id: None,
+            subject_id: Some(idea_uuid),
+            dedupe_key: format!("{}:{}", reputation::IDEA_LAUNCHED, idea_uuid),
+            replace: false,
+        }).await;
+
         Ok(Response::new(Project {
             id: project_id.to_string(),
             owner_id: owner_id.to_string(),
@@ -370,13 +1163,19 @@ impl TaskService for MyTaskService {
             equity_offered: 0.0,
             is_public: false,
             industry: req.industry,
+            role: teams::ROLE_OWNER.into(),
+            archived_at: String::new(),
         }))
     }
 
     async fn create_notification(&self, request: Request<CreateNotificationRequest>) -> Result<Response<Notification>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
         let req = request.into_inner();
         let id = Uuid::new_v4();
         let user_id = Uuid::parse_str(&req.user_id).map_err(|_| Status::invalid_argument("Invalid User UUID"))?;
+        // Notifications for anyone else (invitations, ownership offers) are only written by this service.
+        tenant.require_self(user_id)?;
         let payload = serde_json::from_str::<serde_json::Value>(&req.payload_json).unwrap_or(serde_json::json!({}));
 
         sqlx::query("INSERT INTO notifications (id, user_id, type, content, payload) VALUES ($1, $2, $3, $4, $5)")
@@ -401,8 +1200,12 @@ impl TaskService for MyTaskService {
     }
 
     async fn list_notifications(&self, request: Request<ListNotificationsRequest>) -> Result<Response<ListNotificationsResponse>, Status> {
+        request.get_ref().validate()?;
+         let tenant = Tenant::from_request(&self.pool, &request).await?;
+         let caller = tenant.require_user()?;
          let req = request.into_inner();
-         let user_id = Uuid::parse_str(&req.user_id).map_err(|_| Status::invalid_argument("Invalid User UUID"))?;
+         let user_id = if req.user_id.is_empty() { caller } else { Uuid::parse_str(&req.user_id).map_err(|_| Status::invalid_argument("Invalid User UUID"))? };
+         tenant.require_self(user_id)?;
 
          let rows = sqlx::query("SELECT id, user_id, type, content, payload, read, created_at FROM notifications WHERE user_id = $1 ORDER BY created_at DESC")
             .bind(user_id)
@@ -427,26 +1230,906 @@ impl TaskService for MyTaskService {
     }
 }
 
+// TEAM SERVICE IMPLEMENTATION
+#[derive(Debug)]
+pub struct MyTeamService {
+    pool: PgPool,
+    app_base_url: String,
+}
+
+impl MyTeamService {
+    /// The caller's access to a project whose team they may manage.
+    async fn require_manager(&self, tenant: &Tenant, project_id: Uuid) -> Result<teams::Access, Status> {
+        tenant.require_user()?;
+        let access = teams::access(&self.pool, tenant, project_id).await?;
+        access.require_manage()?;
+        Ok(access)
+    }
+
+    /// A pending, unexpired invitation addressed to the caller's verified email, locked for the response.
+    async fn open_invitation(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        caller: Uuid,
+        id: &str,
+    ) -> Result<sqlx::postgres::PgRow, Status> {
+        let id = Uuid::parse_str(id).map_err(|_| Status::invalid_argument("Invalid Invitation UUID"))?;
+        verification::require_verified_email(&self.pool, caller).await?;
+        sqlx::query(
+            "SELECT i.id, i.project_id, i.role, i.invited_by, p.org_id, p.name AS project_name, p.owner_id \
+             FROM project_invitations i JOIN projects p ON p.id = i.project_id \
+             WHERE i.id = $1 AND i.status = 'pending' AND i.expires_at > NOW() AND p.deleted_at IS NULL \
+             AND i.email = (SELECT LOWER(email) FROM users WHERE id = $2) \
+             FOR UPDATE OF i",
+        )
+        .bind(id)
+        .bind(caller)
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .ok_or_else(|| Status::not_found("Invitation not found or no longer open"))
+    }
+
+    /// A pending, unexpired ownership transfer offered to the caller, locked with its project.
+    async fn open_transfer(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        caller: Uuid,
+        id: &str,
+    ) -> Result<sqlx::postgres::PgRow, Status> {
+        let id = Uuid::parse_str(id).map_err(|_| Status::invalid_argument("Invalid Transfer UUID"))?;
+        sqlx::query(
+            "SELECT t.id, t.project_id, t.from_user_id, t.previous_owner_role, p.name AS project_name, p.owner_id, p.org_id \
+             FROM project_ownership_transfers t JOIN projects p ON p.id = t.project_id \
+             WHERE t.id = $1 AND t.to_user_id = $2 AND t.status = 'pending' AND t.expires_at > NOW() AND p.deleted_at IS NULL \
+             FOR UPDATE OF t, p",
+        )
+        .bind(id)
+        .bind(caller)
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .ok_or_else(|| Status::not_found("Ownership transfer not found or no longer open"))
+    }
+}
+
+#[tonic::async_trait]
+impl TeamService for MyTeamService {
+    async fn get_team(&self, request: Request<GetTeamRequest>) -> Result<Response<Team>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let project_id = Uuid::parse_str(&request.get_ref().project_id).map_err(|_| Status::invalid_argument("Invalid Project UUID"))?;
+        teams::access(&self.pool, &tenant, project_id).await?.require_view()?;
+
+        let members = teams::load_team(&self.pool, project_id).await?;
+        Ok(Response::new(Team { project_id: project_id.to_string(), members }))
+    }
+
+    async fn update_member_role(&self, request: Request<UpdateMemberRoleRequest>) -> Result<Response<Member>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let req = request.into_inner();
+        let project_id = Uuid::parse_str(&req.project_id).map_err(|_| Status::invalid_argument("Invalid Project UUID"))?;
+        let user_id = Uuid::parse_str(&req.user_id).map_err(|_| Status::invalid_argument("Invalid User UUID"))?;
+        let access = self.require_manager(&tenant, project_id).await?;
+        if req.role.trim().is_empty() {
+            return Err(Status::invalid_argument("role is required"));
+        }
+        let role = teams::role(&req.role)?;
+        if user_id == access.owner_id {
+            return Err(Status::failed_precondition("The owner's role can only change by transferring ownership"));
+        }
+
+        let current = teams::load_member(&self.pool, project_id, user_id).await?;
+        if access.role.as_deref() != Some(teams::ROLE_OWNER) && (current.role == teams::ROLE_MAINTAINER || role == teams::ROLE_MAINTAINER) {
+            return Err(Status::permission_denied("Only the project owner can appoint or change maintainers"));
+        }
+
+        sqlx::query("UPDATE project_members SET role = $3 WHERE project_id = $1 AND user_id = $2")
+            .bind(project_id)
+            .bind(user_id)
+            .bind(&role)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+        Ok(Response::new(teams::load_member(&self.pool, project_id, user_id).await?))
+    }
+
+    /// Anyone may leave; otherwise as for `update_member_role`. Tasks the member was assigned
+    /// in the project go back to being unassigned.
+    async fn remove_member(&self, request: Request<RemoveMemberRequest>) -> Result<Response<RemoveMemberResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let req = request.into_inner();
+        let project_id = Uuid::parse_str(&req.project_id).map_err(|_| Status::invalid_argument("Invalid Project UUID"))?;
+        let user_id = Uuid::parse_str(&req.user_id).map_err(|_| Status::invalid_argument("Invalid User UUID"))?;
+        let access = teams::access(&self.pool, &tenant, project_id).await?;
+        access.require_view()?;
+        if user_id == access.owner_id {
+            return Err(Status::failed_precondition("The owner can't leave the project; transfer ownership first"));
+        }
+
+        let current = teams::load_member(&self.pool, project_id, user_id).await?;
+        if user_id != caller {
+            let caller_role = access.require_manage()?;
+            if caller_role != teams::ROLE_OWNER && current.role == teams::ROLE_MAINTAINER {
+                return Err(Status::permission_denied("Only the project owner can remove maintainers"));
+            }
+        }
+
+        let db = |e: sqlx::Error| Status::internal(format!("DB: {}", e));
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        sqlx::query("DELETE FROM project_members WHERE project_id = $1 AND user_id = $2")
+            .bind(project_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        sqlx::query("UPDATE tasks SET assignee_id = NULL WHERE project_id = $1 AND assignee_id = $2")
+            .bind(project_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        tx.commit().await.map_err(db)?;
+
+        Ok(Response::new(RemoveMemberResponse {}))
+    }
+
+    async fn invite_member(&self, request: Request<InviteMemberRequest>) -> Result<Response<InviteMemberResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let req = request.into_inner();
+        let project_id = Uuid::parse_str(&req.project_id).map_err(|_| Status::invalid_argument("Invalid Project UUID"))?;
+        let access = self.require_manager(&tenant, project_id).await?;
+        let (org_id, project_name) = (access.org_id, access.name.clone());
+        let caller = tenant.require_user()?;
+        let email = req.email.trim().to_lowercase();
+        let role = teams::role(&req.role)?;
+        if role == teams::ROLE_MAINTAINER && access.role.as_deref() != Some(teams::ROLE_OWNER) {
+            return Err(Status::permission_denied("Only the project owner can appoint maintainers"));
+        }
+
+        let invitee = sqlx::query(
+            "SELECT u.id, \
+             (u.id = (SELECT owner_id FROM projects WHERE id = $2) \
+              OR EXISTS (SELECT 1 FROM project_members WHERE project_id = $2 AND user_id = u.id)) AS on_team, \
+             ($3::UUID IS NULL OR EXISTS (SELECT 1 FROM organization_members WHERE org_id = $3 AND user_id = u.id)) AS in_org \
+             FROM users u WHERE LOWER(u.email) = $1 AND u.deleted_at IS NULL",
+        )
+        .bind(&email)
+        .bind(project_id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+        let invitee_id: Option<Uuid> = invitee.as_ref().map(|row| row.get("id"));
+        if invitee.as_ref().is_some_and(|row| row.get::<bool, _>("on_team")) {
+            return Err(Status::already_exists("That person is already on the team"));
+        }
+        // An organization's projects stay inside it.
+        if org_id.is_some() && !invitee.as_ref().is_some_and(|row| row.get::<bool, _>("in_org")) {
+            return Err(Status::failed_precondition("Only members of the organization can join its projects"));
+        }
+
+        let db = |e: sqlx::Error| Status::internal(format!("DB: {}", e));
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        // Frees the address for a new invitation once the old one has lapsed.
+        sqlx::query("UPDATE project_invitations SET status = 'expired' WHERE project_id = $1 AND status = 'pending' AND expires_at <= NOW()")
+            .bind(project_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        let pending: i64 = sqlx::query("SELECT COUNT(*) AS n FROM project_invitations WHERE project_id = $1 AND status = 'pending'")
+            .bind(project_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db)?
+            .get("n");
+        if pending >= teams::MAX_PENDING_INVITATIONS {
+            return Err(Status::resource_exhausted(format!(
+                "A project can have at most {} pending invitations",
+                teams::MAX_PENDING_INVITATIONS
+            )));
+        }
+
+        let id = Uuid::new_v4();
+        let token = tokens::generate();
+        sqlx::query(
+            "INSERT INTO project_invitations (id, project_id, email, role, token_hash, invited_by, invitee_id, expires_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, NOW() + make_interval(days => $8))",
+        )
+        .bind(id)
+        .bind(project_id)
+        .bind(&email)
+        .bind(&role)
+        .bind(&token.hash)
+        .bind(caller)
+        .bind(invitee_id)
+        .bind(teams::INVITATION_TTL_DAYS)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                Status::already_exists("An invitation to that address is already pending")
+            }
+            e => Status::internal(format!("DB: {}", e)),
+        })?;
+
+        if let Some(invitee_id) = invitee_id {
+            notifications::notify(
+                &mut tx,
+                invitee_id,
+                notifications::PROJECT_INVITATION,
+                &format!("You've been invited to join {} as a {}", project_name, role),
+                serde_json::json!({ "invitation_id": id, "project_id": project_id, "role": role }),
+            )
+            .await
+            .map_err(db)?;
+        }
+        tx.commit().await.map_err(db)?;
+
+        let invitation = sqlx::query(&format!(
+            "SELECT {} FROM project_invitations i JOIN projects p ON p.id = i.project_id WHERE i.id = $1",
+            teams::INVITATION_COLUMNS
+        ))
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(db)?;
+
+        Ok(Response::new(InviteMemberResponse {
+            invitation: Some(teams::invitation_from_row(&invitation)),
+            invite_url: format!("{}/invitations?token={}", self.app_base_url, token.plain),
+        }))
+    }
+
+    async fn list_invitations(&self, request: Request<ListInvitationsRequest>) -> Result<Response<ListInvitationsResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let project_id = Uuid::parse_str(&request.get_ref().project_id).map_err(|_| Status::invalid_argument("Invalid Project UUID"))?;
+        self.require_manager(&tenant, project_id).await?;
+
+        let invitations = sqlx::query(&format!(
+            "SELECT {} FROM project_invitations i JOIN projects p ON p.id = i.project_id \
+             WHERE i.project_id = $1 AND i.status = 'pending' AND i.expires_at > NOW() ORDER BY i.created_at DESC",
+            teams::INVITATION_COLUMNS
+        ))
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .iter()
+        .map(teams::invitation_from_row)
+        .collect();
+
+        Ok(Response::new(ListInvitationsResponse { invitations }))
+    }
+
+    async fn revoke_invitation(&self, request: Request<RevokeInvitationRequest>) -> Result<Response<RevokeInvitationResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let id = Uuid::parse_str(&request.get_ref().id).map_err(|_| Status::invalid_argument("Invalid Invitation UUID"))?;
+        let not_found = || Status::not_found("Invitation not found or no longer pending");
+
+        let project_id: Uuid = sqlx::query("SELECT project_id FROM project_invitations WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?
+            .ok_or_else(not_found)?
+            .get("project_id");
+        self.require_manager(&tenant, project_id).await.map_err(|e| match e.code() {
+            tonic::Code::NotFound => not_found(),
+            _ => e,
+        })?;
+
+        let revoked = sqlx::query("UPDATE project_invitations SET status = 'revoked', responded_at = NOW() WHERE id = $1 AND status = 'pending'")
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .rows_affected();
+        if revoked == 0 {
+            return Err(not_found());
+        }
+
+        Ok(Response::new(RevokeInvitationResponse {}))
+    }
+
+    async fn list_my_invitations(&self, request: Request<ListMyInvitationsRequest>) -> Result<Response<ListInvitationsResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        verification::require_verified_email(&self.pool, caller).await?;
+
+        let invitations = sqlx::query(&format!(
+            "SELECT {} FROM project_invitations i JOIN projects p ON p.id = i.project_id \
+             WHERE i.email = (SELECT LOWER(email) FROM users WHERE id = $1) AND i.status = 'pending' AND i.expires_at > NOW() \
+             AND p.deleted_at IS NULL \
+             ORDER BY i.created_at DESC",
+            teams::INVITATION_COLUMNS
+        ))
+        .bind(caller)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .iter()
+        .map(teams::invitation_from_row)
+        .collect();
+
+        Ok(Response::new(ListInvitationsResponse { invitations }))
+    }
+
+    async fn get_invitation(&self, request: Request<GetInvitationRequest>) -> Result<Response<Invitation>, Status> {
+        request.get_ref().validate()?;
+        let token_hash = tokens::hash(&request.get_ref().token);
+        sqlx::query(&format!(
+            "SELECT {} FROM project_invitations i JOIN projects p ON p.id = i.project_id WHERE i.token_hash = $1",
+            teams::INVITATION_COLUMNS
+        ))
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .map(|row| Response::new(teams::invitation_from_row(&row)))
+        .ok_or_else(|| Status::not_found("Invitation not found"))
+    }
+
+    async fn accept_invitation(&self, request: Request<RespondToInvitationRequest>) -> Result<Response<Team>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let db = |e: sqlx::Error| Status::internal(format!("DB: {}", e));
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        let invitation = self.open_invitation(&mut tx, caller, &request.get_ref().id).await?;
+        let id: Uuid = invitation.get("id");
+        let project_id: Uuid = invitation.get("project_id");
+        let project_name: String = invitation.get("project_name");
+        let role: String = invitation.get("role");
+
+        if let Some(org_id) = invitation.get::<Option<Uuid>, _>("org_id") {
+            let in_org = sqlx::query("SELECT 1 FROM organization_members WHERE org_id = $1 AND user_id = $2")
+                .bind(org_id)
+                .bind(caller)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(db)?;
+            if in_org.is_none() {
+                return Err(Status::failed_precondition("Only members of the organization can join its projects"));
+            }
+        }
+        // Owners and existing members keep their place; the invitation is simply used up.
+        if invitation.get::<Uuid, _>("owner_id") != caller {
+            sqlx::query("INSERT INTO project_members (project_id, user_id, role) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING")
+                .bind(project_id)
+                .bind(caller)
+                .bind(&role)
+                .execute(&mut *tx)
+                .await
+                .map_err(db)?;
+        }
+        sqlx::query("UPDATE project_invitations SET status = 'accepted', invitee_id = $2, responded_at = NOW() WHERE id = $1")
+            .bind(id)
+            .bind(caller)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        if let Some(inviter) = invitation.get::<Option<Uuid>, _>("invited_by") {
+            notifications::notify(
+                &mut tx,
+                inviter,
+                notifications::INVITATION_ACCEPTED,
+                &format!("Your invitation to {} was accepted", project_name),
+                serde_json::json!({ "invitation_id": id, "project_id": project_id, "user_id": caller, "role": role }),
+            )
+            .await
+            .map_err(db)?;
+        }
+        tx.commit().await.map_err(db)?;
+
+        let members = teams::load_team(&self.pool, project_id).await?;
+        Ok(Response::new(Team { project_id: project_id.to_string(), members }))
+    }
+
+    async fn decline_invitation(&self, request: Request<RespondToInvitationRequest>) -> Result<Response<DeclineInvitationResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let db = |e: sqlx::Error| Status::internal(format!("DB: {}", e));
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        let invitation = self.open_invitation(&mut tx, caller, &request.get_ref().id).await?;
+        let id: Uuid = invitation.get("id");
+        let project_name: String = invitation.get("project_name");
+
+        sqlx::query("UPDATE project_invitations SET status = 'declined', invitee_id = $2, responded_at = NOW() WHERE id = $1")
+            .bind(id)
+            .bind(caller)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        if let Some(inviter) = invitation.get::<Option<Uuid>, _>("invited_by") {
+            notifications::notify(
+                &mut tx,
+                inviter,
+                notifications::INVITATION_DECLINED,
+                &format!("Your invitation to {} was declined", project_name),
+                serde_json::json!({ "invitation_id": id, "project_id": invitation.get::<Uuid, _>("project_id") }),
+            )
+            .await
+            .map_err(db)?;
+        }
+        tx.commit().await.map_err(db)?;
+
+        Ok(Response::new(DeclineInvitationResponse {}))
+    }
+
+    async fn transfer_project_ownership(&self, request: Request<TransferProjectOwnershipRequest>) -> Result<Response<OwnershipTransfer>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let req = request.into_inner();
+        let project_id = Uuid::parse_str(&req.project_id).map_err(|_| Status::invalid_argument("Invalid Project UUID"))?;
+        let user_id = Uuid::parse_str(&req.user_id).map_err(|_| Status::invalid_argument("Invalid User UUID"))?;
+        let access = teams::access(&self.pool, &tenant, project_id).await?;
+        access.require_view()?;
+        if access.owner_id != caller {
+            return Err(Status::permission_denied("Only the project owner can transfer it"));
+        }
+        if user_id == caller {
+            return Err(Status::invalid_argument("You already own this project"));
+        }
+        let previous_owner_role = teams::previous_owner_role(&req.previous_owner_role)?;
+        if !teams::is_on_team(&self.pool, project_id, user_id).await? {
+            return Err(Status::failed_precondition("Ownership can only go to someone already on the team"));
+        }
+
+        let db = |e: sqlx::Error| Status::internal(format!("DB: {}", e));
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        sqlx::query("UPDATE project_ownership_transfers SET status = 'expired' WHERE project_id = $1 AND status = 'pending' AND expires_at <= NOW()")
+            .bind(project_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        let id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO project_ownership_transfers (id, project_id, from_user_id, to_user_id, previous_owner_role, expires_at) \
+             VALUES ($1, $2, $3, $4, $5, NOW() + make_interval(days => $6))",
+        )
+        .bind(id)
+        .bind(project_id)
+        .bind(caller)
+        .bind(user_id)
+        .bind(&previous_owner_role)
+        .bind(teams::TRANSFER_TTL_DAYS)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                Status::already_exists("An ownership transfer is already pending for this project")
+            }
+            e => Status::internal(format!("DB: {}", e)),
+        })?;
+        project_events::record(
+            &mut tx,
+            project_id,
+            project_events::OWNERSHIP_TRANSFER_REQUESTED,
+            caller,
+            Some(user_id),
+            serde_json::json!({ "transfer_id": id, "previous_owner_role": previous_owner_role }),
+        )
+        .await
+        .map_err(db)?;
+        notifications::notify(
+            &mut tx,
+            user_id,
+            notifications::OWNERSHIP_TRANSFER_OFFERED,
+            &format!("You've been offered ownership of {}", access.name),
+            serde_json::json!({ "transfer_id": id, "project_id": project_id }),
+        )
+        .await
+        .map_err(db)?;
+        tx.commit().await.map_err(db)?;
+
+        Ok(Response::new(teams::load_transfer(&self.pool, id).await?))
+    }
+
+    async fn cancel_ownership_transfer(&self, request: Request<RespondToOwnershipTransferRequest>) -> Result<Response<OwnershipTransfer>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let id = Uuid::parse_str(&request.get_ref().id).map_err(|_| Status::invalid_argument("Invalid Transfer UUID"))?;
+
+        let db = |e: sqlx::Error| Status::internal(format!("DB: {}", e));
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        let project_id: Uuid = sqlx::query(
+            "UPDATE project_ownership_transfers t SET status = 'cancelled', responded_at = NOW() FROM projects p \
+             WHERE t.id = $1 AND p.id = t.project_id AND t.from_user_id = $2 AND p.org_id IS NOT DISTINCT FROM $3 \
+             AND t.status = 'pending' AND t.expires_at > NOW() RETURNING t.project_id",
+        )
+        .bind(id)
+        .bind(caller)
+        .bind(tenant.org_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db)?
+        .ok_or_else(|| Status::not_found("Ownership transfer not found or no longer pending"))?
+        .get("project_id");
+        project_events::record(&mut tx, project_id, project_events::OWNERSHIP_TRANSFER_CANCELLED, caller, None, serde_json::json!({ "transfer_id": id }))
+            .await
+            .map_err(db)?;
+        tx.commit().await.map_err(db)?;
+
+        Ok(Response::new(teams::load_transfer(&self.pool, id).await?))
+    }
+
+    async fn list_my_ownership_transfers(&self, request: Request<ListMyOwnershipTransfersRequest>) -> Result<Response<ListOwnershipTransfersResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+
+        let transfers = sqlx::query(&format!(
+            "SELECT {} FROM project_ownership_transfers t JOIN projects p ON p.id = t.project_id \
+             WHERE t.to_user_id = $1 AND t.status = 'pending' AND t.expires_at > NOW() AND p.deleted_at IS NULL ORDER BY t.created_at DESC",
+            teams::TRANSFER_COLUMNS
+        ))
+        .bind(caller)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .iter()
+        .map(teams::transfer_from_row)
+        .collect();
+
+        Ok(Response::new(ListOwnershipTransfersResponse { transfers }))
+    }
+
+    /// The recipient becomes `projects.owner_id` and leaves `project_members`; the outgoing
+    /// owner takes their place there with the role chosen in the offer.
+    async fn accept_ownership_transfer(&self, request: Request<RespondToOwnershipTransferRequest>) -> Result<Response<Team>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let db = |e: sqlx::Error| Status::internal(format!("DB: {}", e));
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        let transfer = self.open_transfer(&mut tx, caller, &request.get_ref().id).await?;
+        let id: Uuid = transfer.get("id");
+        let project_id: Uuid = transfer.get("project_id");
+        let project_name: String = transfer.get("project_name");
+        let from_user_id: Uuid = transfer.get("from_user_id");
+        let previous_owner_role: String = transfer.get("previous_owner_role");
+
+        if transfer.get::<Uuid, _>("owner_id") != from_user_id {
+            return Err(Status::failed_precondition("The project has changed hands since this offer was made"));
+        }
+        let membership = sqlx::query("DELETE FROM project_members WHERE project_id = $1 AND user_id = $2")
+            .bind(project_id)
+            .bind(caller)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        if membership.rows_affected() == 0 {
+            return Err(Status::failed_precondition("You're no longer on this project's team"));
+        }
+        if let Some(org_id) = transfer.get::<Option<Uuid>, _>("org_id") {
+            let in_org = sqlx::query("SELECT 1 FROM organization_members WHERE org_id = $1 AND user_id = $2")
+                .bind(org_id)
+                .bind(caller)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(db)?;
+            if in_org.is_none() {
+                return Err(Status::failed_precondition("Only members of the organization can own its projects"));
+            }
+        }
+
+        sqlx::query("UPDATE projects SET owner_id = $2 WHERE id = $1")
+            .bind(project_id)
+            .bind(caller)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        sqlx::query(
+            "INSERT INTO project_members (project_id, user_id, role) VALUES ($1, $2, $3) \
+             ON CONFLICT (project_id, user_id) DO UPDATE SET role = EXCLUDED.role",
+        )
+        .bind(project_id)
+        .bind(from_user_id)
+        .bind(&previous_owner_role)
+        .execute(&mut *tx)
+        .await
+        .map_err(db)?;
+        sqlx::query("UPDATE project_ownership_transfers SET status = 'accepted', responded_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        project_events::record(
+            &mut tx,
+            project_id,
+            project_events::OWNERSHIP_TRANSFERRED,
+            caller,
+            Some(from_user_id),
+            serde_json::json!({ "transfer_id": id, "from_user_id": from_user_id, "to_user_id": caller, "previous_owner_role": previous_owner_role }),
+        )
+        .await
+        .map_err(db)?;
+        let payload = serde_json::json!({ "transfer_id": id, "project_id": project_id });
+        notifications::notify(
+            &mut tx,
+            from_user_id,
+            notifications::OWNERSHIP_TRANSFER_ACCEPTED,
+            &format!("Ownership of {} was accepted; you're now a {}", project_name, previous_owner_role),
+            payload.clone(),
+        )
+        .await
+        .map_err(db)?;
+        notifications::notify(
+            &mut tx,
+            caller,
+            notifications::OWNERSHIP_TRANSFER_ACCEPTED,
+            &format!("You're now the owner of {}", project_name),
+            payload,
+        )
+        .await
+        .map_err(db)?;
+        tx.commit().await.map_err(db)?;
+
+        let members = teams::load_team(&self.pool, project_id).await?;
+        Ok(Response::new(Team { project_id: project_id.to_string(), members }))
+    }
+
+    async fn decline_ownership_transfer(&self, request: Request<RespondToOwnershipTransferRequest>) -> Result<Response<OwnershipTransfer>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let db = |e: sqlx::Error| Status::internal(format!("DB: {}", e));
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        let transfer = self.open_transfer(&mut tx, caller, &request.get_ref().id).await?;
+        let id: Uuid = transfer.get("id");
+        let project_id: Uuid = transfer.get("project_id");
+        let from_user_id: Uuid = transfer.get("from_user_id");
+
+        sqlx::query("UPDATE project_ownership_transfers SET status = 'declined', responded_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        project_events::record(&mut tx, project_id, project_events::OWNERSHIP_TRANSFER_DECLINED, caller, Some(from_user_id), serde_json::json!({ "transfer_id": id }))
+            .await
+            .map_err(db)?;
+        notifications::notify(
+            &mut tx,
+            from_user_id,
+            notifications::OWNERSHIP_TRANSFER_DECLINED,
+            &format!("Your offer of {} was declined", transfer.get::<String, _>("project_name")),
+            serde_json::json!({ "transfer_id": id, "project_id": project_id }),
+        )
+        .await
+        .map_err(db)?;
+        tx.commit().await.map_err(db)?;
+
+        Ok(Response::new(teams::load_transfer(&self.pool, id).await?))
+    }
+}
+
+// REPUTATION SERVICE IMPLEMENTATION
+#[derive(Debug)]
+pub struct MyReputationService {
+    pool: PgPool,
+    reputation: reputation::Engine,
+}
+
+#[tonic::async_trait]
+impl ReputationService for MyReputationService {
+    async fn endorse_user(&self, request: Request<EndorseUserRequest>) -> Result<Response<EndorseUserResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let req = request.into_inner();
+        let endorser_id = if req.endorser_id.is_empty() { caller } else { Uuid::parse_str(&req.endorser_id).map_err(|_| Status::invalid_argument("Invalid Endorser UUID"))? };
+        tenant.require_self(endorser_id)?;
+        let user_id = Uuid::parse_str(&req.user_id).map_err(|_| Status::invalid_argument("Invalid User UUID"))?;
+        if endorser_id == user_id {
+            return Err(Status::invalid_argument("You can't endorse yourself"));
+        }
+
+        let endorser = sqlx::query(
+            "SELECT email_verified, created_at <= NOW() - make_interval(days => $2) AS established \
+             FROM users WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(endorser_id)
+        .bind(reputation::MIN_ENDORSER_ACCOUNT_AGE_DAYS as i32)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .ok_or_else(|| Status::not_found("Endorser not found"))?;
+        if !endorser.get::<bool, _>("email_verified") {
+            return Err(Status::failed_precondition("Verify your email address before endorsing others"));
+        }
+        if !endorser.get::<bool, _>("established") {
+            return Err(Status::failed_precondition(format!(
+                "Accounts must be at least {} days old to endorse others",
+                reputation::MIN_ENDORSER_ACCOUNT_AGE_DAYS
+            )));
+        }
+
+        // The skill may be given by name or alias, but must be one the user lists.
+        let skill = sqlx::query(
+            "SELECT s.id FROM users u \
+             JOIN user_skills us ON us.user_id = u.id \
+             JOIN skills s ON s.id = us.skill_id \
+             WHERE u.id = $1 AND u.deleted_at IS NULL \
+             AND (s.slug = skill_slug($2) OR s.id IN (SELECT skill_id FROM skill_aliases WHERE alias_slug = skill_slug($2)))",
+        )
+        .bind(user_id)
+        .bind(req.skill.trim())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .ok_or_else(|| Status::not_found("User not found or doesn't list that skill"))?;
+        let skill_id: Uuid = skill.get("id");
+
+        let (_, counted) = self.reputation.record(reputation::Event {
+            user_id,
+            kind: reputation::ENDORSEMENT,
+            points: reputation::ENDORSEMENT_POINTS,
+            source_user_id: Some(endorser_id),
+            subject_id: Some(skill_id),
+            dedupe_key: format!("{}:{}:{}:{}", reputation::ENDORSEMENT, endorser_id, user_id, skill_id),
+            replace: false,
+        }).await?;
+
+        Ok(Response::new(EndorseUserResponse { counted }))
+    }
+
+    async fn rate_project(&self, request: Request<RateProjectRequest>) -> Result<Response<RateProjectResponse>, Status> {
+        request.get_ref().validate()?;
+        let tenant = Tenant::from_request(&self.pool, &request).await?;
+        let caller = tenant.require_user()?;
+        let req = request.into_inner();
+        let investor_id = if req.investor_id.is_empty() { caller } else { Uuid::parse_str(&req.investor_id).map_err(|_| Status::invalid_argument("Invalid Investor UUID"))? };
+        tenant.require_self(investor_id)?;
+        verification::require_verified_email(&self.pool, investor_id).await?;
+        let project_id = Uuid::parse_str(&req.project_id).map_err(|_| Status::invalid_argument("Invalid Project UUID"))?;
+        if !(1..=5).contains(&req.rating) {
+            return Err(Status::invalid_argument("Rating must be between 1 and 5"));
+        }
+
+        let role: String = sqlx::query("SELECT COALESCE(role, '') AS role FROM users WHERE id = $1 AND deleted_at IS NULL")
+            .bind(investor_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?
+            .ok_or_else(|| Status::not_found("Investor not found"))?
+            .get("role");
+        if role != "investor" {
+            return Err(Status::permission_denied("Only investors can rate projects"));
+        }
+
+        // Only projects the investor can see, by the same rule as everywhere else.
+        let access = teams::access(&self.pool, &tenant, project_id).await?;
+        access.require_view()?;
+        let owner_id = access.owner_id;
+        if owner_id == investor_id {
+            return Err(Status::invalid_argument("You can't rate your own project"));
+        }
+
+        self.reputation.record(reputation::Event {
+            user_id: owner_id,
+            kind: reputation::INVESTOR_RATING,
+            points: reputation::rating_points(req.rating),
+            source_user_id: Some(investor_id),
+            subject_id: Some(project_id),
+            dedupe_key: format!("{}:{}:{}", reputation::INVESTOR_RATING, investor_id, project_id),
+            replace: true,
+        }).await?;
+
+        Ok(Response::new(RateProjectResponse {}))
+    }
+
+    async fn get_reputation_breakdown(&self, request: Request<GetReputationBreakdownRequest>) -> Result<Response<ReputationBreakdown>, Status> {
+        request.get_ref().validate()?;
+        let req = request.into_inner();
+        let user_id = Uuid::parse_str(&req.user_id).map_err(|_| Status::invalid_argument("Invalid User UUID"))?;
+
+        sqlx::query("SELECT 1 FROM users WHERE id = $1 AND deleted_at IS NULL")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?
+            .ok_or_else(|| Status::not_found("User not found"))?;
+
+        let breakdown = self.reputation.breakdown(user_id).await?;
+
+        Ok(Response::new(ReputationBreakdown {
+            user_id: user_id.to_string(),
+            score: breakdown.score,
+            components: breakdown.components.into_iter().map(|c| ReputationComponent {
+                kind: c.kind,
+                events: c.events as i32,
+                counted_events: c.counted_events as i32,
+                points: c.points,
+            }).collect(),
+            recent_events: breakdown.recent_events.into_iter().map(|e| ReputationEvent {
+                kind: e.kind,
+                base_points: e.base_points,
+                current_points: e.current_points,
+                counted: e.counted,
+                created_at: e.created_at.to_rfc3339(),
+            }).collect(),
+            computed_at: chrono::Utc::now().to_rfc3339(),
+        }))
+    }
+}
+
+#[derive(Debug)]
+pub struct MyPrivacyService {
+    pool: PgPool,
+}
+
+#[tonic::async_trait]
+impl PrivacyService for MyPrivacyService {
+    async fn export_user_content(&self, request: Request<ExportUserContentRequest>) -> Result<Response<ExportUserContentResponse>, Status> {
+        request.get_ref().validate()?;
+        let user_id = Uuid::parse_str(&request.get_ref().user_id).map_err(|_| Status::invalid_argument("Invalid User UUID"))?;
+        let files = privacy::export(&self.pool, user_id)
+            .await?
+            .into_iter()
+            .map(|(name, content_json)| ExportFile { name, content_json })
+            .collect();
+        Ok(Response::new(ExportUserContentResponse { files }))
+    }
+
+    async fn erase_user_content(&self, request: Request<EraseUserContentRequest>) -> Result<Response<EraseUserContentResponse>, Status> {
+        request.get_ref().validate()?;
+        let req = request.into_inner();
+        let user_id = Uuid::parse_str(&req.user_id).map_err(|_| Status::invalid_argument("Invalid User UUID"))?;
+        let erased = privacy::erase(&self.pool, user_id, req.delete_content).await?;
+        tracing::info!("Erased content of {}: {:?}", user_id, erased);
+        Ok(Response::new(EraseUserContentResponse {
+            ideas_deleted: erased.ideas_deleted as i32,
+            projects_deleted: erased.projects_deleted as i32,
+            tasks_unassigned: erased.tasks_unassigned as i32,
+            notifications_deleted: erased.notifications_deleted as i32,
+        }))
+    }
+}
+
 // MAIN FUNCTION
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::subscriber::set_global_default(FmtSubscriber::new())?;
     let config = config::Config::from_env().expect("Failed to load config");
     
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&config.database_url)
-        .await?;
+    let pool = db::init_pool(&config.database_url).await?;
 
     let addr = config.server_addr.parse()?;
-    let idea_service = MyIdeaService { pool: pool.clone() };
-    let task_service = MyTaskService { pool };
+    let reputation = reputation::Engine::new(pool.clone(), config.reputation_half_life_days);
+    reputation.clone().spawn_recompute_job(Duration::from_secs(config.reputation_recompute_secs));
+
+    let trash = trash::Trash::new(pool.clone(), config.trash_retention_days);
+    trash.clone().spawn_purge_job(Duration::from_secs(config.trash_purge_secs));
+
+    let duplicates = similarity::Duplicates::new(pool.clone(), config.duplicate_idea_threshold, config.block_duplicate_ideas);
+    duplicates.clone().spawn_backfill();
+
+    let idea_service = MyIdeaService { pool: pool.clone(), trash: trash.clone(), duplicates };
+    let task_service = MyTaskService { pool: pool.clone(), reputation: reputation.clone(), trash };
+    let team_service = MyTeamService { pool: pool.clone(), app_base_url: config.app_base_url.clone() };
+    let reputation_service = MyReputationService { pool: pool.clone(), reputation };
+    let privacy_service = MyPrivacyService { pool };
 
     println!("Brain Core Service listening on {}", addr);
 
     Server::builder()
         .add_service(IdeaServiceServer::new(idea_service))
         .add_service(TaskServiceServer::new(task_service))
+        .add_service(TeamServiceServer::new(team_service))
+        .add_service(ReputationServiceServer::new(reputation_service))
+        .add_service(PrivacyServiceServer::new(privacy_service))
         .serve(addr)
         .await?;
 
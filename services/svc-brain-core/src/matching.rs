@@ -0,0 +1,108 @@
+//! Collaborator ranking for an idea. Pure scoring so the weights are easy to reason about;
+//! candidate selection happens in SQL.
+
+/// Skill overlap dominates: the point is finding people who can build the thing.
+const SKILL_WEIGHT: f64 = 0.6;
+const AVAILABILITY_WEIGHT: f64 = 0.25;
+const REPUTATION_WEIGHT: f64 = 0.15;
+
+/// Reputation at which the reputation component reaches 0.5; it saturates towards 1 above.
+const REPUTATION_HALF_POINT: f64 = 50.0;
+/// Hours per week at which someone counts as fully available.
+const FULL_TIME_HOURS: f64 = 20.0;
+/// Availability assumed for users who haven't said how much time they have.
+const UNSTATED_AVAILABILITY: f64 = 0.5;
+/// Open assigned tasks at which the stated availability is halved.
+const TASK_LOAD_HALF_POINT: f64 = 5.0;
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub matched_skills: usize,
+    pub reputation: f64,
+    pub hours_per_week: Option<i32>,
+    pub open_tasks: i64,
+}
+
+/// Score in 0..=1 for a candidate against an idea requiring `required_skills` skills.
+pub fn score(candidate: &Candidate, required_skills: usize) -> f64 {
+    let skill = if required_skills == 0 {
+        0.0
+    } else {
+        (candidate.matched_skills as f64 / required_skills as f64).min(1.0)
+    };
+
+    let stated = match candidate.hours_per_week {
+        Some(hours) => (hours.max(0) as f64 / FULL_TIME_HOURS).min(1.0),
+        None => UNSTATED_AVAILABILITY,
+    };
+    let availability = stated * TASK_LOAD_HALF_POINT / (TASK_LOAD_HALF_POINT + candidate.open_tasks.max(0) as f64);
+
+    let reputation = candidate.reputation.max(0.0);
+    let reputation = reputation / (reputation + REPUTATION_HALF_POINT);
+
+    SKILL_WEIGHT * skill + AVAILABILITY_WEIGHT * availability + REPUTATION_WEIGHT * reputation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(matched_skills: usize, reputation: f64, hours_per_week: Option<i32>, open_tasks: i64) -> Candidate {
+        Candidate { matched_skills, reputation, hours_per_week, open_tasks }
+    }
+
+    #[test]
+    fn score_stays_between_zero_and_one() {
+        let best = score(&candidate(3, 1e9, Some(80), 0), 3);
+        let worst = score(&candidate(0, -10.0, Some(-5), 1_000), 3);
+        assert!(best <= 1.0 && best > 0.99, "{}", best);
+        assert!((0.0..0.01).contains(&worst), "{}", worst);
+    }
+
+    #[test]
+    fn skill_overlap_is_the_share_of_required_skills() {
+        let none = score(&candidate(0, 0.0, Some(0), 0), 4);
+        let half = score(&candidate(2, 0.0, Some(0), 0), 4);
+        let all = score(&candidate(4, 0.0, Some(0), 0), 4);
+        assert_eq!(none, 0.0);
+        assert!((half - SKILL_WEIGHT / 2.0).abs() < 1e-9);
+        assert!((all - SKILL_WEIGHT).abs() < 1e-9);
+        // Extra matches beyond what the idea needs don't count twice.
+        assert_eq!(score(&candidate(6, 0.0, Some(0), 0), 4), all);
+    }
+
+    #[test]
+    fn ideas_without_skills_rank_on_availability_and_reputation() {
+        let c = candidate(2, 50.0, Some(20), 0);
+        assert!((score(&c, 0) - (AVAILABILITY_WEIGHT + REPUTATION_WEIGHT * 0.5)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn skills_outrank_availability_and_reputation() {
+        let builder = candidate(3, 0.0, Some(0), 10);
+        let famous_and_free = candidate(1, 1_000.0, Some(40), 0);
+        assert!(score(&builder, 3) > score(&famous_and_free, 3));
+    }
+
+    #[test]
+    fn ordering_follows_each_component() {
+        let base = candidate(1, 10.0, Some(10), 2);
+        let ranked = |c: Candidate| score(&c, 3) > score(&base, 3);
+        assert!(ranked(candidate(2, 10.0, Some(10), 2)), "more matched skills");
+        assert!(ranked(candidate(1, 40.0, Some(10), 2)), "more reputation");
+        assert!(ranked(candidate(1, 10.0, Some(15), 2)), "more hours");
+        assert!(ranked(candidate(1, 10.0, Some(10), 0)), "fewer open tasks");
+    }
+
+    #[test]
+    fn unstated_hours_count_as_half_available() {
+        assert_eq!(score(&candidate(0, 0.0, None, 0), 1), score(&candidate(0, 0.0, Some(10), 0), 1));
+    }
+
+    #[test]
+    fn open_tasks_halve_availability_at_the_half_point() {
+        let free = score(&candidate(0, 0.0, Some(20), 0), 1);
+        let busy = score(&candidate(0, 0.0, Some(20), TASK_LOAD_HALF_POINT as i64), 1);
+        assert!((busy - free / 2.0).abs() < 1e-9);
+    }
+}
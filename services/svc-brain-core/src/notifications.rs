@@ -0,0 +1,32 @@
+//! In-app notifications written by this service alongside the change they report, in the
+//! same transaction, so a rolled-back change never leaves a notification behind.
+
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+pub const PROJECT_INVITATION: &str = "project_invitation";
+pub const INVITATION_ACCEPTED: &str = "invitation_accepted";
+pub const INVITATION_DECLINED: &str = "invitation_declined";
+pub const OWNERSHIP_TRANSFER_OFFERED: &str = "ownership_transfer_offered";
+pub const OWNERSHIP_TRANSFER_ACCEPTED: &str = "ownership_transfer_accepted";
+pub const OWNERSHIP_TRANSFER_DECLINED: &str = "ownership_transfer_declined";
+pub const IDEA_COMMENT: &str = "idea_comment";
+pub const COMMENT_REPLY: &str = "comment_reply";
+
+pub async fn notify(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    kind: &str,
+    content: &str,
+    payload: serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO notifications (id, user_id, type, content, payload) VALUES ($1, $2, $3, $4, $5)")
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(kind)
+        .bind(content)
+        .bind(payload)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,183 @@
+//! This service's half of data export and erasure. svc-identity drives both and owns the
+//! request status; it calls in here for the ideas, projects, tasks and activity stored on
+//! this side.
+
+use sqlx::{PgPool, Row};
+use tonic::Status;
+use uuid::Uuid;
+use crate::votes;
+
+/// One JSON document per file. Each query returns a single JSON array.
+const EXPORTS: [(&str, &str); 15] = [
+    (
+        "ideas.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(i) || jsonb_build_object('required_skills', \
+             (SELECT COALESCE(jsonb_agg(s.name ORDER BY s.name), '[]') FROM idea_skills x JOIN skills s ON s.id = x.skill_id WHERE x.idea_id = i.id)) \
+         ORDER BY i.created_at), '[]') FROM ideas i WHERE i.creator_id = $1",
+    ),
+    (
+        "idea_revisions.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(r) ORDER BY r.created_at), '[]') FROM idea_revisions r WHERE r.author_id = $1",
+    ),
+    (
+        "idea_upvotes.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(v) ORDER BY v.created_at), '[]') FROM idea_votes v WHERE v.user_id = $1",
+    ),
+    (
+        "idea_bookmarks.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(b) ORDER BY b.created_at), '[]') FROM idea_bookmarks b WHERE b.user_id = $1",
+    ),
+    (
+        "idea_comments.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(c) ORDER BY c.created_at), '[]') FROM idea_comments c WHERE c.author_id = $1",
+    ),
+    (
+        "idea_comment_reactions.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(r) ORDER BY r.created_at), '[]') FROM idea_comment_reactions r WHERE r.user_id = $1",
+    ),
+    (
+        "projects.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(p) ORDER BY p.created_at), '[]') FROM projects p WHERE p.owner_id = $1",
+    ),
+    (
+        // Tasks in the user's own projects and tasks they were assigned elsewhere.
+        "tasks.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(t) ORDER BY t.created_at), '[]') FROM tasks t \
+         WHERE t.assignee_id = $1 OR t.project_id IN (SELECT id FROM projects WHERE owner_id = $1)",
+    ),
+    (
+        "project_memberships.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(m) ORDER BY m.created_at), '[]') FROM project_members m WHERE m.user_id = $1",
+    ),
+    (
+        // Invitations the user sent or answered; link tokens are left out.
+        "invitations.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(i) - 'token_hash' ORDER BY i.created_at), '[]') FROM project_invitations i \
+         WHERE i.invited_by = $1 OR i.invitee_id = $1",
+    ),
+    (
+        "ownership_transfers.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(t) ORDER BY t.created_at), '[]') FROM project_ownership_transfers t \
+         WHERE t.from_user_id = $1 OR t.to_user_id = $1",
+    ),
+    (
+        "project_events.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(e) ORDER BY e.created_at), '[]') FROM project_events e \
+         WHERE e.actor_id = $1 OR e.user_id = $1",
+    ),
+    (
+        "notifications.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(n) ORDER BY n.created_at), '[]') FROM notifications n WHERE n.user_id = $1",
+    ),
+    (
+        "reputation_received.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(e) - 'dedupe_key' ORDER BY e.created_at), '[]') FROM reputation_events e WHERE e.user_id = $1",
+    ),
+    (
+        // Endorsements and ratings the user gave; these stay with the recipient after erasure.
+        "reputation_given.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(e) - 'dedupe_key' ORDER BY e.created_at), '[]') FROM reputation_events e WHERE e.source_user_id = $1",
+    ),
+];
+
+pub async fn export(pool: &PgPool, user_id: Uuid) -> Result<Vec<(String, String)>, Status> {
+    let mut files = Vec::with_capacity(EXPORTS.len());
+    for (name, query) in EXPORTS {
+        let document: serde_json::Value = sqlx::query(query)
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?
+            .get(0);
+        files.push((name.to_string(), document.to_string()));
+    }
+    Ok(files)
+}
+
+#[derive(Debug, Default)]
+pub struct Erased {
+    pub ideas_deleted: u64,
+    pub projects_deleted: u64,
+    pub tasks_unassigned: u64,
+    pub notifications_deleted: u64,
+}
+
+/// Removes what identifies the user. Public work stays up, attributed to the anonymized
+/// account (svc-identity has already replaced the name), unless `delete_content` is set;
+/// private projects always go. Ideas and projects in an organization belong to it and are
+/// kept either way. Repeating it is harmless.
+pub async fn erase(pool: &PgPool, user_id: Uuid, delete_content: bool) -> Result<Erased, Status> {
+    let db = |e: sqlx::Error| Status::internal(format!("DB: {}", e));
+    let mut tx = pool.begin().await.map_err(db)?;
+    let mut erased = Erased::default();
+
+    // Tasks cascade with their project.
+    let projects = if delete_content {
+        "DELETE FROM projects WHERE owner_id = $1 AND org_id IS NULL"
+    } else {
+        "DELETE FROM projects WHERE owner_id = $1 AND org_id IS NULL AND NOT COALESCE(is_public, FALSE)"
+    };
+    erased.projects_deleted = sqlx::query(projects).bind(user_id).execute(&mut *tx).await.map_err(db)?.rows_affected();
+
+    if delete_content {
+        // idea_skills cascade with the idea.
+        erased.ideas_deleted = sqlx::query("DELETE FROM ideas WHERE creator_id = $1 AND org_id IS NULL")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?
+            .rows_affected();
+        // Comments keep their place in threads elsewhere, deleted and emptied.
+        sqlx::query("UPDATE idea_comments SET body = '', deleted_at = COALESCE(deleted_at, NOW()) WHERE author_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+    }
+
+    erased.tasks_unassigned = sqlx::query("UPDATE tasks SET assignee_id = NULL WHERE assignee_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db)?
+        .rows_affected();
+    // Counts on the ideas and comments drop with them.
+    for table in [votes::VOTES, votes::BOOKMARKS, "idea_comment_reactions"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE user_id = $1", table))
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+    }
+    sqlx::query("DELETE FROM project_members WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db)?;
+    // Invitations hold email addresses, the invitee's or the ones the user typed in.
+    sqlx::query("DELETE FROM project_invitations WHERE invited_by = $1 OR invitee_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db)?;
+    sqlx::query("DELETE FROM project_ownership_transfers WHERE from_user_id = $1 OR to_user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db)?;
+    erased.notifications_deleted = sqlx::query("DELETE FROM notifications WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db)?
+        .rows_affected();
+    // Points the user earned; endorsements and ratings they gave belong to the recipients.
+    sqlx::query("DELETE FROM reputation_events WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db)?;
+
+    tx.commit().await.map_err(db)?;
+    Ok(erased)
+}
@@ -0,0 +1,31 @@
+//! Append-only history of changes to who runs a project (`project_events`), written in the
+//! same transaction as the change.
+
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+pub const OWNERSHIP_TRANSFER_REQUESTED: &str = "ownership_transfer_requested";
+pub const OWNERSHIP_TRANSFER_CANCELLED: &str = "ownership_transfer_cancelled";
+pub const OWNERSHIP_TRANSFER_DECLINED: &str = "ownership_transfer_declined";
+pub const OWNERSHIP_TRANSFERRED: &str = "ownership_transferred";
+
+/// `user_id` is who the change was about, when that isn't the actor.
+pub async fn record(
+    tx: &mut Transaction<'_, Postgres>,
+    project_id: Uuid,
+    event_type: &str,
+    actor_id: Uuid,
+    user_id: Option<Uuid>,
+    details: serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO project_events (id, project_id, event_type, actor_id, user_id, details) VALUES ($1, $2, $3, $4, $5, $6)")
+        .bind(Uuid::new_v4())
+        .bind(project_id)
+        .bind(event_type)
+        .bind(actor_id)
+        .bind(user_id)
+        .bind(details)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
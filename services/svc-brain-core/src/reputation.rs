@@ -0,0 +1,292 @@
+//! Reputation is derived, never edited: actions append events to `reputation_events`
+//! and `users.reputation_score` is recomputed from them. Old events decay with a
+//! configurable half-life and the caps below stop any one day or any one person from
+//! inflating a score.
+
+use sqlx::{PgPool, Row};
+use std::time::Duration;
+use tonic::Status;
+use uuid::Uuid;
+
+pub const TASK_COMPLETED: &str = "task_completed";
+pub const IDEA_LAUNCHED: &str = "idea_launched";
+pub const ENDORSEMENT: &str = "endorsement";
+pub const INVESTOR_RATING: &str = "investor_rating";
+
+/// Points for finishing a task in someone else's project.
+pub const TASK_COMPLETED_POINTS: f64 = 5.0;
+/// Finishing tasks in your own project is worth less: you also decide what a task is.
+pub const OWN_TASK_COMPLETED_POINTS: f64 = 2.0;
+pub const IDEA_LAUNCHED_POINTS: f64 = 20.0;
+pub const ENDORSEMENT_POINTS: f64 = 3.0;
+/// Per star away from a neutral 3, so ratings range from -8 to +8.
+pub const RATING_POINTS_PER_STAR: f64 = 4.0;
+
+/// An investor's 1-5 star rating as points: 3 stars is neutral.
+pub fn rating_points(stars: i32) -> f64 {
+    (stars - 3) as f64 * RATING_POINTS_PER_STAR
+}
+
+/// Endorsers must have been around this long, so fresh sock puppets can't vouch for anyone.
+pub const MIN_ENDORSER_ACCOUNT_AGE_DAYS: i64 = 7;
+
+struct Policy {
+    kind: &'static str,
+    /// Events of this kind counted per user per UTC day; the rest are kept but ignored.
+    daily_cap: i32,
+    /// Events of this kind counted from any single source user, ever.
+    per_source_cap: i32,
+}
+
+const POLICIES: [Policy; 4] = [
+    Policy { kind: TASK_COMPLETED, daily_cap: 10, per_source_cap: i32::MAX },
+    Policy { kind: IDEA_LAUNCHED, daily_cap: 3, per_source_cap: i32::MAX },
+    Policy { kind: ENDORSEMENT, daily_cap: 10, per_source_cap: 3 },
+    Policy { kind: INVESTOR_RATING, daily_cap: 5, per_source_cap: 1 },
+];
+
+/// Every event with whether it counts after caps and its decayed value.
+/// $1: user filter (NULL for everyone), $2: half-life in days, $3..$5: the policy table.
+const SCORED_EVENTS: &str = "\
+    WITH ranked AS ( \
+        SELECT e.*, \
+            ROW_NUMBER() OVER (PARTITION BY e.user_id, e.kind, date_trunc('day', e.created_at AT TIME ZONE 'UTC') \
+                               ORDER BY e.created_at, e.id) AS day_rank, \
+            ROW_NUMBER() OVER (PARTITION BY e.user_id, e.kind, e.source_user_id ORDER BY e.created_at, e.id) AS source_rank \
+        FROM reputation_events e \
+        WHERE $1::UUID IS NULL OR e.user_id = $1 \
+    ), scored AS ( \
+        SELECT r.id, r.user_id, r.kind, r.points, r.created_at, \
+            (r.day_rank <= c.daily_cap AND (r.source_user_id IS NULL OR r.source_rank <= c.per_source_cap)) AS counted, \
+            r.points * POWER(0.5, EXTRACT(EPOCH FROM (NOW() - r.created_at)) / 86400.0 / $2) AS decayed \
+        FROM ranked r \
+        JOIN UNNEST($3::TEXT[], $4::INT[], $5::INT[]) AS c(kind, daily_cap, per_source_cap) ON c.kind = r.kind \
+    ) ";
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub user_id: Uuid,
+    pub kind: &'static str,
+    pub points: f64,
+    pub source_user_id: Option<Uuid>,
+    pub subject_id: Option<Uuid>,
+    /// Identifies the real-world action; recording the same key twice is a no-op
+    /// (or an update, with `replace`).
+    pub dedupe_key: String,
+    pub replace: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Engine {
+    pool: PgPool,
+    half_life_days: f64,
+}
+
+pub struct Component {
+    pub kind: String,
+    pub events: i64,
+    pub counted_events: i64,
+    pub points: f64,
+}
+
+pub struct ScoredEvent {
+    pub kind: String,
+    pub base_points: f64,
+    pub current_points: f64,
+    pub counted: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub struct Breakdown {
+    pub score: f64,
+    pub components: Vec<Component>,
+    pub recent_events: Vec<ScoredEvent>,
+}
+
+impl Engine {
+    pub fn new(pool: PgPool, half_life_days: f64) -> Self {
+        Self { pool, half_life_days }
+    }
+
+    fn scored_query<'q>(&self, sql: &'q str, user_id: Option<Uuid>) -> sqlx::query::Query<'q, sqlx::Postgres, sqlx::postgres::PgArguments> {
+        sqlx::query(sql)
+            .bind(user_id)
+            .bind(self.half_life_days)
+            .bind(POLICIES.iter().map(|p| p.kind).collect::<Vec<_>>())
+            .bind(POLICIES.iter().map(|p| p.daily_cap).collect::<Vec<_>>())
+            .bind(POLICIES.iter().map(|p| p.per_source_cap).collect::<Vec<_>>())
+    }
+
+    /// Appends an event and refreshes the user's score. Returns the event id and
+    /// whether it counts towards the score.
+    pub async fn record(&self, event: Event) -> Result<(Uuid, bool), Status> {
+        if event.source_user_id == Some(event.user_id) {
+            return Err(Status::invalid_argument("You can't award reputation to yourself"));
+        }
+
+        let conflict = if event.replace {
+            "ON CONFLICT (dedupe_key) DO UPDATE SET points = EXCLUDED.points"
+        } else {
+            "ON CONFLICT (dedupe_key) DO UPDATE SET dedupe_key = EXCLUDED.dedupe_key"
+        };
+        let event_id: Uuid = sqlx::query(&format!(
+            "INSERT INTO reputation_events (id, user_id, kind, points, source_user_id, subject_id, dedupe_key) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7) {} RETURNING id",
+            conflict
+        ))
+        .bind(Uuid::new_v4())
+        .bind(event.user_id)
+        .bind(event.kind)
+        .bind(event.points)
+        .bind(event.source_user_id)
+        .bind(event.subject_id)
+        .bind(&event.dedupe_key)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .get("id");
+
+        self.recompute(Some(event.user_id)).await.map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+        let counted: bool = self
+            .scored_query(&format!("{} SELECT counted FROM scored WHERE id = $6", SCORED_EVENTS), Some(event.user_id))
+            .bind(event_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?
+            .is_some_and(|row| row.get("counted"));
+
+        Ok((event_id, counted))
+    }
+
+    /// Records an event from a side effect of another action (task done, idea launched).
+    /// Reputation is secondary there, so failures are logged instead of failing the action.
+    pub async fn record_quietly(&self, event: Event) {
+        let kind = event.kind;
+        let user_id = event.user_id;
+        if let Err(e) = self.record(event).await {
+            tracing::error!("Failed to record {} reputation for {}: {}", kind, user_id, e.message());
+        }
+    }
+
+    /// Recomputes one user's score, or everyone's with `None`. Negative totals floor at 0.
+    pub async fn recompute(&self, user_id: Option<Uuid>) -> Result<u64, sqlx::Error> {
+        let sql = format!(
+            "{} UPDATE users u SET reputation_score = t.score, reputation_updated_at = NOW() \
+             FROM (SELECT ids.user_id, GREATEST(0, COALESCE(SUM(s.decayed) FILTER (WHERE s.counted), 0)) AS score \
+                   FROM (SELECT DISTINCT user_id FROM reputation_events WHERE $1::UUID IS NULL OR user_id = $1) ids \
+                   LEFT JOIN scored s ON s.user_id = ids.user_id \
+                   GROUP BY ids.user_id) t \
+             WHERE u.id = t.user_id",
+            SCORED_EVENTS
+        );
+        Ok(self.scored_query(&sql, user_id).execute(&self.pool).await?.rows_affected())
+    }
+
+    pub async fn breakdown(&self, user_id: Uuid) -> Result<Breakdown, Status> {
+        let components = self
+            .scored_query(
+                &format!(
+                    "{} SELECT kind, COUNT(*) AS events, COUNT(*) FILTER (WHERE counted) AS counted_events, \
+                     COALESCE(SUM(decayed) FILTER (WHERE counted), 0) AS points \
+                     FROM scored GROUP BY kind ORDER BY points DESC",
+                    SCORED_EVENTS
+                ),
+                Some(user_id),
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?
+            .into_iter()
+            .map(|row| Component {
+                kind: row.get("kind"),
+                events: row.get("events"),
+                counted_events: row.get("counted_events"),
+                points: row.get("points"),
+            })
+            .collect::<Vec<_>>();
+
+        let recent_events = self
+            .scored_query(
+                &format!("{} SELECT kind, points, decayed, counted, created_at FROM scored ORDER BY created_at DESC LIMIT 20", SCORED_EVENTS),
+                Some(user_id),
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB: {}", e)))?
+            .into_iter()
+            .map(|row| {
+                let counted: bool = row.get("counted");
+                ScoredEvent {
+                    kind: row.get("kind"),
+                    base_points: row.get("points"),
+                    current_points: if counted { row.get("decayed") } else { 0.0 },
+                    counted,
+                    created_at: row.get("created_at"),
+                }
+            })
+            .collect();
+
+        let score = components.iter().map(|c| c.points).sum::<f64>().max(0.0);
+        Ok(Breakdown { score, components, recent_events })
+    }
+
+    /// Decay changes scores even when nothing happens, so everyone is recomputed periodically.
+    pub fn spawn_recompute_job(self, every: Duration) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                match self.recompute(None).await {
+                    Ok(updated) => tracing::info!("Recomputed reputation for {} users", updated),
+                    Err(e) => tracing::error!("Reputation recompute failed: {}", e),
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> Engine {
+        // Never connects; the tests only reach code that runs before any query.
+        Engine::new(sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap(), 180.0)
+    }
+
+    #[test]
+    fn ratings_are_centred_on_three_stars() {
+        assert_eq!(rating_points(1), -8.0);
+        assert_eq!(rating_points(3), 0.0);
+        assert_eq!(rating_points(4), 4.0);
+        assert_eq!(rating_points(5), 8.0);
+    }
+
+    #[test]
+    fn every_event_kind_is_capped() {
+        for kind in [TASK_COMPLETED, IDEA_LAUNCHED, ENDORSEMENT, INVESTOR_RATING] {
+            assert!(POLICIES.iter().any(|p| p.kind == kind), "{} has no policy", kind);
+        }
+        let rating = POLICIES.iter().find(|p| p.kind == INVESTOR_RATING).unwrap();
+        assert_eq!(rating.per_source_cap, 1);
+    }
+
+    #[tokio::test]
+    async fn nobody_awards_reputation_to_themselves() {
+        let user_id = Uuid::new_v4();
+        let err = engine()
+            .record(Event {
+                user_id,
+                kind: ENDORSEMENT,
+                points: ENDORSEMENT_POINTS,
+                source_user_id: Some(user_id),
+                subject_id: None,
+                dedupe_key: "endorsement:self".to_string(),
+                replace: false,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+}
@@ -0,0 +1,211 @@
+//! Idea revision history. Each change to an idea stores a full snapshot in `idea_revisions`
+//! and bumps `ideas.revision`; what changed is worked out against the snapshot before, so
+//! restoring an old revision is just another edit.
+
+use shared_proto::idea::{FieldChange, IdeaRevision, IdeaRevisionSummary};
+use sqlx::{PgConnection, Row};
+use tonic::Status;
+use uuid::Uuid;
+
+pub const FIELD_TITLE: &str = "title";
+pub const FIELD_PROBLEM: &str = "problem";
+pub const FIELD_SOLUTION: &str = "solution";
+pub const FIELD_REQUIRED_SKILLS: &str = "required_skills";
+
+pub const SUMMARY_COLUMNS: &str = "r.revision, r.author_id, r.changed_fields, r.restored_from, r.created_at";
+
+#[derive(Debug)]
+struct Snapshot {
+    title: String,
+    problem: String,
+    solution: String,
+    required_skills: Vec<String>,
+}
+
+impl Snapshot {
+    fn from_row(row: &sqlx::postgres::PgRow) -> Self {
+        Self {
+            title: row.get("title"),
+            problem: row.get("problem"),
+            solution: row.get("solution"),
+            required_skills: row.get("required_skills"),
+        }
+    }
+
+    fn fields(&self) -> [(&'static str, String); 4] {
+        [
+            (FIELD_TITLE, self.title.clone()),
+            (FIELD_PROBLEM, self.problem.clone()),
+            (FIELD_SOLUTION, self.solution.clone()),
+            (FIELD_REQUIRED_SKILLS, self.required_skills.join(", ")),
+        ]
+    }
+}
+
+/// What changed from `before` to `after`; everything, for the first revision.
+fn changes(before: Option<&Snapshot>, after: &Snapshot) -> Vec<FieldChange> {
+    let before = before.map(Snapshot::fields);
+    after
+        .fields()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, (field, value))| {
+            let old = before.as_ref().map(|b| b[i].1.clone()).unwrap_or_default();
+            (before.is_none() || old != value).then(|| FieldChange { field: field.to_string(), before: old, after: value })
+        })
+        .collect()
+}
+
+/// Snapshots the idea as it now stands, within the transaction that changed it. Returns the
+/// new revision number, or `None` when nothing differs from the latest revision.
+pub async fn record(
+    conn: &mut PgConnection,
+    idea_id: Uuid,
+    author_id: Uuid,
+    restored_from: Option<i32>,
+) -> Result<Option<i32>, Status> {
+    let db = |e: sqlx::Error| Status::internal(format!("DB: {}", e));
+    let current = sqlx::query(
+        "SELECT i.title, i.problem, i.solution, \
+         ARRAY(SELECT s.name FROM idea_skills x JOIN skills s ON s.id = x.skill_id WHERE x.idea_id = i.id ORDER BY s.name)::TEXT[] AS required_skills \
+         FROM ideas i WHERE i.id = $1",
+    )
+    .bind(idea_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(db)?;
+    let current = Snapshot::from_row(&current);
+
+    let latest = sqlx::query(
+        "SELECT revision, title, problem, solution, required_skills FROM idea_revisions \
+         WHERE idea_id = $1 ORDER BY revision DESC LIMIT 1",
+    )
+    .bind(idea_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(db)?;
+    let previous = latest.as_ref().map(Snapshot::from_row);
+    let changed = changes(previous.as_ref(), &current);
+    if changed.is_empty() {
+        return Ok(None);
+    }
+
+    let revision = latest.map(|row| row.get::<i32, _>("revision") + 1).unwrap_or(1);
+    let changed_fields: Vec<String> = changed.into_iter().map(|c| c.field).collect();
+    sqlx::query(
+        "INSERT INTO idea_revisions (idea_id, revision, author_id, title, problem, solution, required_skills, changed_fields, restored_from) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+    )
+    .bind(idea_id)
+    .bind(revision)
+    .bind(author_id)
+    .bind(&current.title)
+    .bind(&current.problem)
+    .bind(&current.solution)
+    .bind(&current.required_skills)
+    .bind(&changed_fields)
+    .bind(restored_from)
+    .execute(&mut *conn)
+    .await
+    .map_err(db)?;
+    sqlx::query("UPDATE ideas SET revision = $2 WHERE id = $1")
+        .bind(idea_id)
+        .bind(revision)
+        .execute(&mut *conn)
+        .await
+        .map_err(db)?;
+
+    Ok(Some(revision))
+}
+
+pub fn summary_from_row(row: &sqlx::postgres::PgRow) -> IdeaRevisionSummary {
+    IdeaRevisionSummary {
+        revision: row.get("revision"),
+        author_id: row.get::<Option<Uuid>, _>("author_id").map(|u| u.to_string()).unwrap_or_default(),
+        changed_fields: row.get("changed_fields"),
+        restored_from: row.get::<Option<i32>, _>("restored_from").unwrap_or(0),
+        created_at: row
+            .get::<Option<chrono::DateTime<chrono::Utc>>, _>("created_at")
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default(),
+    }
+}
+
+/// One revision with its changes from the one before.
+pub async fn load(conn: &mut PgConnection, idea_id: Uuid, revision: i32) -> Result<IdeaRevision, Status> {
+    let rows = sqlx::query(&format!(
+        "SELECT {}, r.title, r.problem, r.solution, r.required_skills FROM idea_revisions r \
+         WHERE r.idea_id = $1 AND r.revision <= $2 ORDER BY r.revision DESC LIMIT 2",
+        SUMMARY_COLUMNS
+    ))
+    .bind(idea_id)
+    .bind(revision)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+    let row = rows
+        .first()
+        .filter(|row| row.get::<i32, _>("revision") == revision)
+        .ok_or_else(|| Status::not_found("Revision not found"))?;
+    let snapshot = Snapshot::from_row(row);
+    let previous = rows.get(1).map(Snapshot::from_row);
+
+    Ok(IdeaRevision {
+        summary: Some(summary_from_row(row)),
+        changes: changes(previous.as_ref(), &snapshot),
+        title: snapshot.title,
+        problem: snapshot.problem,
+        solution: snapshot.solution,
+        required_skills: snapshot.required_skills,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(title: &str, skills: &[&str]) -> Snapshot {
+        Snapshot {
+            title: title.to_string(),
+            problem: "Too much food waste".to_string(),
+            solution: "Share leftovers".to_string(),
+            required_skills: skills.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn fields(changes: &[FieldChange]) -> Vec<&str> {
+        changes.iter().map(|c| c.field.as_str()).collect()
+    }
+
+    #[test]
+    fn first_revision_lists_every_field() {
+        let changed = changes(None, &snapshot("Leftovers", &["rust"]));
+        assert_eq!(fields(&changed), [FIELD_TITLE, FIELD_PROBLEM, FIELD_SOLUTION, FIELD_REQUIRED_SKILLS]);
+        assert!(changed.iter().all(|c| c.before.is_empty()));
+    }
+
+    #[test]
+    fn lists_only_what_differs() {
+        let before = snapshot("Leftovers", &["design", "rust"]);
+        let changed = changes(Some(&before), &snapshot("Leftover swap", &["design", "rust"]));
+        assert_eq!(fields(&changed), [FIELD_TITLE]);
+        assert_eq!(changed[0].before, "Leftovers");
+        assert_eq!(changed[0].after, "Leftover swap");
+    }
+
+    #[test]
+    fn skills_compare_as_a_joined_list() {
+        let before = snapshot("Leftovers", &["rust"]);
+        let changed = changes(Some(&before), &snapshot("Leftovers", &["design", "rust"]));
+        assert_eq!(fields(&changed), [FIELD_REQUIRED_SKILLS]);
+        assert_eq!(changed[0].before, "rust");
+        assert_eq!(changed[0].after, "design, rust");
+    }
+
+    #[test]
+    fn unchanged_snapshot_has_no_changes() {
+        let before = snapshot("Leftovers", &["rust"]);
+        assert!(changes(Some(&before), &snapshot("Leftovers", &["rust"])).is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+//! Near-duplicate detection for ideas. An idea's title, problem and solution are cut into
+//! word shingles and summarised as a MinHash signature, kept on `ideas.minhash`. The signature
+//! is also split into bands (locality-sensitive hashing): ideas sharing a band bucket are the
+//! candidates, ranked by how many signature values they share, which estimates the Jaccard
+//! similarity of their shingle sets.
+
+use shared_proto::idea::SimilarIdea;
+use sqlx::{PgConnection, PgPool, Row};
+use std::collections::HashSet;
+use tonic::Status;
+use uuid::Uuid;
+
+const SHINGLE_WORDS: usize = 3;
+const HASHES: usize = 128;
+/// 32 bands of 4 rows: a pair at 0.5 similarity shares a band ~87% of the time, one at 0.2 ~5%.
+const BANDS: usize = 32;
+const ROWS: usize = HASHES / BANDS;
+
+pub const DEFAULT_LIMIT: usize = 5;
+pub const MAX_LIMIT: usize = 20;
+
+#[derive(Debug, Clone)]
+pub struct Duplicates {
+    pool: PgPool,
+    /// Estimated similarity from which an idea counts as a likely duplicate.
+    pub threshold: f64,
+    /// Refuse likely duplicates at creation rather than only flagging them.
+    pub block: bool,
+}
+
+/// The text an idea's signature is taken over.
+pub fn text(title: &str, problem: &str, solution: &str) -> String {
+    format!("{}\n{}\n{}", title, problem, solution)
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, b| (h ^ *b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// splitmix64's finalizer; `mix(x ^ seed)` stands in for one random hash function per seed.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Hashes of the runs of `SHINGLE_WORDS` lowercased words; shorter texts are one shingle.
+fn shingles(text: &str) -> HashSet<u64> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.is_empty() {
+        return HashSet::new();
+    }
+    words
+        .windows(SHINGLE_WORDS.min(words.len()))
+        .map(|w| fnv1a(w.join(" ").as_bytes()))
+        .collect()
+}
+
+/// Empty for a text without words, which then matches nothing.
+pub fn signature(text: &str) -> Vec<i64> {
+    let shingles = shingles(text);
+    if shingles.is_empty() {
+        return Vec::new();
+    }
+    (0..HASHES as u64)
+        .map(|i| {
+            let seed = mix(i + 1);
+            shingles.iter().map(|s| mix(s ^ seed)).min().unwrap_or(0) as i64
+        })
+        .collect()
+}
+
+fn buckets(signature: &[i64]) -> Vec<i64> {
+    signature
+        .chunks(ROWS)
+        .map(|rows| fnv1a(&rows.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<u8>>()) as i64)
+        .collect()
+}
+
+fn similarity(a: &[i64], b: &[i64]) -> f64 {
+    if a.len() != HASHES || b.len() != HASHES {
+        return 0.0;
+    }
+    a.iter().zip(b).filter(|(x, y)| x == y).count() as f64 / HASHES as f64
+}
+
+/// Stores the idea's signature and band buckets from its current text, within the
+/// transaction that changed it.
+pub async fn index(conn: &mut PgConnection, idea_id: Uuid) -> Result<(), Status> {
+    let db = |e: sqlx::Error| Status::internal(format!("DB: {}", e));
+    let row = sqlx::query("SELECT title, problem, solution FROM ideas WHERE id = $1")
+        .bind(idea_id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(db)?;
+    let signature = signature(&text(row.get("title"), row.get("problem"), row.get("solution")));
+
+    sqlx::query("UPDATE ideas SET minhash = $2 WHERE id = $1")
+        .bind(idea_id)
+        .bind(&signature)
+        .execute(&mut *conn)
+        .await
+        .map_err(db)?;
+    sqlx::query("DELETE FROM idea_minhash_bands WHERE idea_id = $1")
+        .bind(idea_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(db)?;
+    sqlx::query(
+        "INSERT INTO idea_minhash_bands (idea_id, band, bucket) \
+         SELECT $1, (q.band - 1)::SMALLINT, q.bucket FROM UNNEST($2::BIGINT[]) WITH ORDINALITY AS q(bucket, band)",
+    )
+    .bind(idea_id)
+    .bind(buckets(&signature))
+    .execute(&mut *conn)
+    .await
+    .map_err(db)?;
+    Ok(())
+}
+
+impl Duplicates {
+    pub fn new(pool: PgPool, threshold: f64, block: bool) -> Self {
+        Self { pool, threshold, block }
+    }
+
+    /// Live ideas visible in the workspace whose text is at least `threshold` similar to
+    /// `text`, most similar first, leaving out `exclude`.
+    pub async fn find(&self, text: &str, org_id: Option<Uuid>, exclude: Option<Uuid>, limit: usize) -> Result<Vec<SimilarIdea>, Status> {
+        let signature = signature(text);
+        if signature.is_empty() {
+            return Ok(Vec::new());
+        }
+        let rows = sqlx::query(&format!(
+            "SELECT i.id, i.title, i.creator_id, i.minhash FROM ideas i \
+             WHERE i.id IN (SELECT b.idea_id FROM idea_minhash_bands b \
+                            JOIN UNNEST($1::BIGINT[]) WITH ORDINALITY AS q(bucket, band) ON b.band = q.band - 1 AND b.bucket = q.bucket) \
+             AND i.id IS DISTINCT FROM $3 AND {}",
+            crate::IDEA_VISIBLE
+        ))
+        .bind(buckets(&signature))
+        .bind(org_id)
+        .bind(exclude)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+        let mut similar: Vec<SimilarIdea> = rows
+            .iter()
+            .map(|row| SimilarIdea {
+                id: row.get::<Uuid, _>("id").to_string(),
+                title: row.get("title"),
+                creator_id: row.get::<Uuid, _>("creator_id").to_string(),
+                similarity: similarity(&signature, &row.get::<Option<Vec<i64>>, _>("minhash").unwrap_or_default()),
+            })
+            .filter(|idea| idea.similarity >= self.threshold)
+            .collect();
+        similar.sort_by(|a, b| b.similarity.total_cmp(&a.similarity).then_with(|| a.id.cmp(&b.id)));
+        similar.truncate(limit);
+        Ok(similar)
+    }
+
+    /// Signs ideas posted before signatures were kept, so they can be found too.
+    pub fn spawn_backfill(self) {
+        tokio::spawn(async move {
+            let ids: Vec<Uuid> = match sqlx::query("SELECT id FROM ideas WHERE minhash IS NULL").fetch_all(&self.pool).await {
+                Ok(rows) => rows.iter().map(|row| row.get("id")).collect(),
+                Err(e) => return tracing::error!("Idea signature backfill failed: {}", e),
+            };
+            for id in &ids {
+                let result = match self.pool.acquire().await {
+                    Ok(mut conn) => index(&mut conn, *id).await,
+                    Err(e) => Err(Status::internal(e.to_string())),
+                };
+                if let Err(e) = result {
+                    return tracing::error!("Idea signature backfill failed: {}", e.message());
+                }
+            }
+            if !ids.is_empty() {
+                tracing::info!("Computed similarity signatures for {} ideas", ids.len());
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDEA: &str = "A marketplace for surplus restaurant food\n\
+        Restaurants throw away a large share of the food they prepare every evening while many \
+        people nearby would gladly buy it at a discount\n\
+        An app lists unsold meals an hour before closing so customers can reserve and collect them";
+
+    fn shared_buckets(a: &str, b: &str) -> usize {
+        let (a, b) = (buckets(&signature(a)), buckets(&signature(b)));
+        a.iter().zip(&b).filter(|(x, y)| x == y).count()
+    }
+
+    #[test]
+    fn shingles_ignore_case_and_punctuation() {
+        assert_eq!(shingles("Solar panels, for schools!"), shingles("solar PANELS for   schools"));
+        assert_eq!(shingles("one two three four").len(), 2);
+        assert_eq!(shingles("too short").len(), 1);
+        assert!(shingles(" ,.- ").is_empty());
+    }
+
+    #[test]
+    fn texts_without_words_match_nothing() {
+        assert!(signature("").is_empty());
+        assert!(signature("?!").is_empty());
+        assert_eq!(similarity(&signature(""), &signature("")), 0.0);
+    }
+
+    #[test]
+    fn identical_text_scores_one() {
+        let signature = signature(IDEA);
+        assert_eq!(signature.len(), HASHES);
+        assert_eq!(buckets(&signature).len(), BANDS);
+        assert_eq!(similarity(&signature, &super::signature(IDEA)), 1.0);
+        assert_eq!(shared_buckets(IDEA, IDEA), BANDS);
+    }
+
+    #[test]
+    fn unrelated_text_falls_below_the_threshold() {
+        let other = "Drone based inspection of wind turbine blades\n\
+            Climbing crews are slow and expensive and the blades must be stopped for days\n\
+            Autonomous drones photograph each blade and a model flags cracks for repair";
+        assert!(similarity(&signature(IDEA), &signature(other)) < 0.5);
+        assert!(shared_buckets(IDEA, other) < 2);
+    }
+
+    #[test]
+    fn lightly_edited_text_shares_a_band() {
+        let edited = IDEA.replace("every evening", "each evening").replace("An app", "A mobile app");
+        let score = similarity(&signature(IDEA), &signature(&edited));
+        assert!((0.5..1.0).contains(&score), "similarity {}", score);
+        assert!(shared_buckets(IDEA, &edited) >= 1);
+    }
+}
@@ -0,0 +1,42 @@
+use sqlx::PgConnection;
+use tonic::Status;
+use uuid::Uuid;
+
+const MAX_SKILLS_PER_IDEA: usize = 20;
+const MAX_SKILL_CHARS: usize = 50; // skills.slug is VARCHAR(50)
+
+/// Checks free-form skill names before they reach the catalog.
+pub fn validate(names: &[String]) -> Result<Vec<String>, Status> {
+    let names: Vec<String> = names.iter().map(|n| n.trim().to_string()).filter(|n| !n.is_empty()).collect();
+    if names.len() > MAX_SKILLS_PER_IDEA {
+        return Err(Status::invalid_argument(format!("At most {} required skills are allowed", MAX_SKILLS_PER_IDEA)));
+    }
+    if names.iter().any(|n| n.chars().count() > MAX_SKILL_CHARS) {
+        return Err(Status::invalid_argument(format!("Skills must be at most {} characters", MAX_SKILL_CHARS)));
+    }
+    Ok(names)
+}
+
+/// Select-list expression for an idea's required skill names; the query must alias `ideas` as `i`.
+pub const IDEA_SKILLS_COLUMN: &str =
+    "ARRAY(SELECT s.name FROM idea_skills x JOIN skills s ON s.id = x.skill_id WHERE x.idea_id = i.id ORDER BY s.name)::TEXT[] AS required_skills";
+
+/// Replaces an idea's required skills, creating catalog entries for new names.
+pub async fn set_idea_skills(conn: &mut PgConnection, idea_id: Uuid, names: &[String]) -> Result<(), Status> {
+    sqlx::query("DELETE FROM idea_skills WHERE idea_id = $1")
+        .bind(idea_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+    sqlx::query(
+        "INSERT INTO idea_skills (id, idea_id, skill_id) \
+         SELECT uuid_generate_v4(), $1, skill_id FROM resolve_skills($2::TEXT[])",
+    )
+    .bind(idea_id)
+    .bind(names)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+    Ok(())
+}
@@ -0,0 +1,371 @@
+//! Project teams, what each role may do, and the invitations that grow a team. An invitation
+//! is addressed to an email address, which need not have an account yet; it is accepted by
+//! whoever signs in with that address verified. The token in the invite link only lets its
+//! holder look the invitation up. Ownership changes hands by an offer the recipient accepts.
+
+use shared_proto::team::{Invitation, Member, OwnershipTransfer};
+use sqlx::{PgPool, Row};
+use tonic::Status;
+use uuid::Uuid;
+
+use crate::tenant::Tenant;
+
+/// `projects.owner_id`; every other role is a `project_members` row.
+pub const ROLE_OWNER: &str = "owner";
+pub const ROLE_MAINTAINER: &str = "maintainer";
+pub const ROLE_CONTRIBUTOR: &str = "contributor";
+pub const ROLE_VIEWER: &str = "viewer";
+pub const ROLE_INVESTOR_OBSERVER: &str = "investor-observer";
+/// Roles that can be granted; ownership only changes hands by transfer.
+pub const ROLES: [&str; 4] = [ROLE_MAINTAINER, ROLE_CONTRIBUTOR, ROLE_VIEWER, ROLE_INVESTOR_OBSERVER];
+pub const DEFAULT_ROLE: &str = ROLE_CONTRIBUTOR;
+
+pub const INVITATION_TTL_DAYS: i32 = 14;
+pub const MAX_PENDING_INVITATIONS: i64 = 100;
+
+pub const TRANSFER_TTL_DAYS: i32 = 7;
+/// What the outgoing owner stays on as unless they choose otherwise.
+pub const DEFAULT_PREVIOUS_OWNER_ROLE: &str = ROLE_MAINTAINER;
+
+/// Pending transfers past their expiry read as expired, as with `INVITATION_COLUMNS`.
+pub const TRANSFER_COLUMNS: &str = "t.id, t.project_id, p.name AS project_name, t.from_user_id, t.to_user_id, \
+    t.previous_owner_role, \
+    CASE WHEN t.status = 'pending' AND t.expires_at <= NOW() THEN 'expired' ELSE t.status END AS status, \
+    t.created_at, t.expires_at";
+
+/// Pending invitations past their expiry read as expired before the sweep in `invite` marks them.
+pub const INVITATION_COLUMNS: &str = "i.id, i.project_id, p.name AS project_name, i.email, i.role, \
+    CASE WHEN i.status = 'pending' AND i.expires_at <= NOW() THEN 'expired' ELSE i.status END AS status, \
+    i.invited_by, i.created_at, i.expires_at";
+
+/// The caller's standing on a project in their current workspace.
+#[derive(Debug)]
+pub struct Access {
+    pub owner_id: Uuid,
+    pub name: String,
+    pub is_public: bool,
+    pub org_id: Option<Uuid>,
+    /// `None` when the caller isn't on the team (or isn't signed in).
+    pub role: Option<String>,
+}
+
+impl Access {
+    /// Private projects are only visible to their team; to anyone else they don't exist.
+    pub fn require_view(&self) -> Result<(), Status> {
+        if self.role.is_none() && !self.is_public {
+            return Err(Status::not_found("Project not found"));
+        }
+        Ok(())
+    }
+
+    /// Project settings and the team: owner and maintainers.
+    pub fn require_manage(&self) -> Result<&str, Status> {
+        self.require_any(&[ROLE_OWNER, ROLE_MAINTAINER], "Only the project owner and maintainers can do this")
+    }
+
+    /// Creating and updating tasks: everyone but the read-only roles.
+    pub fn require_edit_tasks(&self) -> Result<&str, Status> {
+        self.require_any(&[ROLE_OWNER, ROLE_MAINTAINER, ROLE_CONTRIBUTOR], "Viewers and observers can't change tasks")
+    }
+
+    fn require_any(&self, roles: &[&str], message: &str) -> Result<&str, Status> {
+        self.require_view()?;
+        match self.role.as_deref() {
+            Some(role) if roles.contains(&role) => Ok(role),
+            _ => Err(Status::permission_denied(message.to_string())),
+        }
+    }
+}
+
+/// Looks the project up from the tenant's workspace; projects it can't see, or archived, are
+/// reported missing. Another workspace's public project is read-only from here.
+pub async fn access(pool: &PgPool, tenant: &Tenant, project_id: Uuid) -> Result<Access, Status> {
+    let row = sqlx::query(&format!(
+        "SELECT p.owner_id, p.name, COALESCE(p.is_public, FALSE) AS is_public, p.org_id, {} \
+         FROM projects p WHERE p.id = $1 AND p.deleted_at IS NULL",
+        member_role_column(2)
+    ))
+    .bind(project_id)
+    .bind(tenant.user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| Status::internal(format!("DB: {}", e)))?
+    .filter(|row| visible_from(tenant, row.get("org_id"), row.get("is_public")))
+    .ok_or_else(|| Status::not_found("Project not found"))?;
+
+    let (owner_id, org_id) = (row.get("owner_id"), row.get("org_id"));
+    Ok(Access {
+        owner_id,
+        name: row.get("name"),
+        is_public: row.get("is_public"),
+        org_id,
+        role: resolve_role(tenant, owner_id, org_id, row.get("member_role")),
+    })
+}
+
+/// Access to the project a task belongs to; tasks the caller can't see are reported missing.
+pub async fn task_access(pool: &PgPool, tenant: &Tenant, task_id: Uuid) -> Result<(Uuid, Access), Status> {
+    let project_id: Uuid = sqlx::query("SELECT project_id FROM tasks WHERE id = $1 AND deleted_at IS NULL")
+        .bind(task_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .ok_or_else(|| Status::not_found("Task not found"))?
+        .get("project_id");
+    let access = access(pool, tenant, project_id)
+        .await
+        .and_then(|access| access.require_view().map(|()| access))
+        .map_err(|e| match e.code() {
+            tonic::Code::NotFound => Status::not_found("Task not found"),
+            _ => e,
+        })?;
+    Ok((project_id, access))
+}
+
+/// SQL for the `project_members` role of the user bound at `$param` on project `p`, as
+/// column `member_role`; `resolve_role` makes it their role on the project.
+pub fn member_role_column(param: usize) -> String {
+    format!(
+        "(SELECT m.role FROM project_members m WHERE m.project_id = p.id AND m.user_id = ${}) AS member_role",
+        param
+    )
+}
+
+/// The caller's role on a project: owner, else their team role, else none (an outsider, who
+/// can still view it if it's public). A role on a project never carries over into another
+/// workspace, and anonymous callers have none.
+pub fn resolve_role(tenant: &Tenant, owner_id: Uuid, org_id: Option<Uuid>, member_role: Option<String>) -> Option<String> {
+    let user_id = tenant.user_id?;
+    if org_id != tenant.org_id {
+        return None;
+    }
+    if owner_id == user_id {
+        return Some(ROLE_OWNER.to_string());
+    }
+    member_role
+}
+
+/// Whether a project can be seen from the tenant's workspace: the workspace's own projects,
+/// and public projects from any workspace. Private ones are still limited to their team by
+/// `Access::require_view`.
+pub fn visible_from(tenant: &Tenant, org_id: Option<Uuid>, is_public: bool) -> bool {
+    org_id == tenant.org_id || is_public
+}
+
+pub async fn is_on_team(pool: &PgPool, project_id: Uuid, user_id: Uuid) -> Result<bool, Status> {
+    sqlx::query(
+        "SELECT EXISTS (SELECT 1 FROM projects WHERE id = $1 AND owner_id = $2) \
+         OR EXISTS (SELECT 1 FROM project_members WHERE project_id = $1 AND user_id = $2) AS on_team",
+    )
+    .bind(project_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| Status::internal(format!("DB: {}", e)))
+    .map(|row| row.get("on_team"))
+}
+
+pub fn role(value: &str) -> Result<String, Status> {
+    let value = value.trim().to_ascii_lowercase();
+    if value.is_empty() {
+        return Ok(DEFAULT_ROLE.to_string());
+    }
+    if !ROLES.contains(&value.as_str()) {
+        return Err(Status::invalid_argument(format!("role must be one of: {}", ROLES.join(", "))));
+    }
+    Ok(value)
+}
+
+/// What the outgoing owner stays on as after a transfer; unlike `role`, it defaults to
+/// `DEFAULT_PREVIOUS_OWNER_ROLE`.
+pub fn previous_owner_role(value: &str) -> Result<String, Status> {
+    if value.trim().is_empty() {
+        return Ok(DEFAULT_PREVIOUS_OWNER_ROLE.to_string());
+    }
+    role(value)
+}
+
+pub async fn load_member(pool: &PgPool, project_id: Uuid, user_id: Uuid) -> Result<Member, Status> {
+    load_team(pool, project_id)
+        .await?
+        .into_iter()
+        .find(|m| m.user_id == user_id.to_string())
+        .ok_or_else(|| Status::not_found("Member not found"))
+}
+
+/// The owner first, then members in the order they joined.
+pub async fn load_team(pool: &PgPool, project_id: Uuid) -> Result<Vec<Member>, Status> {
+    let rows = sqlx::query(
+        "SELECT u.id, $2::TEXT AS role, COALESCE(u.full_name, '') AS full_name, p.created_at, 0 AS rank \
+         FROM projects p JOIN users u ON u.id = p.owner_id WHERE p.id = $1 \
+         UNION ALL \
+         SELECT u.id, m.role, COALESCE(u.full_name, '') AS full_name, m.created_at, 1 AS rank \
+         FROM project_members m JOIN users u ON u.id = m.user_id WHERE m.project_id = $1 \
+         ORDER BY rank, created_at, id",
+    )
+    .bind(project_id)
+    .bind(ROLE_OWNER)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+
+    Ok(rows
+        .iter()
+        .map(|row| Member {
+            user_id: row.get::<Uuid, _>("id").to_string(),
+            role: row.get("role"),
+            full_name: row.get("full_name"),
+            joined_at: row
+                .get::<Option<chrono::DateTime<chrono::Utc>>, _>("created_at")
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default(),
+        })
+        .collect())
+}
+
+pub fn invitation_from_row(row: &sqlx::postgres::PgRow) -> Invitation {
+    Invitation {
+        id: row.get::<Uuid, _>("id").to_string(),
+        project_id: row.get::<Uuid, _>("project_id").to_string(),
+        project_name: row.get("project_name"),
+        email: row.get("email"),
+        role: row.get("role"),
+        status: row.get("status"),
+        invited_by: row.get::<Option<Uuid>, _>("invited_by").map(|u| u.to_string()).unwrap_or_default(),
+        created_at: row
+            .get::<Option<chrono::DateTime<chrono::Utc>>, _>("created_at")
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default(),
+        expires_at: row.get::<chrono::DateTime<chrono::Utc>, _>("expires_at").to_rfc3339(),
+    }
+}
+
+pub fn transfer_from_row(row: &sqlx::postgres::PgRow) -> OwnershipTransfer {
+    OwnershipTransfer {
+        id: row.get::<Uuid, _>("id").to_string(),
+        project_id: row.get::<Uuid, _>("project_id").to_string(),
+        project_name: row.get("project_name"),
+        from_user_id: row.get::<Uuid, _>("from_user_id").to_string(),
+        to_user_id: row.get::<Uuid, _>("to_user_id").to_string(),
+        previous_owner_role: row.get("previous_owner_role"),
+        status: row.get("status"),
+        created_at: row
+            .get::<Option<chrono::DateTime<chrono::Utc>>, _>("created_at")
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default(),
+        expires_at: row.get::<chrono::DateTime<chrono::Utc>, _>("expires_at").to_rfc3339(),
+    }
+}
+
+pub async fn load_transfer(pool: &PgPool, id: Uuid) -> Result<OwnershipTransfer, Status> {
+    sqlx::query(&format!(
+        "SELECT {} FROM project_ownership_transfers t JOIN projects p ON p.id = t.project_id WHERE t.id = $1",
+        TRANSFER_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| Status::internal(format!("DB: {}", e)))?
+    .map(|row| transfer_from_row(&row))
+    .ok_or_else(|| Status::not_found("Ownership transfer not found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access(role: Option<&str>, is_public: bool) -> Access {
+        Access {
+            owner_id: Uuid::new_v4(),
+            name: "Project".into(),
+            is_public,
+            org_id: Some(Uuid::new_v4()),
+            role: role.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn private_projects_are_missing_to_outsiders() {
+        let project = access(None, false);
+        assert_eq!(project.require_view().unwrap_err().code(), tonic::Code::NotFound);
+        assert_eq!(project.require_edit_tasks().unwrap_err().code(), tonic::Code::NotFound);
+        assert_eq!(project.require_manage().unwrap_err().code(), tonic::Code::NotFound);
+    }
+
+    #[test]
+    fn public_projects_are_read_only_to_outsiders() {
+        let project = access(None, true);
+        assert!(project.require_view().is_ok());
+        assert_eq!(project.require_edit_tasks().unwrap_err().code(), tonic::Code::PermissionDenied);
+        assert_eq!(project.require_manage().unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+
+    #[test]
+    fn team_roles_grant_what_they_say() {
+        assert_eq!(access(Some(ROLE_OWNER), false).require_manage().unwrap(), ROLE_OWNER);
+        assert_eq!(access(Some(ROLE_MAINTAINER), false).require_manage().unwrap(), ROLE_MAINTAINER);
+        assert!(access(Some(ROLE_CONTRIBUTOR), false).require_manage().is_err());
+        assert_eq!(access(Some(ROLE_CONTRIBUTOR), false).require_edit_tasks().unwrap(), ROLE_CONTRIBUTOR);
+        assert!(access(Some(ROLE_VIEWER), false).require_edit_tasks().is_err());
+        assert!(access(Some(ROLE_INVESTOR_OBSERVER), false).require_edit_tasks().is_err());
+        assert!(access(Some(ROLE_VIEWER), false).require_view().is_ok());
+    }
+
+    #[test]
+    fn projects_are_visible_in_their_workspace_and_everywhere_once_public() {
+        let org = Some(Uuid::new_v4());
+        let here = Tenant { user_id: Some(Uuid::new_v4()), org_id: org };
+        let personal = Tenant { user_id: here.user_id, org_id: None };
+        assert!(visible_from(&here, org, false));
+        assert!(!visible_from(&personal, org, false));
+        assert!(visible_from(&personal, org, true));
+        assert!(visible_from(&personal, None, false));
+    }
+
+    #[test]
+    fn roles_default_to_contributor_and_exclude_owner() {
+        assert_eq!(role("").unwrap(), DEFAULT_ROLE);
+        assert_eq!(role(" Maintainer ").unwrap(), ROLE_MAINTAINER);
+        assert_eq!(role("investor-observer").unwrap(), ROLE_INVESTOR_OBSERVER);
+        assert_eq!(role(ROLE_OWNER).unwrap_err().code(), tonic::Code::InvalidArgument);
+        assert!(role("admin").is_err());
+    }
+
+    #[test]
+    fn ownership_comes_before_team_role() {
+        let owner = Uuid::new_v4();
+        let tenant = Tenant { user_id: Some(owner), org_id: None };
+        assert_eq!(resolve_role(&tenant, owner, None, Some(ROLE_VIEWER.into())).as_deref(), Some(ROLE_OWNER));
+        assert_eq!(resolve_role(&tenant, owner, None, None).as_deref(), Some(ROLE_OWNER));
+    }
+
+    #[test]
+    fn members_get_their_team_role_and_outsiders_none() {
+        let tenant = Tenant { user_id: Some(Uuid::new_v4()), org_id: None };
+        let owner = Uuid::new_v4();
+        assert_eq!(resolve_role(&tenant, owner, None, Some(ROLE_CONTRIBUTOR.into())).as_deref(), Some(ROLE_CONTRIBUTOR));
+        assert_eq!(resolve_role(&tenant, owner, None, None), None);
+        // An outsider's standing on a public project is view-only.
+        let outsider = Access { role: resolve_role(&tenant, owner, None, None), ..access(None, true) };
+        assert!(outsider.require_view().is_ok());
+        assert!(outsider.require_edit_tasks().is_err());
+    }
+
+    #[test]
+    fn roles_stay_in_their_workspace_and_need_a_signed_in_caller() {
+        let (user, org) = (Uuid::new_v4(), Some(Uuid::new_v4()));
+        let elsewhere = Tenant { user_id: Some(user), org_id: None };
+        assert_eq!(resolve_role(&elsewhere, user, org, Some(ROLE_MAINTAINER.into())), None);
+        let here = Tenant { user_id: Some(user), org_id: org };
+        assert_eq!(resolve_role(&here, user, org, None).as_deref(), Some(ROLE_OWNER));
+        let anonymous = Tenant::default();
+        assert_eq!(resolve_role(&anonymous, user, None, Some(ROLE_VIEWER.into())), None);
+    }
+
+    #[test]
+    fn outgoing_owners_stay_on_as_maintainers_unless_they_choose() {
+        assert_eq!(previous_owner_role(" ").unwrap(), ROLE_MAINTAINER);
+        assert_eq!(previous_owner_role("viewer").unwrap(), ROLE_VIEWER);
+        assert!(previous_owner_role(ROLE_OWNER).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+//! The workspace a call acts in. The gateway passes the signed-in user and the organization
+//! from their token as `x-user-id` / `x-org-id` metadata (trusted the way svc-identity trusts
+//! `x-forwarded-for`); membership is re-checked here, so a token minted before someone was
+//! removed from an organization can't read it. Calls without `x-org-id` act in the personal
+//! space, which never sees an organization's ideas or projects.
+
+use sqlx::PgPool;
+use tonic::{Request, Status};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tenant {
+    pub user_id: Option<Uuid>,
+    pub org_id: Option<Uuid>,
+}
+
+impl Tenant {
+    pub async fn from_request<T>(pool: &PgPool, request: &Request<T>) -> Result<Self, Status> {
+        let user_id = metadata_uuid(request, "x-user-id")?;
+        let org_id = metadata_uuid(request, "x-org-id")?;
+
+        if let Some(org_id) = org_id {
+            let user_id = user_id.ok_or_else(|| Status::unauthenticated("Sign in to work in an organization"))?;
+            let member = sqlx::query("SELECT 1 FROM organization_members WHERE org_id = $1 AND user_id = $2")
+                .bind(org_id)
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+            if member.is_none() {
+                return Err(Status::permission_denied("You are not a member of this organization"));
+            }
+        }
+        Ok(Self { user_id, org_id })
+    }
+
+    /// For calls that only make sense for a signed-in user.
+    pub fn require_user(&self) -> Result<Uuid, Status> {
+        self.user_id.ok_or_else(|| Status::unauthenticated("Sign in to continue"))
+    }
+
+    /// Rejects acting for anyone but the signed-in caller; anonymous calls act for nobody.
+    pub fn require_self(&self, user_id: Uuid) -> Result<(), Status> {
+        if self.require_user()? != user_id {
+            return Err(Status::permission_denied("You can only act for yourself"));
+        }
+        Ok(())
+    }
+}
+
+fn metadata_uuid<T>(request: &Request<T>, key: &str) -> Result<Option<Uuid>, Status> {
+    request
+        .metadata()
+        .get(key)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|v| Uuid::parse_str(v).ok())
+                .ok_or_else(|| Status::invalid_argument(format!("Invalid {} metadata", key)))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anonymous_callers_act_for_nobody() {
+        let anonymous = Tenant::default();
+        assert_eq!(anonymous.require_user().unwrap_err().code(), tonic::Code::Unauthenticated);
+        assert_eq!(anonymous.require_self(Uuid::new_v4()).unwrap_err().code(), tonic::Code::Unauthenticated);
+    }
+
+    #[test]
+    fn signed_in_callers_act_only_for_themselves() {
+        let me = Uuid::new_v4();
+        let tenant = Tenant { user_id: Some(me), org_id: None };
+        assert_eq!(tenant.require_user().unwrap(), me);
+        assert!(tenant.require_self(me).is_ok());
+        assert_eq!(tenant.require_self(Uuid::new_v4()).unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+
+    #[test]
+    fn metadata_ids_must_be_uuids() {
+        let id = Uuid::new_v4();
+        let mut request = Request::new(());
+        request.metadata_mut().insert("x-user-id", id.to_string().parse().unwrap());
+        request.metadata_mut().insert("x-org-id", "acme".parse().unwrap());
+
+        assert_eq!(metadata_uuid(&request, "x-user-id").unwrap(), Some(id));
+        assert_eq!(metadata_uuid(&request, "x-org-id").unwrap_err().code(), tonic::Code::InvalidArgument);
+        assert_eq!(metadata_uuid(&request, "x-missing").unwrap(), None);
+    }
+}
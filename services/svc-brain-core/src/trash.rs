@@ -0,0 +1,96 @@
+//! Soft deletion. Deleted ideas and tasks and archived projects keep their rows with
+//! `deleted_at` set: every list and lookup skips them, whoever removed them can restore them
+//! within the retention window, and a background job purges them once it has passed.
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct Trash {
+    pool: PgPool,
+    pub retention_days: i32,
+}
+
+#[derive(Debug, Default)]
+pub struct Purged {
+    pub projects: u64,
+    pub tasks: u64,
+    pub ideas: u64,
+}
+
+impl Purged {
+    pub fn total(&self) -> u64 {
+        self.projects + self.tasks + self.ideas
+    }
+}
+
+/// Anything removed after this can still be restored; anything removed at or before it is
+/// due for purging.
+fn cutoff(retention_days: i32, now: DateTime<Utc>) -> DateTime<Utc> {
+    now - chrono::Duration::days(retention_days.into())
+}
+
+impl Trash {
+    pub fn new(pool: PgPool, retention_days: i32) -> Self {
+        Self { pool, retention_days }
+    }
+
+    /// The restore window as of now; queries keep rows with `deleted_at > cutoff()`.
+    pub fn cutoff(&self) -> DateTime<Utc> {
+        cutoff(self.retention_days, Utc::now())
+    }
+
+    /// Hard-deletes everything removed longer ago than the retention window. Tasks, members,
+    /// invitations and skills go with their project or idea.
+    pub async fn purge(&self) -> Result<Purged, sqlx::Error> {
+        let mut purged = Purged::default();
+        let cutoff = self.cutoff();
+        for (table, count) in [("projects", &mut purged.projects), ("tasks", &mut purged.tasks), ("ideas", &mut purged.ideas)] {
+            *count = sqlx::query(&format!("DELETE FROM {} WHERE deleted_at <= $1", table))
+                .bind(cutoff)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+        }
+        Ok(purged)
+    }
+
+    pub fn spawn_purge_job(self, every: Duration) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            loop {
+                interval.tick().await;
+                match self.purge().await {
+                    Ok(p) if p.total() > 0 => tracing::info!(
+                        "Purged {} projects, {} tasks and {} ideas past the restore window",
+                        p.projects, p.tasks, p.ideas
+                    ),
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("Purge failed: {}", e),
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn cutoff_is_the_retention_window_before_now() {
+        assert_eq!(cutoff(30, at("2026-03-31T12:00:00Z")), at("2026-03-01T12:00:00Z"));
+        assert_eq!(cutoff(1, at("2026-01-01T00:00:00Z")), at("2025-12-31T00:00:00Z"));
+    }
+
+    #[test]
+    fn totals_every_kind() {
+        assert_eq!(Purged::default().total(), 0);
+        assert_eq!(Purged { projects: 1, tasks: 4, ideas: 2 }.total(), 7);
+    }
+}
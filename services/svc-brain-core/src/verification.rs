@@ -0,0 +1,20 @@
+use sqlx::{PgPool, Row};
+use tonic::Status;
+use uuid::Uuid;
+
+/// Publishing and investing are reserved for accounts that confirmed their email
+/// (`users.email_verified` is maintained by svc-identity).
+pub async fn require_verified_email(pool: &PgPool, user_id: Uuid) -> Result<(), Status> {
+    let verified: Option<bool> = sqlx::query("SELECT email_verified FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?
+        .map(|row| row.get("email_verified"));
+
+    match verified {
+        Some(true) => Ok(()),
+        Some(false) => Err(Status::failed_precondition("Verify your email address before doing this")),
+        None => Err(Status::not_found("User not found")),
+    }
+}
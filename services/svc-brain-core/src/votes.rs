@@ -0,0 +1,144 @@
+//! Upvotes and bookmarks, and the feed orderings built on them. The counts live on `ideas`
+//! and are kept in step by triggers on `idea_votes` and `idea_bookmarks`.
+
+use chrono::{DateTime, Utc};
+use shared_proto::idea::Idea;
+use sqlx::PgPool;
+use tonic::Status;
+use uuid::Uuid;
+
+pub const VOTES: &str = "idea_votes";
+pub const BOOKMARKS: &str = "idea_bookmarks";
+
+/// Trending score is `upvotes / (age in hours + 2) ^ gravity`, as on Hacker News.
+const TRENDING_GRAVITY: f64 = 1.8;
+/// Older ideas don't trend, which also keeps the scan to recent rows.
+const TRENDING_WINDOW_DAYS: i32 = 7;
+/// Trending ranks at most this many of the window's newest ideas.
+pub const MAX_TRENDING_CANDIDATES: i64 = 1000;
+
+/// `upvoted` and `bookmarked` for the idea `i`, where `param` holds the caller's user id; both
+/// are false for anonymous callers.
+pub fn viewer_columns(param: &str) -> String {
+    format!(
+        "EXISTS (SELECT 1 FROM idea_votes v WHERE v.idea_id = i.id AND v.user_id = {p}) AS upvoted, \
+         EXISTS (SELECT 1 FROM idea_bookmarks b WHERE b.idea_id = i.id AND b.user_id = {p}) AS bookmarked",
+        p = param
+    )
+}
+
+/// A `ListIdeas` sort mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    New,
+    Top,
+    Trending,
+}
+
+impl Sort {
+    pub fn from_name(sort: &str) -> Result<Self, Status> {
+        match sort {
+            "" | "new" => Ok(Sort::New),
+            "top" => Ok(Sort::Top),
+            "trending" => Ok(Sort::Trending),
+            _ => Err(Status::invalid_argument("sort must be new, top or trending")),
+        }
+    }
+
+    /// The live-idea filter and ordering. Trending fetches its window newest first, to be put
+    /// in order by `rank_trending`.
+    pub fn feed(self) -> String {
+        match self {
+            Sort::New => "i.deleted_at IS NULL ORDER BY i.created_at DESC".into(),
+            Sort::Top => "i.deleted_at IS NULL ORDER BY i.upvote_count DESC, i.created_at DESC".into(),
+            Sort::Trending => format!(
+                "i.deleted_at IS NULL AND i.created_at > NOW() - INTERVAL '{} days' ORDER BY i.created_at DESC",
+                TRENDING_WINDOW_DAYS
+            ),
+        }
+    }
+}
+
+pub fn trending_score(upvotes: i32, age: chrono::Duration) -> f64 {
+    let hours = age.num_seconds().max(0) as f64 / 3600.0;
+    f64::from(upvotes) / (hours + 2.0).powf(TRENDING_GRAVITY)
+}
+
+/// Ideas, each with when it was created, by trending score, ties newest first.
+pub fn rank_trending(ideas: Vec<(Idea, DateTime<Utc>)>, now: DateTime<Utc>) -> Vec<Idea> {
+    let mut scored: Vec<(f64, DateTime<Utc>, Idea)> = ideas
+        .into_iter()
+        .map(|(idea, created_at)| (trending_score(idea.upvote_count, now - created_at), created_at, idea))
+        .collect();
+    scored.sort_by(|(a, a_at, _), (b, b_at, _)| b.total_cmp(a).then(b_at.cmp(a_at)));
+    scored.into_iter().map(|(_, _, idea)| idea).collect()
+}
+
+/// Adds or takes back the user's upvote or bookmark; `table` is `VOTES` or `BOOKMARKS`.
+pub async fn set(pool: &PgPool, table: &str, idea_id: Uuid, user_id: Uuid, on: bool) -> Result<(), Status> {
+    let query = if on {
+        format!("INSERT INTO {} (idea_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING", table)
+    } else {
+        format!("DELETE FROM {} WHERE idea_id = $1 AND user_id = $2", table)
+    };
+    sqlx::query(&query)
+        .bind(idea_id)
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB: {}", e)))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idea(id: &str, upvotes: i32) -> Idea {
+        Idea { id: id.into(), upvote_count: upvotes, ..Default::default() }
+    }
+
+    fn hours_ago(now: DateTime<Utc>, hours: i64) -> DateTime<Utc> {
+        now - chrono::Duration::hours(hours)
+    }
+
+    #[test]
+    fn sort_defaults_to_new_and_refuses_unknown_modes() {
+        assert_eq!(Sort::from_name("").unwrap(), Sort::New);
+        assert_eq!(Sort::from_name("top").unwrap(), Sort::Top);
+        assert_eq!(Sort::from_name("trending").unwrap(), Sort::Trending);
+        assert_eq!(Sort::from_name("hot").unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn trending_score_grows_with_votes_and_decays_with_age() {
+        let hour = chrono::Duration::hours(1);
+        assert!(trending_score(10, hour) > trending_score(5, hour));
+        assert!(trending_score(10, hour) > trending_score(10, hour * 24));
+        assert_eq!(trending_score(0, hour), 0.0);
+        // Clock skew can't make a brand new idea outrank itself.
+        assert_eq!(trending_score(3, -hour), trending_score(3, chrono::Duration::zero()));
+    }
+
+    #[test]
+    fn fresh_ideas_outrank_older_ones_with_more_votes() {
+        let now = Utc::now();
+        let ranked = rank_trending(
+            vec![
+                (idea("old-popular", 40), hours_ago(now, 72)),
+                (idea("fresh", 5), hours_ago(now, 1)),
+                (idea("day-old", 20), hours_ago(now, 24)),
+            ],
+            now,
+        );
+        let ids: Vec<&str> = ranked.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, ["fresh", "day-old", "old-popular"]);
+    }
+
+    #[test]
+    fn ties_go_to_the_newer_idea() {
+        let now = Utc::now();
+        let ranked = rank_trending(vec![(idea("older", 0), hours_ago(now, 5)), (idea("newer", 0), hours_ago(now, 2))], now);
+        assert_eq!(ranked[0].id, "newer");
+    }
+}
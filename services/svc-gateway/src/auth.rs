@@ -0,0 +1,224 @@
+use axum::http::{header::{AUTHORIZATION, USER_AGENT}, HeaderMap};
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use serde::de::IgnoredAny;
+use shared_proto::user::user_service_client::UserServiceClient;
+use shared_proto::user::IntrospectTokenRequest;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tonic::metadata::MetadataValue;
+use tonic::transport::Channel;
+
+/// Personal access tokens start with this; anything else is treated as a session JWT.
+const PAT_PREFIX: &str = "bb_pat_";
+/// Bounds memory if a client cycles through many tokens; the cache is simply cleared when full.
+const MAX_CACHED_TOKENS: usize = 10_000;
+/// How long a token svc-identity rejected stays rejected without asking again, so made-up
+/// tokens can't each cost a lookup.
+const REJECTED_TOKEN_TTL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub user_id: String,
+    /// `None` for sessions; personal access tokens carry the scopes they were granted.
+    pub scopes: Option<Vec<String>>,
+    /// The organization the token acts in; `None` for the personal space.
+    pub org_id: Option<String>,
+}
+
+impl AuthUser {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.as_ref().is_none_or(|scopes| scopes.iter().any(|s| s == scope))
+    }
+}
+
+/// Resolves callers through svc-identity, with answers cached for the TTL it gives. Session
+/// JWTs are first checked locally so forged or expired ones never cost a lookup, but only
+/// svc-identity knows whether a session was revoked since (password reset, email change...).
+pub struct Authenticator {
+    key: DecodingKey,
+    validation: Validation,
+    user_client: UserServiceClient<Channel>,
+    cache: Mutex<TokenCache>,
+}
+
+/// Introspection answers by token: the caller, or `None` for a rejected token.
+#[derive(Debug, Default)]
+struct TokenCache {
+    entries: HashMap<String, (Option<AuthUser>, Instant)>,
+}
+
+impl TokenCache {
+    /// `None` when there is no live answer for the token.
+    fn get(&self, token: &str, now: Instant) -> Option<Option<AuthUser>> {
+        self.entries.get(token).filter(|(_, expires)| *expires > now).map(|(user, _)| user.clone())
+    }
+
+    fn insert(&mut self, token: &str, user: Option<AuthUser>, expires: Instant) {
+        if self.entries.len() >= MAX_CACHED_TOKENS {
+            self.entries.clear();
+        }
+        self.entries.insert(token.to_string(), (user, expires));
+    }
+
+    fn remove(&mut self, token: &str) {
+        self.entries.remove(token);
+    }
+}
+
+impl Authenticator {
+    pub fn from_env(user_client: UserServiceClient<Channel>) -> Self {
+        let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "supersecretkey123".to_string());
+        Self {
+            key: DecodingKey::from_secret(secret.as_bytes()),
+            validation: Validation::default(),
+            user_client,
+            cache: Mutex::new(TokenCache::default()),
+        }
+    }
+
+    /// Resolves the caller from an `Authorization: Bearer <token>` header.
+    /// Missing, malformed, expired or revoked tokens are treated as anonymous.
+    pub async fn authenticate(&self, headers: &HeaderMap) -> Option<AuthUser> {
+        let token = bearer_token(headers)?;
+        if !token.starts_with(PAT_PREFIX) {
+            decode::<IgnoredAny>(token, &self.key, &self.validation).ok()?;
+        }
+        self.introspect(token).await
+    }
+
+    async fn introspect(&self, token: &str) -> Option<AuthUser> {
+        if let Some(user) = self.cache.lock().unwrap().get(token, Instant::now()) {
+            return user;
+        }
+
+        let request = IntrospectTokenRequest { token: token.to_string() };
+        let resp = match self.user_client.clone().introspect_token(request).await {
+            Ok(resp) => resp.into_inner(),
+            Err(e) if e.code() == tonic::Code::Unauthenticated => {
+                self.cache.lock().unwrap().insert(token, None, Instant::now() + REJECTED_TOKEN_TTL);
+                return None;
+            }
+            Err(e) => {
+                // Not cached: an outage of svc-identity says nothing about the token.
+                tracing::warn!("Token introspection failed: {}", e);
+                self.cache.lock().unwrap().remove(token);
+                return None;
+            }
+        };
+        let user = AuthUser {
+            user_id: resp.user_id,
+            scopes: (!resp.full_access).then_some(resp.scopes),
+            org_id: (!resp.org_id.is_empty()).then_some(resp.org_id),
+        };
+
+        let ttl = Duration::from_secs(resp.cache_ttl_secs.max(0) as u64);
+        self.cache.lock().unwrap().insert(token, Some(user.clone()), Instant::now() + ttl);
+        Some(user)
+    }
+}
+
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+}
+
+/// Wraps a gRPC message with the caller's bearer token, client IP and user agent so
+/// backend services can authenticate the user and attribute the request.
+pub fn forward<T>(headers: &HeaderMap, ip: Option<IpAddr>, message: T) -> tonic::Request<T> {
+    let mut request = tonic::Request::new(message);
+    if let Some(value) = bearer_token(headers).and_then(|t| MetadataValue::try_from(format!("Bearer {}", t)).ok()) {
+        request.metadata_mut().insert("authorization", value);
+    }
+    if let Some(value) = ip.and_then(|ip| MetadataValue::try_from(ip.to_string()).ok()) {
+        request.metadata_mut().insert("x-forwarded-for", value);
+    }
+    if let Some(value) = headers.get(USER_AGENT).and_then(|v| v.to_str().ok()).and_then(|v| MetadataValue::try_from(v).ok()) {
+        request.metadata_mut().insert("x-client-user-agent", value);
+    }
+    request
+}
+
+/// Wraps a gRPC message for svc-brain-core, which trusts the caller and workspace it is given
+/// here (and checks organization membership itself). Anonymous calls act in the personal space.
+pub fn as_caller<T>(user: Option<&AuthUser>, message: T) -> tonic::Request<T> {
+    let mut request = tonic::Request::new(message);
+    if let Some(user) = user {
+        if let Ok(value) = MetadataValue::try_from(user.user_id.as_str()) {
+            request.metadata_mut().insert("x-user-id", value);
+        }
+        if let Some(value) = user.org_id.as_deref().and_then(|org| MetadataValue::try_from(org).ok()) {
+            request.metadata_mut().insert("x-org-id", value);
+        }
+    }
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn user(id: &str) -> AuthUser {
+        AuthUser { user_id: id.into(), scopes: None, org_id: None }
+    }
+
+    #[test]
+    fn cache_answers_until_expiry() {
+        let now = Instant::now();
+        let mut cache = TokenCache::default();
+        cache.insert("bb_pat_a", Some(user("u1")), now + Duration::from_secs(30));
+
+        assert_eq!(cache.get("bb_pat_a", now).unwrap().unwrap().user_id, "u1");
+        assert!(cache.get("bb_pat_a", now + Duration::from_secs(30)).is_none());
+        assert!(cache.get("bb_pat_b", now).is_none());
+    }
+
+    #[test]
+    fn rejected_tokens_are_remembered_as_anonymous() {
+        let now = Instant::now();
+        let mut cache = TokenCache::default();
+        cache.insert("bb_pat_made_up", None, now + REJECTED_TOKEN_TTL);
+
+        assert!(matches!(cache.get("bb_pat_made_up", now), Some(None)));
+        assert!(cache.get("bb_pat_made_up", now + REJECTED_TOKEN_TTL).is_none());
+    }
+
+    #[test]
+    fn full_cache_starts_over() {
+        let expires = Instant::now() + Duration::from_secs(30);
+        let mut cache = TokenCache::default();
+        for i in 0..MAX_CACHED_TOKENS {
+            cache.insert(&format!("t{}", i), None, expires);
+        }
+        cache.insert("last", Some(user("u1")), expires);
+        assert_eq!(cache.entries.len(), 1);
+        assert!(cache.get("last", Instant::now()).is_some());
+    }
+
+    #[test]
+    fn bearer_token_is_trimmed_and_required() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer  abc "));
+        assert_eq!(bearer_token(&headers), Some("abc"));
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer "));
+        assert_eq!(bearer_token(&headers), None);
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Basic abc"));
+        assert_eq!(bearer_token(&headers), None);
+    }
+
+    #[test]
+    fn sessions_have_every_scope_and_tokens_only_theirs() {
+        assert!(user("u1").has_scope("ideas:write"));
+        let token = AuthUser { scopes: Some(vec!["ideas:read".into()]), ..user("u1") };
+        assert!(token.has_scope("ideas:read"));
+        assert!(!token.has_scope("ideas:write"));
+    }
+}
@@ -0,0 +1,53 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use tonic::{Code, Status};
+
+/// Wraps a gRPC status from a backend service and renders it as JSON with a matching HTTP code.
+pub struct ApiError(Status);
+
+impl From<Status> for ApiError {
+    fn from(status: Status) -> Self {
+        ApiError(status)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self.0.code() {
+            Code::InvalidArgument | Code::OutOfRange => StatusCode::BAD_REQUEST,
+            Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+            Code::PermissionDenied => StatusCode::FORBIDDEN,
+            Code::NotFound => StatusCode::NOT_FOUND,
+            Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+            Code::FailedPrecondition => StatusCode::PRECONDITION_FAILED,
+            Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+            Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        // Don't leak backend internals (SQL errors etc.) to clients.
+        let message = if status.is_server_error() {
+            tracing::error!("Upstream error: {:?}", self.0);
+            "Internal server error".to_string()
+        } else {
+            self.0.message().to_string()
+        };
+
+        let violations = shared_proto::errors::field_violations(&self.0);
+        let body = if violations.is_empty() {
+            serde_json::json!({ "error": message })
+        } else {
+            let violations: Vec<_> = violations
+                .into_iter()
+                .map(|v| serde_json::json!({ "field": v.field, "description": v.description }))
+                .collect();
+            serde_json::json!({ "error": message, "violations": violations })
+        };
+
+        (status, Json(body)).into_response()
+    }
+}
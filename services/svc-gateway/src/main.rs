@@ -1,19 +1,41 @@
+// tonic::Status is large, but it is the error ApiError wraps for every handler.
+#![allow(clippy::result_large_err)]
+
+mod auth;
+mod error;
+mod rate_limit;
+
 use axum::{
-    routing::{get, post},
-    Router, Json, extract::State,
-    http::{StatusCode, Method},
+    routing::{delete, get, post, patch},
+    Router, Json, extract::{Path, Query, State}, Extension,
+    http::{header, HeaderMap, Method},
+    response::IntoResponse,
+    middleware,
 };
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tower_http::cors::{CorsLayer, Any};
 use shared_proto::user::user_service_client::UserServiceClient;
+use shared_proto::organization::organization_service_client::OrganizationServiceClient;
 use shared_proto::idea::idea_service_client::IdeaServiceClient;
+use shared_proto::task::task_service_client::TaskServiceClient;
+use shared_proto::team::team_service_client::TeamServiceClient;
+use shared_proto::reputation::reputation_service_client::ReputationServiceClient;
 use tonic::transport::Channel;
+use error::ApiError;
+use rate_limit::{ClientIp, RateLimitConfig, RateLimiter};
 
 #[derive(Clone)]
 struct AppState {
     user_client: UserServiceClient<Channel>,
+    organization_client: OrganizationServiceClient<Channel>,
     idea_client: IdeaServiceClient<Channel>,
+    task_client: TaskServiceClient<Channel>,
+    team_client: TeamServiceClient<Channel>,
+    reputation_client: ReputationServiceClient<Channel>,
+    authenticator: Arc<auth::Authenticator>,
 }
 
 #[tokio::main]
@@ -37,26 +59,108 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .expect("Invalid idea service URL")
         .connect_lazy();
 
+    let user_client = UserServiceClient::new(user_channel.clone());
+    let authenticator = Arc::new(auth::Authenticator::from_env(user_client.clone()));
+
     let state = AppState {
-        user_client: UserServiceClient::new(user_channel),
-        idea_client: IdeaServiceClient::new(idea_channel),
+        user_client,
+        organization_client: OrganizationServiceClient::new(user_channel),
+        idea_client: IdeaServiceClient::new(idea_channel.clone()),
+        task_client: TaskServiceClient::new(idea_channel.clone()),
+        team_client: TeamServiceClient::new(idea_channel.clone()),
+        reputation_client: ReputationServiceClient::new(idea_channel),
+        authenticator: authenticator.clone(),
     };
 
+    let rate_limit_config = RateLimitConfig::from_env().expect("Failed to load rate limit config");
+    let redis_url = std::env::var("REDIS_URL").ok();
+    let limiter = Arc::new(RateLimiter::new(rate_limit_config, redis_url, authenticator).await);
+
     let cors = CorsLayer::new()
         .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST]);
+        .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE]);
 
     let app = Router::new()
         .route("/health", get(health_check))
-        .route("/api/users", post(create_user))
+        .route("/api/users", get(batch_get_users).post(create_user))
+        .route("/api/users/:id", get(get_user))
+        .route("/api/users/:id/reputation", get(get_reputation_breakdown))
+        .route("/api/users/:id/endorsements", post(endorse_user))
+        .route("/api/auth/login", post(login))
+        .route("/api/auth/login/verify", post(verify_login_challenge))
+        .route("/api/auth/oidc/providers", get(list_oidc_providers))
+        .route("/api/auth/oidc/:provider/start", get(begin_oidc_login))
+        .route("/api/auth/oidc/:provider/callback", post(complete_oidc_login))
+        .route("/api/users/me", patch(update_user).delete(delete_account))
+        .route("/api/users/me/password", post(change_password))
+        .route("/api/users/me/email", post(change_email))
+        .route("/api/users/me/tokens", get(list_personal_access_tokens).post(create_personal_access_token))
+        .route("/api/users/me/tokens/:id", delete(revoke_personal_access_token))
+        .route("/api/auth/confirm-email-change", post(confirm_email_change))
+        .route("/api/users/me/2fa/enroll", post(enroll_totp))
+        .route("/api/users/me/2fa/confirm", post(confirm_totp))
+        .route("/api/users/me/2fa/disable", post(disable_totp))
+        .route("/api/auth/verify-email", post(verify_email))
+        .route("/api/auth/resend-verification", post(resend_verification))
+        .route("/api/auth/password-reset/request", post(request_password_reset))
+        .route("/api/auth/password-reset/confirm", post(reset_password))
+        .route("/api/users/me/security-events", get(list_security_events))
+        .route("/api/users/me/data-requests", get(list_data_requests))
+        .route("/api/users/me/data-exports", post(request_data_export))
+        .route("/api/users/me/data-exports/:id/download", get(download_data_export))
+        .route("/api/data-requests/:id", get(get_data_request))
+        .route("/api/admin/users/:id/unlock", post(unlock_account))
+        .route("/api/admin/users/:id/role", post(set_user_role))
+        .route("/api/admin/security-events", get(admin_list_security_events))
+        .route("/api/orgs", get(list_my_organizations).post(create_organization))
+        .route("/api/orgs/:id", get(get_organization))
+        .route("/api/orgs/:id/members", get(list_organization_members).post(add_organization_member))
+        .route("/api/orgs/:id/members/:user_id", patch(update_organization_member).delete(remove_organization_member))
+        .route("/api/auth/switch-org", post(switch_organization))
         .route("/api/ideas", get(list_ideas).post(create_idea))
+        .route("/api/ideas/:id", patch(update_idea).delete(delete_idea))
+        .route("/api/ideas/:id/upvote", post(upvote_idea).delete(remove_idea_upvote))
+        .route("/api/ideas/:id/bookmark", post(bookmark_idea).delete(remove_idea_bookmark))
+        .route("/api/users/me/bookmarks", get(list_my_bookmarks))
+        .route("/api/ideas/:id/comments", get(list_idea_comments).post(create_idea_comment))
+        .route("/api/comments/:id", patch(update_idea_comment).delete(delete_idea_comment))
+        .route("/api/comments/:id/reactions/:reaction", post(add_comment_reaction).delete(remove_comment_reaction))
+        .route("/api/ideas/:id/revisions", get(list_idea_revisions))
+        .route("/api/ideas/:id/revisions/:revision", get(get_idea_revision))
+        .route("/api/ideas/:id/revisions/:revision/restore", post(restore_idea_revision))
+        .route("/api/ideas/:id/restore", post(restore_idea))
+        .route("/api/ideas/:id/collaborators", get(recommend_collaborators))
+        .route("/api/ideas/:id/similar", get(find_similar_ideas))
+        .route("/api/skills", get(search_skills))
+        .route("/api/projects", get(list_projects).post(create_project))
+        .route("/api/projects/:id/tasks", get(list_tasks).post(create_task))
+        .route("/api/tasks/:id", patch(update_task).delete(delete_task))
+        .route("/api/tasks/:id/restore", post(restore_task))
+        .route("/api/projects/:id/archive", post(archive_project))
+        .route("/api/projects/:id/restore", post(restore_project))
+        .route("/api/projects/:id/team", get(get_team))
+        .route("/api/projects/:id/members/:user_id", patch(update_member_role).delete(remove_member))
+        .route("/api/projects/:id/invitations", get(list_invitations).post(invite_member))
+        .route("/api/users/me/invitations", get(list_my_invitations))
+        .route("/api/invitations", get(get_invitation))
+        .route("/api/invitations/:id", delete(revoke_invitation))
+        .route("/api/invitations/:id/accept", post(accept_invitation))
+        .route("/api/invitations/:id/decline", post(decline_invitation))
+        .route("/api/projects/:id/ownership-transfers", post(transfer_project_ownership))
+        .route("/api/users/me/ownership-transfers", get(list_my_ownership_transfers))
+        .route("/api/ownership-transfers/:id", delete(cancel_ownership_transfer))
+        .route("/api/ownership-transfers/:id/accept", post(accept_ownership_transfer))
+        .route("/api/ownership-transfers/:id/decline", post(decline_ownership_transfer))
+        .route("/api/projects/:id/ratings", post(rate_project))
+        .route_layer(middleware::from_fn_with_state(limiter, rate_limit::enforce))
         .layer(cors)
         .with_state(state);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 4000));
     println!("Gateway listening on {}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     Ok(())
 }
@@ -69,6 +173,9 @@ async fn health_check() -> &'static str {
 
 #[derive(Deserialize)]
 struct CreateUserPayload {
+    #[serde(default)]
+    email: String,
+    #[serde(default)]
     username: String,
     full_name: String,
     bio: String,
@@ -78,18 +185,20 @@ struct CreateUserPayload {
 
 async fn create_user(
     State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
     Json(payload): Json<CreateUserPayload>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let req = shared_proto::user::CreateUserRequest {
         username: payload.username,
         full_name: payload.full_name,
         bio: payload.bio,
         password: payload.password,
         role: payload.role,
+        email: payload.email,
     };
 
-    let resp = state.user_client.create_user(req).await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let resp = state.user_client.create_user(auth::forward(&headers, Some(ip), req)).await?;
     
     let user = resp.into_inner();
     Ok(Json(serde_json::json!({
@@ -98,58 +207,1875 @@ async fn create_user(
     })))
 }
 
+#[derive(Deserialize)]
+struct LoginPayload {
+    email: String,
+    password: String,
+}
+
+async fn login(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<LoginPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::LoginRequest {
+        email: payload.email,
+        password: payload.password,
+    };
+
+    let resp = state.user_client.login(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(login_json(resp)))
+}
+
+fn login_json(resp: shared_proto::user::LoginResponse) -> serde_json::Value {
+    if resp.mfa_required {
+        return serde_json::json!({
+            "mfa_required": true,
+            "mfa_token": resp.mfa_token
+        });
+    }
+
+    let user = resp.user.unwrap_or_default();
+    serde_json::json!({
+        "token": resp.token,
+        "user": {
+            "id": user.id,
+            "username": user.username,
+            "full_name": user.full_name
+        }
+    })
+}
+
+#[derive(Deserialize)]
+struct VerifyLoginChallengePayload {
+    mfa_token: String,
+    code: String,
+}
+
+async fn verify_login_challenge(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<VerifyLoginChallengePayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::VerifyLoginChallengeRequest {
+        mfa_token: payload.mfa_token,
+        code: payload.code,
+    };
+    let resp = state.user_client.verify_login_challenge(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(login_json(resp)))
+}
+
+async fn list_oidc_providers(
+    State(mut state): State<AppState>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let resp = state.user_client.list_oidc_providers(shared_proto::user::ListOidcProvidersRequest {}).await?.into_inner();
+    let providers: Vec<_> = resp.providers.into_iter().map(|p| serde_json::json!({
+        "name": p.name,
+        "display_name": p.display_name
+    })).collect();
+    Ok(Json(serde_json::json!({ "providers": providers })))
+}
+
+async fn begin_oidc_login(
+    State(mut state): State<AppState>,
+    Path(provider): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::BeginOidcLoginRequest { provider };
+    let resp = state.user_client.begin_oidc_login(req).await?.into_inner();
+    Ok(Json(serde_json::json!({
+        "authorization_url": resp.authorization_url,
+        "state": resp.state
+    })))
+}
+
+#[derive(Deserialize)]
+struct CompleteOidcLoginPayload {
+    state: String,
+    code: String,
+}
+
+// The web app receives the provider redirect and posts its query parameters here.
+async fn complete_oidc_login(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Path(provider): Path<String>,
+    Json(payload): Json<CompleteOidcLoginPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::CompleteOidcLoginRequest {
+        provider,
+        state: payload.state,
+        code: payload.code,
+    };
+    let resp = state.user_client.complete_oidc_login(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(login_json(resp)))
+}
+
+fn user_json(user: shared_proto::user::User) -> serde_json::Value {
+    if user.deleted {
+        return serde_json::json!({
+            "id": user.id,
+            "full_name": user.full_name,
+            "deleted": true
+        });
+    }
+
+    let mut json = serde_json::json!({
+        "id": user.id,
+        "full_name": user.full_name,
+        "bio": user.bio,
+        "avatar_url": user.avatar_url,
+        "skills": user.skills,
+        "links": user.links,
+        "role": user.role,
+        "reputation_score": user.reputation_score,
+        "created_at": user.created_at,
+        "idea_count": user.idea_count,
+        "project_count": user.project_count,
+        "open_to_collaborate": user.open_to_collaborate,
+        "hours_per_week": user.hours_per_week
+    });
+    // Only present when the caller may see it (themselves or an admin).
+    if !user.email.is_empty() {
+        json["email"] = serde_json::json!(user.email);
+        json["username"] = serde_json::json!(user.username);
+    }
+    json
+}
+
+async fn get_user(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::GetUserRequest { id: user_id };
+    let user = state.user_client.get_user(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(user_json(user)))
+}
+
+#[derive(Deserialize)]
+struct BatchGetUsersQuery {
+    /// Comma separated user ids.
+    ids: String,
+}
+
+async fn batch_get_users(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Query(query): Query<BatchGetUsersQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let ids = query.ids.split(',').map(str::trim).filter(|id| !id.is_empty()).map(String::from).collect();
+    let req = shared_proto::user::BatchGetUsersRequest { ids };
+    let resp = state.user_client.batch_get_users(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    let users: Vec<_> = resp.users.into_iter().map(user_json).collect();
+    Ok(Json(serde_json::json!({ "users": users })))
+}
+
+// Omitted fields are left unchanged.
+#[derive(Deserialize)]
+struct UpdateUserPayload {
+    full_name: Option<String>,
+    bio: Option<String>,
+    avatar_url: Option<String>,
+    skills: Option<Vec<String>>,
+    links: Option<Vec<String>>,
+    open_to_collaborate: Option<bool>,
+    hours_per_week: Option<i32>,
+}
+
+async fn update_user(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateUserPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let list = |values| shared_proto::user::StringList { values };
+    let req = shared_proto::user::UpdateUserRequest {
+        full_name: payload.full_name,
+        bio: payload.bio,
+        avatar_url: payload.avatar_url,
+        skills: payload.skills.map(list),
+        links: payload.links.map(list),
+        open_to_collaborate: payload.open_to_collaborate,
+        hours_per_week: payload.hours_per_week,
+    };
+    let user = state.user_client.update_user(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(user_json(user)))
+}
+
+#[derive(Deserialize)]
+struct ChangePasswordPayload {
+    current_password: String,
+    new_password: String,
+}
+
+async fn change_password(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<ChangePasswordPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::ChangePasswordRequest {
+        current_password: payload.current_password,
+        new_password: payload.new_password,
+    };
+    let resp = state.user_client.change_password(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(serde_json::json!({ "token": resp.token })))
+}
+
+#[derive(Deserialize)]
+struct ChangeEmailPayload {
+    new_email: String,
+    current_password: String,
+}
+
+async fn change_email(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<ChangeEmailPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::ChangeEmailRequest {
+        new_email: payload.new_email,
+        current_password: payload.current_password,
+    };
+    state.user_client.change_email(auth::forward(&headers, Some(ip), req)).await?;
+    Ok(Json(serde_json::json!({ "confirmation_sent": true })))
+}
+
+#[derive(Deserialize)]
+struct ConfirmEmailChangePayload {
+    token: String,
+}
+
+async fn confirm_email_change(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<ConfirmEmailChangePayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::ConfirmEmailChangeRequest { token: payload.token };
+    let user = state.user_client.confirm_email_change(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(user_json(user)))
+}
+
+#[derive(Deserialize)]
+struct DeleteAccountPayload {
+    current_password: String,
+    #[serde(default)]
+    delete_content: bool,
+}
+
+async fn delete_account(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<DeleteAccountPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::DeleteAccountRequest {
+        current_password: payload.current_password,
+        delete_content: payload.delete_content,
+    };
+    let resp = state.user_client.delete_account(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(serde_json::json!({
+        "deleted": true,
+        "erasure": resp.erasure.map(data_request_json),
+        "status_token": resp.status_token,
+    })))
+}
+
+fn data_request_json(r: shared_proto::user::DataRequest) -> serde_json::Value {
+    serde_json::json!({
+        "id": r.id,
+        "kind": r.kind,
+        "status": r.status,
+        "format": r.format,
+        "error": r.error,
+        "created_at": r.created_at,
+        "completed_at": r.completed_at,
+        "expires_at": r.expires_at,
+        "size_bytes": r.size_bytes,
+    })
+}
+
+#[derive(Deserialize)]
+struct RequestDataExportPayload {
+    #[serde(default)]
+    format: String,
+}
+
+async fn request_data_export(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<RequestDataExportPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::RequestDataExportRequest { format: payload.format };
+    let export = state.user_client.request_data_export(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(data_request_json(export)))
+}
+
+async fn list_data_requests(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::ListDataRequestsRequest {};
+    let resp = state.user_client.list_data_requests(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    let requests: Vec<_> = resp.requests.into_iter().map(data_request_json).collect();
+    Ok(Json(serde_json::json!({ "requests": requests })))
+}
+
+#[derive(Deserialize)]
+struct DataRequestQuery {
+    #[serde(default)]
+    status_token: String,
+}
+
+async fn get_data_request(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<DataRequestQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::GetDataRequestRequest { id, status_token: query.status_token };
+    let resp = state.user_client.get_data_request(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(data_request_json(resp)))
+}
+
+async fn download_data_export(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let req = shared_proto::user::DownloadDataExportRequest { id };
+    let archive = state.user_client.download_data_export(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok((
+        [
+            (header::CONTENT_TYPE, archive.content_type),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", archive.filename)),
+        ],
+        archive.data,
+    ))
+}
+
+fn personal_access_token_json(t: shared_proto::user::PersonalAccessToken) -> serde_json::Value {
+    serde_json::json!({
+        "id": t.id,
+        "name": t.name,
+        "token_prefix": t.token_prefix,
+        "scopes": t.scopes,
+        "expires_at": t.expires_at,
+        "last_used_at": (!t.last_used_at.is_empty()).then_some(t.last_used_at),
+        "created_at": t.created_at,
+        "revoked": t.revoked
+    })
+}
+
+#[derive(Deserialize)]
+struct CreatePersonalAccessTokenPayload {
+    name: String,
+    scopes: Vec<String>,
+    #[serde(default)]
+    expires_in_days: i32,
+}
+
+async fn create_personal_access_token(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<CreatePersonalAccessTokenPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::CreatePersonalAccessTokenRequest {
+        name: payload.name,
+        scopes: payload.scopes,
+        expires_in_days: payload.expires_in_days,
+    };
+    let resp = state.user_client.create_personal_access_token(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(serde_json::json!({
+        "token": resp.token,
+        "details": resp.details.map(personal_access_token_json)
+    })))
+}
+
+async fn list_personal_access_tokens(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::ListPersonalAccessTokensRequest {};
+    let resp = state.user_client.list_personal_access_tokens(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    let tokens: Vec<_> = resp.tokens.into_iter().map(personal_access_token_json).collect();
+    Ok(Json(serde_json::json!({ "tokens": tokens })))
+}
+
+async fn revoke_personal_access_token(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::RevokePersonalAccessTokenRequest { id };
+    state.user_client.revoke_personal_access_token(auth::forward(&headers, Some(ip), req)).await?;
+    Ok(Json(serde_json::json!({ "revoked": true })))
+}
+
+async fn enroll_totp(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::EnrollTotpRequest {};
+    let resp = state.user_client.enroll_totp(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(serde_json::json!({
+        "secret": resp.secret,
+        "otpauth_uri": resp.otpauth_uri
+    })))
+}
+
+#[derive(Deserialize)]
+struct ConfirmTotpPayload {
+    code: String,
+}
+
+async fn confirm_totp(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<ConfirmTotpPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::ConfirmTotpRequest { code: payload.code };
+    let resp = state.user_client.confirm_totp(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(serde_json::json!({ "recovery_codes": resp.recovery_codes })))
+}
+
+#[derive(Deserialize)]
+struct DisableTotpPayload {
+    password: String,
+    code: String,
+}
+
+async fn disable_totp(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<DisableTotpPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::DisableTotpRequest {
+        password: payload.password,
+        code: payload.code,
+    };
+    state.user_client.disable_totp(auth::forward(&headers, Some(ip), req)).await?;
+    Ok(Json(serde_json::json!({ "disabled": true })))
+}
+
+#[derive(Deserialize)]
+struct VerifyEmailPayload {
+    token: String,
+}
+
+async fn verify_email(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<VerifyEmailPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::VerifyEmailRequest { token: payload.token };
+    let user = state.user_client.verify_email(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(serde_json::json!({
+        "id": user.id,
+        "username": user.username,
+        "email_verified": true
+    })))
+}
+
+#[derive(Deserialize)]
+struct ResendVerificationPayload {
+    email: String,
+}
+
+async fn resend_verification(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<ResendVerificationPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::ResendVerificationEmailRequest { email: payload.email };
+    state.user_client.resend_verification_email(auth::forward(&headers, Some(ip), req)).await?;
+    Ok(Json(serde_json::json!({ "sent": true })))
+}
+
+#[derive(Deserialize)]
+struct PasswordResetRequestPayload {
+    email: String,
+}
+
+async fn request_password_reset(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<PasswordResetRequestPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::RequestPasswordResetRequest { email: payload.email };
+    state.user_client.request_password_reset(auth::forward(&headers, Some(ip), req)).await?;
+    Ok(Json(serde_json::json!({ "sent": true })))
+}
+
+#[derive(Deserialize)]
+struct ResetPasswordPayload {
+    token: String,
+    new_password: String,
+}
+
+async fn reset_password(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<ResetPasswordPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::ResetPasswordRequest {
+        token: payload.token,
+        new_password: payload.new_password,
+    };
+    state.user_client.reset_password(auth::forward(&headers, Some(ip), req)).await?;
+    Ok(Json(serde_json::json!({ "reset": true })))
+}
+
+async fn unlock_account(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::UnlockAccountRequest { user_id };
+    let resp = state.user_client.unlock_account(auth::forward(&headers, Some(ip), req)).await?;
+    Ok(Json(serde_json::json!({ "unlocked": resp.into_inner().unlocked })))
+}
+
+#[derive(Deserialize)]
+struct SecurityEventsQuery {
+    #[serde(default)]
+    page_size: i32,
+    #[serde(default)]
+    page_token: String,
+}
+
+async fn list_security_events(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Query(query): Query<SecurityEventsQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::ListSecurityEventsRequest {
+        page_size: query.page_size,
+        page_token: query.page_token,
+    };
+    let resp = state.user_client.list_security_events(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(security_events_json(resp)))
+}
+
+#[derive(Deserialize)]
+struct AdminSecurityEventsQuery {
+    #[serde(default)]
+    user_id: String,
+    #[serde(default)]
+    event_type: String,
+    #[serde(default)]
+    outcome: String,
+    #[serde(default)]
+    ip: String,
+    #[serde(default)]
+    since: String,
+    #[serde(default)]
+    until: String,
+    #[serde(default)]
+    page_size: i32,
+    #[serde(default)]
+    page_token: String,
+}
+
+async fn admin_list_security_events(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Query(query): Query<AdminSecurityEventsQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::AdminListSecurityEventsRequest {
+        user_id: query.user_id,
+        event_type: query.event_type,
+        outcome: query.outcome,
+        ip: query.ip,
+        since: query.since,
+        until: query.until,
+        page_size: query.page_size,
+        page_token: query.page_token,
+    };
+    let resp = state.user_client.admin_list_security_events(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(security_events_json(resp)))
+}
+
+fn security_events_json(resp: shared_proto::user::ListSecurityEventsResponse) -> serde_json::Value {
+    let events: Vec<_> = resp
+        .events
+        .into_iter()
+        .map(|e| {
+            let details: serde_json::Value = serde_json::from_str(&e.details_json).unwrap_or_default();
+            serde_json::json!({
+                "id": e.id,
+                "event_type": e.event_type,
+                "outcome": e.outcome,
+                "user_id": e.user_id,
+                "actor_id": e.actor_id,
+                "email": e.email,
+                "ip": e.ip,
+                "user_agent": e.user_agent,
+                "details": details,
+                "created_at": e.created_at,
+            })
+        })
+        .collect();
+    serde_json::json!({ "events": events, "next_page_token": resp.next_page_token })
+}
+
+#[derive(Deserialize)]
+struct SetUserRolePayload {
+    role: String,
+}
+
+async fn set_user_role(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+    Json(payload): Json<SetUserRolePayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::user::SetUserRoleRequest { user_id, role: payload.role };
+    let user = state.user_client.set_user_role(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(user_json(user)))
+}
+
+fn organization_json(org: shared_proto::organization::Organization) -> serde_json::Value {
+    serde_json::json!({
+        "id": org.id,
+        "name": org.name,
+        "slug": org.slug,
+        "role": org.role,
+        "member_count": org.member_count,
+        "created_at": org.created_at
+    })
+}
+
+fn organization_member_json(member: shared_proto::organization::OrganizationMember) -> serde_json::Value {
+    serde_json::json!({
+        "user_id": member.user_id,
+        "full_name": member.full_name,
+        "email": member.email,
+        "role": member.role,
+        "joined_at": member.joined_at
+    })
+}
+
+#[derive(Deserialize)]
+struct CreateOrganizationPayload {
+    name: String,
+    #[serde(default)]
+    slug: String,
+}
+
+async fn create_organization(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateOrganizationPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::organization::CreateOrganizationRequest { name: payload.name, slug: payload.slug };
+    let org = state.organization_client.create_organization(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(organization_json(org)))
+}
+
+async fn list_my_organizations(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::organization::ListMyOrganizationsRequest {};
+    let resp = state.organization_client.list_my_organizations(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    let organizations: Vec<_> = resp.organizations.into_iter().map(organization_json).collect();
+    Ok(Json(serde_json::json!({ "organizations": organizations })))
+}
+
+async fn get_organization(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::organization::GetOrganizationRequest { id };
+    let org = state.organization_client.get_organization(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(organization_json(org)))
+}
+
+async fn list_organization_members(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Path(org_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::organization::ListOrganizationMembersRequest { org_id };
+    let resp = state.organization_client.list_organization_members(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    let members: Vec<_> = resp.members.into_iter().map(organization_member_json).collect();
+    Ok(Json(serde_json::json!({ "members": members })))
+}
+
+#[derive(Deserialize)]
+struct AddOrganizationMemberPayload {
+    email: String,
+    #[serde(default)]
+    role: String,
+}
+
+async fn add_organization_member(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Path(org_id): Path<String>,
+    Json(payload): Json<AddOrganizationMemberPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::organization::AddOrganizationMemberRequest { org_id, email: payload.email, role: payload.role };
+    let member = state.organization_client.add_organization_member(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(organization_member_json(member)))
+}
+
+#[derive(Deserialize)]
+struct UpdateOrganizationMemberPayload {
+    role: String,
+}
+
+async fn update_organization_member(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Path((org_id, user_id)): Path<(String, String)>,
+    Json(payload): Json<UpdateOrganizationMemberPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::organization::UpdateOrganizationMemberRequest { org_id, user_id, role: payload.role };
+    let member = state.organization_client.update_organization_member(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(organization_member_json(member)))
+}
+
+async fn remove_organization_member(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Path((org_id, user_id)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::organization::RemoveOrganizationMemberRequest { org_id, user_id };
+    state.organization_client.remove_organization_member(auth::forward(&headers, Some(ip), req)).await?;
+    Ok(Json(serde_json::json!({ "removed": true })))
+}
+
+#[derive(Deserialize)]
+struct SwitchOrganizationPayload {
+    #[serde(default)]
+    org_id: String,
+}
+
+async fn switch_organization(
+    State(mut state): State<AppState>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    headers: HeaderMap,
+    Json(payload): Json<SwitchOrganizationPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::organization::SwitchOrganizationRequest { org_id: payload.org_id };
+    let resp = state.organization_client.switch_organization(auth::forward(&headers, Some(ip), req)).await?.into_inner();
+    Ok(Json(serde_json::json!({
+        "token": resp.token,
+        "organization": resp.organization.map(organization_json)
+    })))
+}
+
 #[derive(Deserialize)]
 struct CreateIdeaPayload {
     title: String,
     problem: String,
     solution: String,
-    creator_id: String,
+    #[serde(default)]
+    required_skills: Vec<String>,
+    #[serde(default)]
+    allow_duplicate: bool,
 }
 
+fn similar_idea_json(i: shared_proto::idea::SimilarIdea) -> serde_json::Value {
+    serde_json::json!({
+        "id": i.id,
+        "title": i.title,
+        "creator_id": i.creator_id,
+        "similarity": i.similarity
+    })
+}
+
+/// Posted by the caller, into the workspace their token is switched to. Likely duplicates
+/// come back in `similar_ideas`; where they are blocked, `allow_duplicate` posts anyway.
 async fn create_idea(
     State(mut state): State<AppState>,
+    headers: HeaderMap,
     Json(payload): Json<CreateIdeaPayload>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "ideas:write").await?;
     let req = shared_proto::idea::CreateIdeaRequest {
         title: payload.title,
         problem: payload.problem,
         solution: payload.solution,
-        creator_id: payload.creator_id,
+        creator_id: caller.user_id.clone(),
+        required_skills: payload.required_skills,
+        allow_duplicate: payload.allow_duplicate,
     };
 
-    let resp = state.idea_client.create_idea(req).await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let resp = state.idea_client.create_idea(auth::as_caller(Some(&caller), req)).await?;
 
     let idea = resp.into_inner();
+    let similar_ideas: Vec<_> = idea.similar_ideas.into_iter().map(similar_idea_json).collect();
     Ok(Json(serde_json::json!({
         "id": idea.id,
         "title": idea.title,
-        "status": idea.status
+        "status": idea.status,
+        "required_skills": idea.required_skills,
+        "similar_ideas": similar_ideas,
+        "possible_duplicate_of": (!idea.possible_duplicate_of.is_empty()).then_some(idea.possible_duplicate_of)
     })))
 }
 
+#[derive(Deserialize)]
+struct ListIdeasQuery {
+    #[serde(default)]
+    deleted: bool,
+    #[serde(default)]
+    sort: String,
+    /// 0 leaves it to the service.
+    #[serde(default)]
+    page_size: i32,
+    #[serde(skip)]
+    bookmarked: bool,
+}
+
+/// The public feed, or the organization's ideas when the caller's token is switched to one.
+/// `?sort=` is `new` (default), `top` or `trending`; `?deleted=true` lists the caller's own
+/// deleted ideas that can still be restored.
 async fn list_ideas(
     State(mut state): State<AppState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+    headers: HeaderMap,
+    Query(query): Query<ListIdeasQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = if query.deleted || query.bookmarked {
+        Some(require_user(&state, &headers, "ideas:read").await?)
+    } else {
+        state.authenticator.authenticate(&headers).await
+    };
     let req = shared_proto::idea::ListIdeasRequest {
-        page_size: 10,
+        page_size: query.page_size,
         page_token: "".into(),
+        deleted: query.deleted,
+        sort: query.sort,
+        bookmarked: query.bookmarked,
     };
 
-    let resp = state.idea_client.list_ideas(req).await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let resp = state.idea_client.list_ideas(auth::as_caller(caller.as_ref(), req)).await?;
     
     let ideas = resp.into_inner().ideas;
+
+    // Resolve every creator in one round trip instead of one GetUser per idea.
+    let mut creator_ids: Vec<String> = ideas.iter().map(|i| i.creator_id.clone()).collect();
+    creator_ids.sort();
+    creator_ids.dedup();
+    let creators: HashMap<String, shared_proto::user::User> = state
+        .user_client
+        .batch_get_users(shared_proto::user::BatchGetUsersRequest { ids: creator_ids })
+        .await?
+        .into_inner()
+        .users
+        .into_iter()
+        .map(|u| (u.id.clone(), u))
+        .collect();
     
     // Manual mapping or serde impls if we added them to proto structs (requires modification to build.rs)
     // For now simple manual JSON construction
     let json_ideas: Vec<_> = ideas.into_iter().map(|i| {
+        let creator = creators.get(&i.creator_id).map(|u| serde_json::json!({
+            "id": u.id,
+            "full_name": u.full_name,
+            "avatar_url": u.avatar_url
+        }));
         serde_json::json!({
             "id": i.id,
             "title": i.title,
-            "problem": i.problem
+            "problem": i.problem,
+            "required_skills": i.required_skills,
+            "creator": creator,
+            "upvote_count": i.upvote_count,
+            "bookmark_count": i.bookmark_count,
+            "upvoted": i.upvoted,
+            "bookmarked": i.bookmarked,
+            "possible_duplicate_of": (!i.possible_duplicate_of.is_empty()).then_some(i.possible_duplicate_of),
+            "deleted_at": i.deleted_at
         })
     }).collect();
 
     Ok(Json(serde_json::json!({ "ideas": json_ideas })))
 }
+
+/// The caller's bookmarked ideas, most recently bookmarked first.
+async fn list_my_bookmarks(
+    state: State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let query = ListIdeasQuery { deleted: false, sort: String::new(), page_size: 0, bookmarked: true };
+    list_ideas(state, headers, Query(query)).await
+}
+
+async fn set_idea_upvote(state: &mut AppState, headers: &HeaderMap, id: String, upvoted: bool) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(state, headers, "ideas:write").await?;
+    let req = shared_proto::idea::SetIdeaUpvoteRequest { id, upvoted };
+    let idea = state.idea_client.set_idea_upvote(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(idea_json(idea)))
+}
+
+async fn upvote_idea(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    set_idea_upvote(&mut state, &headers, id, true).await
+}
+
+async fn remove_idea_upvote(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    set_idea_upvote(&mut state, &headers, id, false).await
+}
+
+async fn set_idea_bookmark(state: &mut AppState, headers: &HeaderMap, id: String, bookmarked: bool) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(state, headers, "ideas:write").await?;
+    let req = shared_proto::idea::SetIdeaBookmarkRequest { id, bookmarked };
+    let idea = state.idea_client.set_idea_bookmark(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(idea_json(idea)))
+}
+
+async fn bookmark_idea(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    set_idea_bookmark(&mut state, &headers, id, true).await
+}
+
+async fn remove_idea_bookmark(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    set_idea_bookmark(&mut state, &headers, id, false).await
+}
+
+async fn delete_idea(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "ideas:write").await?;
+    let req = shared_proto::idea::DeleteIdeaRequest { id };
+    state.idea_client.delete_idea(auth::as_caller(Some(&caller), req)).await?;
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
+
+async fn restore_idea(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "ideas:write").await?;
+    let req = shared_proto::idea::RestoreIdeaRequest { id };
+    let idea = state.idea_client.restore_idea(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(serde_json::json!({
+        "id": idea.id,
+        "title": idea.title,
+        "status": idea.status,
+        "required_skills": idea.required_skills
+    })))
+}
+
+#[derive(Deserialize)]
+struct UpdateIdeaPayload {
+    title: Option<String>,
+    problem: Option<String>,
+    solution: Option<String>,
+    required_skills: Option<Vec<String>>,
+}
+
+fn idea_json(idea: shared_proto::idea::Idea) -> serde_json::Value {
+    serde_json::json!({
+        "id": idea.id,
+        "title": idea.title,
+        "problem": idea.problem,
+        "solution": idea.solution,
+        "status": idea.status,
+        "required_skills": idea.required_skills,
+        "revision": idea.revision,
+        "upvote_count": idea.upvote_count,
+        "bookmark_count": idea.bookmark_count,
+        "upvoted": idea.upvoted,
+        "bookmarked": idea.bookmarked
+    })
+}
+
+/// Only the fields present in the body change; each edit that changes something adds a revision.
+async fn update_idea(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateIdeaPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "ideas:write").await?;
+    let req = shared_proto::idea::UpdateIdeaRequest {
+        id,
+        title: payload.title,
+        problem: payload.problem,
+        solution: payload.solution,
+        required_skills: payload.required_skills.map(|names| shared_proto::idea::SkillList { names }),
+    };
+    let idea = state.idea_client.update_idea(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(idea_json(idea)))
+}
+
+fn revision_summary_json(summary: shared_proto::idea::IdeaRevisionSummary) -> serde_json::Value {
+    serde_json::json!({
+        "revision": summary.revision,
+        "author_id": summary.author_id,
+        "changed_fields": summary.changed_fields,
+        "restored_from": (summary.restored_from > 0).then_some(summary.restored_from),
+        "created_at": summary.created_at
+    })
+}
+
+async fn list_idea_revisions(
+    State(mut state): State<AppState>,
+    Path(idea_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = state.authenticator.authenticate(&headers).await;
+    let req = shared_proto::idea::ListIdeaRevisionsRequest { idea_id };
+    let resp = state.idea_client.list_idea_revisions(auth::as_caller(caller.as_ref(), req)).await?.into_inner();
+    let revisions: Vec<_> = resp.revisions.into_iter().map(revision_summary_json).collect();
+    Ok(Json(serde_json::json!({ "revisions": revisions })))
+}
+
+/// The revision's full snapshot and what it changed from the one before.
+async fn get_idea_revision(
+    State(mut state): State<AppState>,
+    Path((idea_id, revision)): Path<(String, i32)>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = state.authenticator.authenticate(&headers).await;
+    let req = shared_proto::idea::GetIdeaRevisionRequest { idea_id, revision };
+    let resp = state.idea_client.get_idea_revision(auth::as_caller(caller.as_ref(), req)).await?.into_inner();
+    let changes: Vec<_> = resp.changes.into_iter().map(|c| serde_json::json!({
+        "field": c.field,
+        "before": c.before,
+        "after": c.after
+    })).collect();
+    let mut json = resp.summary.map(revision_summary_json).unwrap_or_default();
+    json["title"] = resp.title.into();
+    json["problem"] = resp.problem.into();
+    json["solution"] = resp.solution.into();
+    json["required_skills"] = resp.required_skills.into();
+    json["changes"] = changes.into();
+    Ok(Json(json))
+}
+
+async fn restore_idea_revision(
+    State(mut state): State<AppState>,
+    Path((idea_id, revision)): Path<(String, i32)>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "ideas:write").await?;
+    let req = shared_proto::idea::RestoreIdeaRevisionRequest { idea_id, revision };
+    let idea = state.idea_client.restore_idea_revision(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(idea_json(idea)))
+}
+
+fn comment_json(c: shared_proto::idea::IdeaComment, authors: &HashMap<String, shared_proto::user::User>) -> serde_json::Value {
+    let author = authors.get(&c.author_id).map(|u| serde_json::json!({
+        "id": u.id,
+        "full_name": u.full_name,
+        "avatar_url": u.avatar_url
+    }));
+    let reactions: serde_json::Map<String, serde_json::Value> =
+        c.reactions.into_iter().map(|r| (r.reaction, r.count.into())).collect();
+    serde_json::json!({
+        "id": c.id,
+        "idea_id": c.idea_id,
+        "parent_id": (!c.parent_id.is_empty()).then_some(c.parent_id),
+        "author": author,
+        "body": c.body,
+        "depth": c.depth,
+        "reply_count": c.reply_count,
+        "reactions": reactions,
+        "my_reactions": c.my_reactions,
+        "created_at": c.created_at,
+        "edited_at": c.edited_at,
+        "deleted_at": c.deleted_at
+    })
+}
+
+/// Looks up comment authors in one round trip; deleted comments have none.
+async fn comment_authors(
+    state: &mut AppState,
+    comments: &[shared_proto::idea::IdeaComment],
+) -> Result<HashMap<String, shared_proto::user::User>, ApiError> {
+    let mut ids: Vec<String> = comments.iter().filter(|c| !c.author_id.is_empty()).map(|c| c.author_id.clone()).collect();
+    ids.sort();
+    ids.dedup();
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    Ok(state
+        .user_client
+        .batch_get_users(shared_proto::user::BatchGetUsersRequest { ids })
+        .await?
+        .into_inner()
+        .users
+        .into_iter()
+        .map(|u| (u.id.clone(), u))
+        .collect())
+}
+
+async fn comment_response(state: &mut AppState, comment: shared_proto::idea::IdeaComment) -> Result<Json<serde_json::Value>, ApiError> {
+    let authors = comment_authors(state, std::slice::from_ref(&comment)).await?;
+    Ok(Json(comment_json(comment, &authors)))
+}
+
+#[derive(Deserialize)]
+struct ListIdeaCommentsQuery {
+    #[serde(default)]
+    sort: String,
+}
+
+/// The whole thread in display order, each comment followed by its replies. `?sort=` is
+/// `oldest` (default), `newest` or `top`.
+async fn list_idea_comments(
+    State(mut state): State<AppState>,
+    Path(idea_id): Path<String>,
+    headers: HeaderMap,
+    Query(query): Query<ListIdeaCommentsQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = state.authenticator.authenticate(&headers).await;
+    let req = shared_proto::idea::ListIdeaCommentsRequest { idea_id, sort: query.sort };
+    let comments = state.idea_client.list_idea_comments(auth::as_caller(caller.as_ref(), req)).await?.into_inner().comments;
+    let authors = comment_authors(&mut state, &comments).await?;
+    let comments: Vec<_> = comments.into_iter().map(|c| comment_json(c, &authors)).collect();
+    Ok(Json(serde_json::json!({ "comments": comments })))
+}
+
+#[derive(Deserialize)]
+struct CreateIdeaCommentPayload {
+    body: String,
+    #[serde(default)]
+    parent_id: String,
+}
+
+async fn create_idea_comment(
+    State(mut state): State<AppState>,
+    Path(idea_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateIdeaCommentPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "ideas:write").await?;
+    let req = shared_proto::idea::CreateIdeaCommentRequest { idea_id, parent_id: payload.parent_id, body: payload.body };
+    let comment = state.idea_client.create_idea_comment(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    comment_response(&mut state, comment).await
+}
+
+#[derive(Deserialize)]
+struct UpdateIdeaCommentPayload {
+    body: String,
+}
+
+async fn update_idea_comment(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateIdeaCommentPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "ideas:write").await?;
+    let req = shared_proto::idea::UpdateIdeaCommentRequest { id, body: payload.body };
+    let comment = state.idea_client.update_idea_comment(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    comment_response(&mut state, comment).await
+}
+
+async fn delete_idea_comment(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "ideas:write").await?;
+    let req = shared_proto::idea::DeleteIdeaCommentRequest { id };
+    state.idea_client.delete_idea_comment(auth::as_caller(Some(&caller), req)).await?;
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
+
+async fn set_comment_reaction(
+    state: &mut AppState,
+    headers: &HeaderMap,
+    comment_id: String,
+    reaction: String,
+    reacted: bool,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(state, headers, "ideas:write").await?;
+    let req = shared_proto::idea::SetCommentReactionRequest { comment_id, reaction, reacted };
+    let comment = state.idea_client.set_comment_reaction(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    comment_response(state, comment).await
+}
+
+async fn add_comment_reaction(
+    State(mut state): State<AppState>,
+    Path((id, reaction)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    set_comment_reaction(&mut state, &headers, id, reaction, true).await
+}
+
+async fn remove_comment_reaction(
+    State(mut state): State<AppState>,
+    Path((id, reaction)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    set_comment_reaction(&mut state, &headers, id, reaction, false).await
+}
+
+#[derive(Deserialize)]
+struct SearchSkillsQuery {
+    #[serde(default)]
+    q: String,
+    #[serde(default)]
+    limit: i32,
+}
+
+async fn search_skills(
+    State(mut state): State<AppState>,
+    Query(query): Query<SearchSkillsQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::idea::SearchSkillsRequest { query: query.q, limit: query.limit };
+    let resp = state.idea_client.search_skills(req).await?.into_inner();
+    let skills: Vec<_> = resp.skills.into_iter().map(|s| serde_json::json!({
+        "id": s.id,
+        "name": s.name,
+        "user_count": s.user_count
+    })).collect();
+    Ok(Json(serde_json::json!({ "skills": skills })))
+}
+
+#[derive(Deserialize)]
+struct FindSimilarIdeasQuery {
+    #[serde(default)]
+    limit: i32,
+}
+
+async fn find_similar_ideas(
+    State(mut state): State<AppState>,
+    Path(idea_id): Path<String>,
+    headers: HeaderMap,
+    Query(query): Query<FindSimilarIdeasQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = state.authenticator.authenticate(&headers).await;
+    let req = shared_proto::idea::FindSimilarIdeasRequest { idea_id, limit: query.limit };
+    let resp = state.idea_client.find_similar_ideas(auth::as_caller(caller.as_ref(), req)).await?.into_inner();
+    let ideas: Vec<_> = resp.ideas.into_iter().map(similar_idea_json).collect();
+    Ok(Json(serde_json::json!({ "ideas": ideas })))
+}
+
+#[derive(Deserialize)]
+struct RecommendCollaboratorsQuery {
+    #[serde(default)]
+    limit: i32,
+}
+
+async fn recommend_collaborators(
+    State(mut state): State<AppState>,
+    Path(idea_id): Path<String>,
+    headers: HeaderMap,
+    Query(query): Query<RecommendCollaboratorsQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = state.authenticator.authenticate(&headers).await;
+    let req = shared_proto::idea::RecommendCollaboratorsRequest { idea_id, limit: query.limit };
+    let resp = state.idea_client.recommend_collaborators(auth::as_caller(caller.as_ref(), req)).await?.into_inner();
+    let matches: Vec<_> = resp.matches.into_iter().map(|m| serde_json::json!({
+        "user_id": m.user_id,
+        "full_name": m.full_name,
+        "avatar_url": m.avatar_url,
+        "score": m.score,
+        "matched_skills": m.matched_skills,
+        "reputation_score": m.reputation_score,
+        "hours_per_week": m.hours_per_week,
+        "open_tasks": m.open_tasks
+    })).collect();
+    Ok(Json(serde_json::json!({ "matches": matches })))
+}
+
+fn project_json(p: shared_proto::task::Project) -> serde_json::Value {
+    serde_json::json!({
+        "id": p.id,
+        "owner_id": p.owner_id,
+        "name": p.name,
+        "description": p.description,
+        "status": p.status,
+        "funding_goal": p.funding_goal,
+        "equity_offered": p.equity_offered,
+        "is_public": p.is_public,
+        "industry": p.industry,
+        "role": p.role,
+        "archived_at": p.archived_at
+    })
+}
+
+#[derive(Deserialize)]
+struct ListProjectsQuery {
+    #[serde(default)]
+    owner_id: String,
+    #[serde(default)]
+    archived: bool,
+}
+
+/// Projects in the caller's current workspace that they own or are on the team of,
+/// optionally narrowed to one owner. `?archived=true` lists the caller's archived projects
+/// that can still be restored.
+async fn list_projects(
+    State(mut state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ListProjectsQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:read").await?;
+    let req = shared_proto::task::ListProjectsRequest { owner_id: query.owner_id, archived: query.archived };
+    let resp = state.task_client.list_projects(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    let projects: Vec<_> = resp.projects.into_iter().map(project_json).collect();
+    Ok(Json(serde_json::json!({ "projects": projects })))
+}
+
+#[derive(Deserialize)]
+struct CreateProjectPayload {
+    name: String,
+    #[serde(default)]
+    description: String,
+}
+
+async fn create_project(
+    State(mut state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateProjectPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::task::CreateProjectRequest {
+        name: payload.name,
+        description: payload.description,
+        owner_id: caller.user_id.clone(),
+    };
+    let project = state.task_client.create_project(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(project_json(project)))
+}
+
+fn task_json(t: shared_proto::task::Task) -> serde_json::Value {
+    serde_json::json!({
+        "id": t.id,
+        "project_id": t.project_id,
+        "title": t.title,
+        "description": t.description,
+        "status": t.status,
+        "priority": t.priority,
+        "assignee_id": t.assignee_id,
+        "position": t.position,
+        "deleted_at": t.deleted_at
+    })
+}
+
+#[derive(Deserialize)]
+struct ListTasksQuery {
+    #[serde(default)]
+    deleted: bool,
+}
+
+/// `?deleted=true` lists deleted tasks that can still be restored.
+async fn list_tasks(
+    State(mut state): State<AppState>,
+    Path(project_id): Path<String>,
+    headers: HeaderMap,
+    Query(query): Query<ListTasksQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:read").await?;
+    let req = shared_proto::task::ListTasksRequest { project_id, deleted: query.deleted };
+    let resp = state.task_client.list_tasks(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    let tasks: Vec<_> = resp.tasks.into_iter().map(task_json).collect();
+    Ok(Json(serde_json::json!({ "tasks": tasks })))
+}
+
+#[derive(Deserialize)]
+struct CreateTaskPayload {
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    priority: String,
+    #[serde(default)]
+    assignee_id: String,
+}
+
+async fn create_task(
+    State(mut state): State<AppState>,
+    Path(project_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateTaskPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::task::CreateTaskRequest {
+        project_id,
+        title: payload.title,
+        description: payload.description,
+        priority: payload.priority,
+        assignee_id: payload.assignee_id,
+    };
+    let task = state.task_client.create_task(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(task_json(task)))
+}
+
+#[derive(Deserialize)]
+struct UpdateTaskPayload {
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    priority: String,
+    #[serde(default)]
+    position: i32,
+}
+
+async fn update_task(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateTaskPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::task::UpdateTaskRequest { id, status: payload.status, priority: payload.priority, position: payload.position };
+    let resp = state.task_client.update_task(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(resp.task.map(task_json).unwrap_or_default()))
+}
+
+async fn delete_task(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::task::DeleteTaskRequest { id };
+    state.task_client.delete_task(auth::as_caller(Some(&caller), req)).await?;
+    Ok(Json(serde_json::json!({ "deleted": true })))
+}
+
+async fn restore_task(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::task::RestoreTaskRequest { id };
+    let task = state.task_client.restore_task(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(task_json(task)))
+}
+
+async fn archive_project(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::task::ArchiveProjectRequest { id };
+    let project = state.task_client.archive_project(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(project_json(project)))
+}
+
+async fn restore_project(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::task::RestoreProjectRequest { id };
+    let project = state.task_client.restore_project(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(project_json(project)))
+}
+
+/// The signed-in caller, for endpoints whose backend trusts the user id it is given.
+/// Access tokens must also carry `scope`.
+async fn require_user(state: &AppState, headers: &HeaderMap, scope: &str) -> Result<auth::AuthUser, ApiError> {
+    let user = state
+        .authenticator
+        .authenticate(headers)
+        .await
+        .ok_or_else(|| tonic::Status::unauthenticated("Sign in to continue"))?;
+    if !user.has_scope(scope) {
+        return Err(tonic::Status::permission_denied(format!("Access token is missing the '{}' scope", scope)).into());
+    }
+    Ok(user)
+}
+
+#[derive(Deserialize)]
+struct EndorseUserPayload {
+    skill: String,
+}
+
+async fn endorse_user(
+    State(mut state): State<AppState>,
+    Path(user_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<EndorseUserPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "reputation:write").await?;
+    let req = shared_proto::reputation::EndorseUserRequest {
+        endorser_id: caller.user_id.clone(),
+        user_id,
+        skill: payload.skill,
+    };
+    let resp = state.reputation_client.endorse_user(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(serde_json::json!({ "counted": resp.counted })))
+}
+
+fn team_json(team: shared_proto::team::Team) -> serde_json::Value {
+    let members: Vec<_> = team.members.into_iter().map(|m| serde_json::json!({
+        "user_id": m.user_id,
+        "role": m.role,
+        "full_name": m.full_name,
+        "joined_at": m.joined_at
+    })).collect();
+    serde_json::json!({ "project_id": team.project_id, "members": members })
+}
+
+fn invitation_json(i: shared_proto::team::Invitation) -> serde_json::Value {
+    serde_json::json!({
+        "id": i.id,
+        "project_id": i.project_id,
+        "project_name": i.project_name,
+        "email": i.email,
+        "role": i.role,
+        "status": i.status,
+        "invited_by": i.invited_by,
+        "created_at": i.created_at,
+        "expires_at": i.expires_at
+    })
+}
+
+async fn get_team(
+    State(mut state): State<AppState>,
+    Path(project_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:read").await?;
+    let req = shared_proto::team::GetTeamRequest { project_id };
+    let team = state.team_client.get_team(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(team_json(team)))
+}
+
+#[derive(Deserialize)]
+struct UpdateMemberRolePayload {
+    role: String,
+}
+
+async fn update_member_role(
+    State(mut state): State<AppState>,
+    Path((project_id, user_id)): Path<(String, String)>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateMemberRolePayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::team::UpdateMemberRoleRequest { project_id, user_id, role: payload.role };
+    let m = state.team_client.update_member_role(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(serde_json::json!({
+        "user_id": m.user_id,
+        "role": m.role,
+        "full_name": m.full_name,
+        "joined_at": m.joined_at
+    })))
+}
+
+async fn remove_member(
+    State(mut state): State<AppState>,
+    Path((project_id, user_id)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::team::RemoveMemberRequest { project_id, user_id };
+    state.team_client.remove_member(auth::as_caller(Some(&caller), req)).await?;
+    Ok(Json(serde_json::json!({ "removed": true })))
+}
+
+#[derive(Deserialize)]
+struct InviteMemberPayload {
+    email: String,
+    #[serde(default)]
+    role: String,
+}
+
+async fn invite_member(
+    State(mut state): State<AppState>,
+    Path(project_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<InviteMemberPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::team::InviteMemberRequest { project_id, email: payload.email, role: payload.role };
+    let resp = state.team_client.invite_member(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(serde_json::json!({
+        "invitation": resp.invitation.map(invitation_json),
+        "invite_url": resp.invite_url
+    })))
+}
+
+async fn list_invitations(
+    State(mut state): State<AppState>,
+    Path(project_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:read").await?;
+    let req = shared_proto::team::ListInvitationsRequest { project_id };
+    let resp = state.team_client.list_invitations(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    let invitations: Vec<_> = resp.invitations.into_iter().map(invitation_json).collect();
+    Ok(Json(serde_json::json!({ "invitations": invitations })))
+}
+
+async fn revoke_invitation(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::team::RevokeInvitationRequest { id };
+    state.team_client.revoke_invitation(auth::as_caller(Some(&caller), req)).await?;
+    Ok(Json(serde_json::json!({ "revoked": true })))
+}
+
+async fn list_my_invitations(
+    State(mut state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:read").await?;
+    let req = shared_proto::team::ListMyInvitationsRequest {};
+    let resp = state.team_client.list_my_invitations(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    let invitations: Vec<_> = resp.invitations.into_iter().map(invitation_json).collect();
+    Ok(Json(serde_json::json!({ "invitations": invitations })))
+}
+
+#[derive(Deserialize)]
+struct InvitationQuery {
+    #[serde(default)]
+    token: String,
+}
+
+/// What an invite link points at, so the landing page can show it before sign-in.
+async fn get_invitation(
+    State(mut state): State<AppState>,
+    Query(query): Query<InvitationQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::team::GetInvitationRequest { token: query.token };
+    let invitation = state.team_client.get_invitation(req).await?.into_inner();
+    Ok(Json(invitation_json(invitation)))
+}
+
+async fn accept_invitation(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::team::RespondToInvitationRequest { id };
+    let team = state.team_client.accept_invitation(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(team_json(team)))
+}
+
+async fn decline_invitation(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::team::RespondToInvitationRequest { id };
+    state.team_client.decline_invitation(auth::as_caller(Some(&caller), req)).await?;
+    Ok(Json(serde_json::json!({ "declined": true })))
+}
+
+fn transfer_json(t: shared_proto::team::OwnershipTransfer) -> serde_json::Value {
+    serde_json::json!({
+        "id": t.id,
+        "project_id": t.project_id,
+        "project_name": t.project_name,
+        "from_user_id": t.from_user_id,
+        "to_user_id": t.to_user_id,
+        "previous_owner_role": t.previous_owner_role,
+        "status": t.status,
+        "created_at": t.created_at,
+        "expires_at": t.expires_at
+    })
+}
+
+#[derive(Deserialize)]
+struct TransferProjectOwnershipPayload {
+    user_id: String,
+    #[serde(default)]
+    previous_owner_role: String,
+}
+
+async fn transfer_project_ownership(
+    State(mut state): State<AppState>,
+    Path(project_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<TransferProjectOwnershipPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::team::TransferProjectOwnershipRequest {
+        project_id,
+        user_id: payload.user_id,
+        previous_owner_role: payload.previous_owner_role,
+    };
+    let transfer = state.team_client.transfer_project_ownership(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(transfer_json(transfer)))
+}
+
+async fn list_my_ownership_transfers(
+    State(mut state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:read").await?;
+    let req = shared_proto::team::ListMyOwnershipTransfersRequest {};
+    let resp = state.team_client.list_my_ownership_transfers(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    let transfers: Vec<_> = resp.transfers.into_iter().map(transfer_json).collect();
+    Ok(Json(serde_json::json!({ "transfers": transfers })))
+}
+
+async fn cancel_ownership_transfer(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::team::RespondToOwnershipTransferRequest { id };
+    let transfer = state.team_client.cancel_ownership_transfer(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(transfer_json(transfer)))
+}
+
+async fn accept_ownership_transfer(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::team::RespondToOwnershipTransferRequest { id };
+    let team = state.team_client.accept_ownership_transfer(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(team_json(team)))
+}
+
+async fn decline_ownership_transfer(
+    State(mut state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "tasks:write").await?;
+    let req = shared_proto::team::RespondToOwnershipTransferRequest { id };
+    let transfer = state.team_client.decline_ownership_transfer(auth::as_caller(Some(&caller), req)).await?.into_inner();
+    Ok(Json(transfer_json(transfer)))
+}
+
+#[derive(Deserialize)]
+struct RateProjectPayload {
+    rating: i32,
+}
+
+async fn rate_project(
+    State(mut state): State<AppState>,
+    Path(project_id): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<RateProjectPayload>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let caller = require_user(&state, &headers, "reputation:write").await?;
+    let req = shared_proto::reputation::RateProjectRequest {
+        investor_id: caller.user_id.clone(),
+        project_id,
+        rating: payload.rating,
+    };
+    state.reputation_client.rate_project(auth::as_caller(Some(&caller), req)).await?;
+    Ok(Json(serde_json::json!({ "rating": payload.rating })))
+}
+
+async fn get_reputation_breakdown(
+    State(mut state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let req = shared_proto::reputation::GetReputationBreakdownRequest { user_id };
+    let resp = state.reputation_client.get_reputation_breakdown(req).await?.into_inner();
+    let components: Vec<_> = resp.components.into_iter().map(|c| serde_json::json!({
+        "kind": c.kind,
+        "events": c.events,
+        "counted_events": c.counted_events,
+        "points": c.points
+    })).collect();
+    let recent_events: Vec<_> = resp.recent_events.into_iter().map(|e| serde_json::json!({
+        "kind": e.kind,
+        "base_points": e.base_points,
+        "current_points": e.current_points,
+        "counted": e.counted,
+        "created_at": e.created_at
+    })).collect();
+    Ok(Json(serde_json::json!({
+        "user_id": resp.user_id,
+        "score": resp.score,
+        "components": components,
+        "recent_events": recent_events,
+        "computed_at": resp.computed_at
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_json_leaves_out_private_fields_unless_sent() {
+        let user = shared_proto::user::User {
+            id: "u1".into(),
+            full_name: "Ada".into(),
+            skills: vec!["Rust".into()],
+            ..Default::default()
+        };
+        let public = user_json(user.clone());
+        assert_eq!(public["full_name"], "Ada");
+        assert_eq!(public["skills"], serde_json::json!(["Rust"]));
+        assert!(public.get("email").is_none() && public.get("username").is_none());
+
+        let own = user_json(shared_proto::user::User { email: "ada@example.com".into(), username: "ada@example.com".into(), ..user });
+        assert_eq!(own["email"], "ada@example.com");
+        assert_eq!(own["username"], "ada@example.com");
+    }
+
+    #[test]
+    fn deleted_users_show_only_their_placeholder() {
+        let json = user_json(shared_proto::user::User {
+            id: "u1".into(),
+            full_name: "Deleted user".into(),
+            deleted: true,
+            bio: "left over".into(),
+            ..Default::default()
+        });
+        assert_eq!(json, serde_json::json!({ "id": "u1", "full_name": "Deleted user", "deleted": true }));
+    }
+}
@@ -0,0 +1,389 @@
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{header::RETRY_AFTER, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use redis::aio::ConnectionManager;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::auth::Authenticator;
+
+// --- Configuration ---
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyBy {
+    /// Client IP address.
+    Ip,
+    /// Authenticated user id; anonymous callers fall back to their IP.
+    User,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Limit {
+    pub limit: u64,
+    pub window_secs: u64,
+    pub key: KeyBy,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteRule {
+    pub name: String,
+    /// Axum route pattern, e.g. `/api/ideas`.
+    pub path: String,
+    /// HTTP method; omit to match every method on the path.
+    pub method: Option<String>,
+    #[serde(flatten)]
+    pub limit: Limit,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+    /// Only enable behind a proxy that overwrites `X-Forwarded-For`.
+    #[serde(default)]
+    pub trust_forwarded_for: bool,
+    /// Applied to every route without a dedicated rule.
+    pub default: Option<Limit>,
+    #[serde(default)]
+    pub routes: Vec<RouteRule>,
+}
+
+const DEFAULT_CONFIG: &str = include_str!("../rate_limits.toml");
+
+impl RateLimitConfig {
+    /// Loads the file named by `RATE_LIMIT_CONFIG`, or the bundled `rate_limits.toml`.
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("RATE_LIMIT_CONFIG") {
+            Ok(path) => {
+                let raw = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read rate limit config {}: {}", path, e))?;
+                toml::from_str(&raw).map_err(|e| format!("Invalid rate limit config {}: {}", path, e))
+            }
+            Err(_) => toml::from_str(DEFAULT_CONFIG).map_err(|e| format!("Invalid bundled rate limit config: {}", e)),
+        }
+    }
+
+    fn rules_for(&self, method: &str, path: &str) -> Vec<(&str, &Limit)> {
+        let matched: Vec<_> = self
+            .routes
+            .iter()
+            .filter(|r| r.path == path && r.method.as_deref().is_none_or(|m| m.eq_ignore_ascii_case(method)))
+            .map(|r| (r.name.as_str(), &r.limit))
+            .collect();
+
+        if matched.is_empty() {
+            self.default.iter().map(|l| ("default", l)).collect()
+        } else {
+            matched
+        }
+    }
+}
+
+// --- Storage ---
+
+/// Sliding window counter: the previous fixed window is weighted by how much of it
+/// still overlaps the sliding window, which smooths bursts at window boundaries.
+#[derive(Debug, Default, Clone, Copy)]
+struct WindowCounts {
+    window: u64,
+    window_secs: u64,
+    current: u64,
+    previous: u64,
+}
+
+impl WindowCounts {
+    /// Whether the counts can still matter at `now_secs`: as the current window, or as the
+    /// previous one carried into the next.
+    fn live_at(&self, now_secs: u64) -> bool {
+        (self.window + 2) * self.window_secs > now_secs
+    }
+}
+
+/// The sliding-window count: every hit in the current window plus the share of the
+/// previous window's hits still inside the sliding window, `elapsed` (0..1) into it.
+fn estimate(previous: u64, current: u64, elapsed: f64) -> f64 {
+    previous as f64 * (1.0 - elapsed) + current as f64
+}
+
+/// Milliseconds from `offset_ms` into the current window until the estimate leaves room
+/// for one more hit. The previous window's weight fades as the window goes on, so the
+/// wait can end before the window does; once the current window has had more than its
+/// share, it has to fade in turn as the next window's previous.
+fn retry_after_ms(limit: u64, previous: u64, current: u64, offset_ms: u64, window_ms: u64) -> u64 {
+    let room = limit.saturating_sub(1) as f64;
+    let window = window_ms as f64;
+    let at = if current as f64 <= room {
+        if previous == 0 {
+            return 0;
+        }
+        window * (1.0 - (room - current as f64) / previous as f64)
+    } else {
+        window * (2.0 - room / current as f64)
+    };
+    (at.ceil() as u64).saturating_sub(offset_ms)
+}
+
+enum Store {
+    Redis(ConnectionManager),
+    Memory(Mutex<HashMap<String, WindowCounts>>),
+}
+
+const MEMORY_PRUNE_THRESHOLD: usize = 10_000;
+
+impl Store {
+    /// Records a hit and returns `(previous, current)` counts for the window.
+    async fn hit(&self, key: &str, window: u64, window_secs: u64) -> Result<(u64, u64), redis::RedisError> {
+        match self {
+            Store::Redis(conn) => {
+                let mut conn = conn.clone();
+                let current_key = format!("rl:{}:{}", key, window);
+                let previous_key = format!("rl:{}:{}", key, window.saturating_sub(1));
+                let (current, previous): (u64, Option<u64>) = redis::pipe()
+                    .atomic()
+                    .incr(&current_key, 1)
+                    .expire(&current_key, (window_secs * 2) as i64)
+                    .ignore()
+                    .get(&previous_key)
+                    .query_async(&mut conn)
+                    .await?;
+                Ok((previous.unwrap_or(0), current))
+            }
+            Store::Memory(map) => {
+                let mut map = map.lock().unwrap();
+                if map.len() > MEMORY_PRUNE_THRESHOLD {
+                    // Entries come from rules with different window lengths, so compare
+                    // times rather than window numbers. The start of this window is no
+                    // later than now, so nothing still live is dropped.
+                    let now_secs = window * window_secs;
+                    map.retain(|_, c| c.live_at(now_secs));
+                }
+                let counts = map.entry(key.to_string()).or_default();
+                counts.window_secs = window_secs;
+                if counts.window != window {
+                    counts.previous = if counts.window + 1 == window { counts.current } else { 0 };
+                    counts.current = 0;
+                    counts.window = window;
+                }
+                counts.current += 1;
+                Ok((counts.previous, counts.current))
+            }
+        }
+    }
+}
+
+// --- Limiter ---
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    store: Store,
+    authenticator: Arc<Authenticator>,
+}
+
+pub enum Decision {
+    Allowed,
+    Limited { retry_after_secs: u64 },
+}
+
+impl RateLimiter {
+    /// Uses Redis when `redis_url` is set and reachable, otherwise a per-process
+    /// in-memory store (fine for local dev, not shared across gateway replicas).
+    pub async fn new(config: RateLimitConfig, redis_url: Option<String>, authenticator: Arc<Authenticator>) -> Self {
+        let store = match redis_url {
+            Some(url) => match Self::connect(&url).await {
+                Ok(conn) => {
+                    tracing::info!("Rate limiter using Redis at {}", url);
+                    Store::Redis(conn)
+                }
+                Err(e) => {
+                    tracing::warn!("Redis unavailable ({}), falling back to in-memory rate limiting", e);
+                    Store::Memory(Mutex::new(HashMap::new()))
+                }
+            },
+            None => {
+                tracing::info!("REDIS_URL not set, using in-memory rate limiting");
+                Store::Memory(Mutex::new(HashMap::new()))
+            }
+        };
+
+        Self { config, store, authenticator }
+    }
+
+    async fn connect(url: &str) -> Result<ConnectionManager, redis::RedisError> {
+        let client = redis::Client::open(url)?;
+        ConnectionManager::new(client).await
+    }
+
+    pub async fn check(&self, method: &str, path: &str, ip: IpAddr, headers: &HeaderMap) -> Decision {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let mut retry_after_secs = 0;
+        // Resolved at most once however many rules key by user; the handler's own lookup
+        // then finds the answer cached.
+        let caller = tokio::sync::OnceCell::new();
+
+        for (name, limit) in self.config.rules_for(method, path) {
+            let subject = match limit.key {
+                KeyBy::Ip => format!("ip:{}", ip),
+                KeyBy::User => match caller.get_or_init(|| self.authenticator.authenticate(headers)).await {
+                    Some(user) => format!("user:{}", user.user_id),
+                    None => format!("ip:{}", ip),
+                },
+            };
+
+            let window_ms = limit.window_secs.max(1) * 1000;
+            let window = now_ms / window_ms;
+            let key = format!("{}:{}", name, subject);
+
+            let (previous, current) = match self.store.hit(&key, window, limit.window_secs.max(1)).await {
+                Ok(counts) => counts,
+                Err(e) => {
+                    // Fail open: an outage of the limiter must not take the API down with it.
+                    tracing::warn!("Rate limiter store error: {}", e);
+                    continue;
+                }
+            };
+
+            let offset_ms = now_ms % window_ms;
+            if estimate(previous, current, offset_ms as f64 / window_ms as f64) > limit.limit as f64 {
+                let wait = retry_after_ms(limit.limit, previous, current, offset_ms, window_ms).div_ceil(1000);
+                retry_after_secs = retry_after_secs.max(wait.max(1));
+            }
+        }
+
+        if retry_after_secs > 0 {
+            Decision::Limited { retry_after_secs }
+        } else {
+            Decision::Allowed
+        }
+    }
+
+    fn client_ip(&self, peer: SocketAddr, headers: &HeaderMap) -> IpAddr {
+        if self.config.trust_forwarded_for {
+            let forwarded = headers
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.split(',').next())
+                .and_then(|v| v.trim().parse().ok());
+            if let Some(ip) = forwarded {
+                return ip;
+            }
+        }
+        peer.ip()
+    }
+}
+
+/// Resolved client address, stored in request extensions for handlers to forward.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+/// Axum middleware; install with `route_layer` so `MatchedPath` is available.
+pub async fn enforce(
+    State(limiter): State<Arc<RateLimiter>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let ip = limiter.client_ip(peer, request.headers());
+    request.extensions_mut().insert(ClientIp(ip));
+
+    match limiter.check(request.method().as_str(), &path, ip, request.headers()).await {
+        Decision::Allowed => next.run(request).await,
+        Decision::Limited { retry_after_secs } => {
+            let mut response = (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(serde_json::json!({ "error": "Too many requests, slow down" })),
+            )
+                .into_response();
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE_MS: u64 = 60_000;
+
+    #[test]
+    fn previous_window_fades_across_the_current_one() {
+        assert_eq!(estimate(10, 4, 0.0), 14.0);
+        assert_eq!(estimate(10, 4, 0.25), 11.5);
+        assert_eq!(estimate(10, 4, 1.0), 4.0);
+    }
+
+    #[test]
+    fn retry_after_ends_once_carry_over_has_faded() {
+        // 9 more hits fit once the previous 20 weigh no more than 4: 80% into the window.
+        assert_eq!(retry_after_ms(10, 20, 5, 0, MINUTE_MS), 48_000);
+        assert_eq!(retry_after_ms(10, 20, 5, 30_000, MINUTE_MS), 18_000);
+    }
+
+    #[test]
+    fn retry_after_runs_into_the_next_window_when_this_one_is_full() {
+        let wait = retry_after_ms(10, 0, 11, 30_000, MINUTE_MS);
+        assert!(wait > 30_000, "has to outlast the current window, got {}", wait);
+        assert_eq!(wait, 40_910);
+    }
+
+    #[test]
+    fn retry_after_leaves_room_for_one_more_hit() {
+        for (limit, previous, current, offset) in [(10, 20, 5, 0), (10, 3, 12, 59_000), (5, 50, 1, 10_000), (100, 150, 40, 1)] {
+            let at = offset + retry_after_ms(limit, previous, current, offset, MINUTE_MS);
+            let (previous, current, at) = if at < MINUTE_MS { (previous, current, at) } else { (current, 0, at - MINUTE_MS) };
+            let estimated = estimate(previous, current, at as f64 / MINUTE_MS as f64) + 1.0;
+            assert!(estimated <= limit as f64 + 1e-9, "{} hits estimated after waiting", estimated);
+        }
+    }
+
+    #[test]
+    fn limit_of_zero_waits_out_both_windows() {
+        assert_eq!(retry_after_ms(0, 0, 1, 0, MINUTE_MS), 2 * MINUTE_MS);
+    }
+
+    #[tokio::test]
+    async fn memory_counts_roll_into_the_previous_window() {
+        let store = Store::Memory(Mutex::new(HashMap::new()));
+        assert_eq!(store.hit("k", 7, 60).await.unwrap(), (0, 1));
+        assert_eq!(store.hit("k", 7, 60).await.unwrap(), (0, 2));
+        assert_eq!(store.hit("k", 8, 60).await.unwrap(), (2, 1));
+        assert_eq!(store.hit("k", 10, 60).await.unwrap(), (0, 1));
+    }
+
+    #[tokio::test]
+    async fn pruning_keeps_live_counts_of_longer_windows() {
+        let store = Store::Memory(Mutex::new(HashMap::new()));
+        let now_secs = 1_000_000 * 60;
+        let hour = now_secs / 3600;
+        store.hit("login:ip:1", hour, 3600).await.unwrap();
+        store.hit("stale", hour - 2, 3600).await.unwrap();
+        for i in 0..=MEMORY_PRUNE_THRESHOLD {
+            store.hit(&format!("old:{}", i), now_secs / 60 - 5, 60).await.unwrap();
+        }
+
+        store.hit("default:ip:2", now_secs / 60, 60).await.unwrap();
+
+        let Store::Memory(map) = &store else { unreachable!() };
+        let map = map.lock().unwrap();
+        assert!(map.contains_key("login:ip:1"));
+        assert!(!map.contains_key("stale"));
+        assert!(!map.contains_key("old:0"));
+        assert_eq!(map.len(), 2);
+    }
+}
@@ -0,0 +1,87 @@
+//! Account deletion. The `users` row is anonymized rather than removed: ideas and
+//! projects other people collaborate on keep a valid owner ("Deleted user") while
+//! everything that identifies the person is dropped. This is the identity half; the
+//! erasure request queued alongside it clears the user's content in svc-brain-core.
+
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+pub const DELETED_USER_NAME: &str = "Deleted user";
+
+/// `unusable_password_hash` must be a valid hash nobody knows the password for.
+pub async fn delete_account(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    unusable_password_hash: &str,
+) -> Result<(), sqlx::Error> {
+    let email: String = sqlx::query_scalar("SELECT email FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&mut **tx)
+        .await?;
+
+    // Rows hanging off the user that have no value once the person is gone.
+    for table in ["user_skills", "user_identities", "totp_recovery_codes", "password_reset_tokens", "login_attempts", "personal_access_tokens"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE user_id = $1", table))
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await?;
+    }
+    // Failed logins from before the account existed were recorded by address alone.
+    sqlx::query("DELETE FROM login_attempts WHERE user_id IS NULL AND LOWER(email) = LOWER($1)")
+        .bind(&email)
+        .execute(&mut **tx)
+        .await?;
+    // The security log is append-only but for this: the entries stay, without the person's
+    // email, user agent or IP. A salted hash stands in for the IP so entries from one address
+    // still line up; the salt isn't kept, so the address can't be recovered.
+    sqlx::query(
+        "UPDATE auth_events SET email = NULL, user_agent = NULL, details = details - 'new_email', \
+         ip = 'erased:' || LEFT(ENCODE(SHA256(CONVERT_TO(ip || $3, 'UTF8')), 'hex'), 16) \
+         WHERE user_id = $1 OR (user_id IS NULL AND LOWER(email) = LOWER($2))",
+    )
+    .bind(user_id)
+    .bind(&email)
+    .bind(Uuid::new_v4().to_string())
+    .execute(&mut **tx)
+    .await?;
+    // Organizations outlive their members. One the user was the last owner of passes to its
+    // longest-standing admin, or failing that its longest-standing member.
+    let orgs: Vec<Uuid> = sqlx::query_scalar("DELETE FROM organization_members WHERE user_id = $1 RETURNING org_id")
+        .bind(user_id)
+        .fetch_all(&mut **tx)
+        .await?;
+    sqlx::query(
+        "UPDATE organization_members m SET role = 'owner' \
+         FROM (SELECT DISTINCT ON (org_id) org_id, user_id FROM organization_members \
+               WHERE org_id = ANY($1) AND org_id NOT IN (SELECT org_id FROM organization_members WHERE role = 'owner') \
+               ORDER BY org_id, role = 'admin' DESC, created_at) heir \
+         WHERE m.org_id = heir.org_id AND m.user_id = heir.user_id",
+    )
+    .bind(&orgs)
+    .execute(&mut **tx)
+    .await?;
+    // Earlier exports hold exactly the data being erased.
+    sqlx::query("DELETE FROM data_requests WHERE user_id = $1 AND kind = 'export'")
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+    // The placeholder address keeps the UNIQUE NOT NULL constraint happy and frees the real one
+    // for a new signup; bumping session_version revokes every token still out there.
+    sqlx::query(
+        "UPDATE users SET email = 'deleted-' || id || '@deleted.invalid', pending_email = NULL, \
+         password_hash = $2, full_name = $3, bio = NULL, avatar_url = NULL, links = '{}', \
+         email_verified = FALSE, email_verified_at = NULL, reputation_score = 0, \
+         totp_secret = NULL, totp_enabled = FALSE, totp_last_used_step = NULL, \
+         failed_login_attempts = 0, last_failed_login_at = NULL, locked_until = NULL, \
+         session_version = session_version + 1, deleted_at = NOW() \
+         WHERE id = $1",
+    )
+    .bind(user_id)
+    .bind(unusable_password_hash)
+    .bind(DELETED_USER_NAME)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
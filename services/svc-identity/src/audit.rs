@@ -0,0 +1,243 @@
+//! Append-only security audit log (`auth_events`): who did what to which account,
+//! from where, and whether it worked.
+
+use shared_proto::user::SecurityEvent;
+use sqlx::{PgPool, Row};
+use tonic::{Request, Status};
+use uuid::Uuid;
+
+use crate::auth;
+
+pub const SIGNUP: &str = "signup";
+pub const LOGIN: &str = "login";
+/// Password accepted, second factor still to come.
+pub const MFA_CHALLENGE: &str = "mfa_challenge";
+pub const REAUTHENTICATION: &str = "reauthentication";
+pub const EMAIL_VERIFIED: &str = "email_verified";
+pub const PASSWORD_CHANGED: &str = "password_changed";
+pub const PASSWORD_RESET_REQUESTED: &str = "password_reset_requested";
+pub const PASSWORD_RESET: &str = "password_reset";
+pub const EMAIL_CHANGE_REQUESTED: &str = "email_change_requested";
+pub const EMAIL_CHANGED: &str = "email_changed";
+pub const TOTP_ENABLED: &str = "totp_enabled";
+pub const TOTP_DISABLED: &str = "totp_disabled";
+pub const ACCOUNT_UNLOCKED: &str = "account_unlocked";
+pub const ROLE_CHANGED: &str = "role_changed";
+pub const ACCESS_TOKEN_CREATED: &str = "access_token_created";
+pub const ACCESS_TOKEN_REVOKED: &str = "access_token_revoked";
+pub const ACCOUNT_DELETED: &str = "account_deleted";
+pub const DATA_EXPORT_REQUESTED: &str = "data_export_requested";
+pub const DATA_EXPORT_DOWNLOADED: &str = "data_export_downloaded";
+pub const ORGANIZATION_CREATED: &str = "organization_created";
+pub const ORGANIZATION_MEMBER_ADDED: &str = "organization_member_added";
+pub const ORGANIZATION_MEMBER_ROLE_CHANGED: &str = "organization_member_role_changed";
+pub const ORGANIZATION_MEMBER_REMOVED: &str = "organization_member_removed";
+pub const ORGANIZATION_SWITCHED: &str = "organization_switched";
+
+const MAX_USER_AGENT_CHARS: usize = 512; // auth_events.user_agent is VARCHAR(512)
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// Where a request came from, captured before the handler consumes it.
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl Context {
+    pub fn from_request<T>(request: &Request<T>) -> Self {
+        let user_agent = request
+            .metadata()
+            .get("x-client-user-agent")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.chars().take(MAX_USER_AGENT_CHARS).collect::<String>())
+            .filter(|v| !v.is_empty());
+        Self { ip: auth::client_ip(request), user_agent }
+    }
+}
+
+pub struct Entry {
+    event_type: &'static str,
+    success: bool,
+    user_id: Option<Uuid>,
+    actor_id: Option<Uuid>,
+    email: Option<String>,
+    details: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Entry {
+    pub fn success(event_type: &'static str, user_id: Uuid) -> Self {
+        Self { event_type, success: true, user_id: Some(user_id), actor_id: None, email: None, details: Default::default() }
+    }
+
+    /// `reason` is a short machine-readable code such as `bad_password`.
+    pub fn failure(event_type: &'static str, user_id: Option<Uuid>, reason: &str) -> Self {
+        Self { event_type, success: false, user_id, actor_id: None, email: None, details: Default::default() }
+            .detail("reason", reason)
+    }
+
+    pub fn actor(mut self, actor_id: Uuid) -> Self {
+        self.actor_id = Some(actor_id);
+        self
+    }
+
+    pub fn email(mut self, email: &str) -> Self {
+        self.email = Some(email.chars().take(255).collect());
+        self
+    }
+
+    pub fn detail(mut self, key: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.details.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+pub struct Filter {
+    pub user_id: Option<Uuid>,
+    pub event_type: Option<String>,
+    pub success: Option<bool>,
+    pub ip: Option<String>,
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    pool: PgPool,
+}
+
+impl AuditLog {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Best effort, like the notification emails: a failed write is logged loudly but
+    /// doesn't fail (or worse, half-complete) the action being audited.
+    pub async fn record(&self, ctx: &Context, entry: Entry) {
+        let result = sqlx::query(
+            "INSERT INTO auth_events (id, event_type, outcome, user_id, actor_id, email, ip, user_agent, details) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(entry.event_type)
+        .bind(if entry.success { "success" } else { "failure" })
+        .bind(entry.user_id)
+        .bind(entry.actor_id)
+        .bind(&entry.email)
+        .bind(&ctx.ip)
+        .bind(&ctx.user_agent)
+        .bind(serde_json::Value::Object(entry.details))
+        .execute(&self.pool)
+        .await;
+
+        if let Err(e) = result {
+            tracing::error!("Failed to write '{}' audit event for {:?}: {}", entry.event_type, entry.user_id, e);
+        }
+    }
+
+    /// Newest first. The page token is the id of the last event on the previous page.
+    pub async fn list(&self, filter: &Filter, page_size: i32, page_token: &str) -> Result<(Vec<SecurityEvent>, String), Status> {
+        let limit = if page_size <= 0 { DEFAULT_PAGE_SIZE } else { (page_size as i64).min(MAX_PAGE_SIZE) };
+        let after = if page_token.is_empty() {
+            None
+        } else {
+            Some(Uuid::parse_str(page_token).map_err(|_| Status::invalid_argument("Invalid page token"))?)
+        };
+
+        let rows = sqlx::query(
+            "SELECT id, event_type, outcome, user_id, actor_id, email, ip, user_agent, details, created_at \
+             FROM auth_events \
+             WHERE ($1::UUID IS NULL OR user_id = $1) \
+             AND ($2::TEXT IS NULL OR event_type = $2) \
+             AND ($3::TEXT IS NULL OR outcome = $3) \
+             AND ($4::TEXT IS NULL OR ip = $4) \
+             AND ($5::TIMESTAMPTZ IS NULL OR created_at >= $5) \
+             AND ($6::TIMESTAMPTZ IS NULL OR created_at < $6) \
+             AND ($7::UUID IS NULL OR (created_at, id) < (SELECT created_at, id FROM auth_events WHERE id = $7)) \
+             ORDER BY created_at DESC, id DESC LIMIT $8",
+        )
+        .bind(filter.user_id)
+        .bind(&filter.event_type)
+        .bind(filter.success.map(|s| if s { "success" } else { "failure" }))
+        .bind(&filter.ip)
+        .bind(filter.since)
+        .bind(filter.until)
+        .bind(after)
+        .bind(limit + 1)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        let more = rows.len() as i64 > limit;
+        let events: Vec<SecurityEvent> = rows.iter().take(limit as usize).map(event_from_row).collect();
+        let next_page_token = if more { events.last().map(|e| e.id.clone()).unwrap_or_default() } else { String::new() };
+        Ok((events, next_page_token))
+    }
+}
+
+fn event_from_row(row: &sqlx::postgres::PgRow) -> SecurityEvent {
+    let uuid = |column: &str| row.get::<Option<Uuid>, _>(column).map(|u| u.to_string()).unwrap_or_default();
+    SecurityEvent {
+        id: row.get::<Uuid, _>("id").to_string(),
+        event_type: row.get("event_type"),
+        outcome: row.get("outcome"),
+        user_id: uuid("user_id"),
+        actor_id: uuid("actor_id"),
+        email: row.get::<Option<String>, _>("email").unwrap_or_default(),
+        ip: row.get::<Option<String>, _>("ip").unwrap_or_default(),
+        user_agent: row.get::<Option<String>, _>("user_agent").unwrap_or_default(),
+        details_json: row.get::<serde_json::Value, _>("details").to_string(),
+        created_at: row.get::<chrono::DateTime<chrono::Utc>, _>("created_at").to_rfc3339(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(headers: &[(&'static str, &str)]) -> Request<()> {
+        let mut request = Request::new(());
+        for (name, value) in headers {
+            request.metadata_mut().insert(*name, value.parse().unwrap());
+        }
+        request
+    }
+
+    #[test]
+    fn context_takes_the_client_ip_and_user_agent_from_the_gateway() {
+        let ctx = Context::from_request(&request(&[
+            ("x-forwarded-for", "203.0.113.7, 10.0.0.2"),
+            ("x-client-user-agent", "Mozilla/5.0"),
+        ]));
+        assert_eq!(ctx.ip.as_deref(), Some("203.0.113.7"));
+        assert_eq!(ctx.user_agent.as_deref(), Some("Mozilla/5.0"));
+
+        let long = "a".repeat(MAX_USER_AGENT_CHARS + 10);
+        let ctx = Context::from_request(&request(&[("x-client-user-agent", &long)]));
+        assert_eq!(ctx.user_agent.map(|ua| ua.len()), Some(MAX_USER_AGENT_CHARS));
+
+        let ctx = Context::from_request(&request(&[("x-client-user-agent", "")]));
+        assert!(ctx.ip.is_none() && ctx.user_agent.is_none());
+    }
+
+    #[test]
+    fn failures_carry_their_reason() {
+        let entry = Entry::failure(LOGIN, None, "unknown_account").email("ada@example.com");
+        assert!(!entry.success);
+        assert_eq!(entry.details["reason"], "unknown_account");
+        assert_eq!(entry.email.as_deref(), Some("ada@example.com"));
+    }
+
+    #[test]
+    fn entries_record_actor_details_and_bounded_emails() {
+        let (user, admin) = (Uuid::new_v4(), Uuid::new_v4());
+        let entry = Entry::success(ROLE_CHANGED, user).actor(admin).detail("from", "creator").detail("to", "admin");
+        assert!(entry.success);
+        assert_eq!((entry.user_id, entry.actor_id), (Some(user), Some(admin)));
+        assert_eq!(serde_json::Value::Object(entry.details), serde_json::json!({ "from": "creator", "to": "admin" }));
+
+        let entry = Entry::failure(LOGIN, None, "unknown_account").email(&"a".repeat(300));
+        assert_eq!(entry.email.map(|e| e.len()), Some(255));
+    }
+}
@@ -0,0 +1,343 @@
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row};
+use std::net::SocketAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tonic::{Request, Status};
+use uuid::Uuid;
+
+use crate::pat;
+
+const TOKEN_TTL_SECS: u64 = 3600 * 24;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub exp: usize,
+    pub user_id: String,
+    /// `users.session_version` at issue time; bumping it revokes every token issued before.
+    #[serde(default)]
+    pub sv: i32,
+    /// Set when the caller used a personal access token instead of a session; never part of a JWT.
+    #[serde(skip)]
+    pub scopes: Option<Vec<String>>,
+    /// Active organization, chosen with `SwitchOrganization`; `None` is the personal space.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org: Option<String>,
+}
+
+/// Claims of short-lived single-purpose tokens (email links, login challenges).
+/// `email` pins the token to the address the account had when it was issued.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PurposeClaims {
+    pub sub: String,
+    pub email: String,
+    pub purpose: String,
+    pub exp: usize,
+}
+
+pub const PURPOSE_VERIFY_EMAIL: &str = "verify_email";
+pub const PURPOSE_MFA_LOGIN: &str = "mfa_login";
+pub const PURPOSE_CHANGE_EMAIL: &str = "change_email";
+
+pub struct JwtKeys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+}
+
+impl std::fmt::Debug for JwtKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("JwtKeys")
+    }
+}
+
+impl JwtKeys {
+    pub fn new(secret: &str) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            decoding: DecodingKey::from_secret(secret.as_bytes()),
+        }
+    }
+
+    pub fn issue(&self, email: &str, user_id: Uuid, session_version: i32, org: Option<Uuid>) -> Result<String, Status> {
+        let expiration = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() + TOKEN_TTL_SECS;
+
+        let claims = Claims {
+            sub: email.to_string(),
+            exp: expiration as usize,
+            user_id: user_id.to_string(),
+            sv: session_version,
+            scopes: None,
+            org: org.map(|id| id.to_string()),
+        };
+
+        encode(&Header::default(), &claims, &self.encoding)
+            .map_err(|e| Status::internal(format!("Token error: {}", e)))
+    }
+
+    pub fn issue_purpose_token(&self, user_id: Uuid, email: &str, purpose: &str, ttl_secs: u64) -> Result<String, Status> {
+        let expiration = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() + ttl_secs;
+
+        let claims = PurposeClaims {
+            sub: user_id.to_string(),
+            email: email.to_string(),
+            purpose: purpose.to_string(),
+            exp: expiration as usize,
+        };
+
+        encode(&Header::default(), &claims, &self.encoding)
+            .map_err(|e| Status::internal(format!("Token error: {}", e)))
+    }
+
+    pub fn decode_purpose_token(&self, token: &str, purpose: &str) -> Result<PurposeClaims, Status> {
+        let claims = decode::<PurposeClaims>(token.trim(), &self.decoding, &Validation::default())
+            .map(|data| data.claims)
+            .map_err(|_| Status::invalid_argument("Invalid or expired link"))?;
+
+        if claims.purpose != purpose {
+            return Err(Status::invalid_argument("Invalid or expired link"));
+        }
+        Ok(claims)
+    }
+
+    /// Checks a session JWT's signature and expiry; [`authenticate`] also rejects revoked sessions.
+    fn decode_session(&self, token: &str) -> Result<Claims, Status> {
+        decode::<Claims>(token.trim(), &self.decoding, &Validation::default())
+            .map(|data| data.claims)
+            .map_err(|_| Status::unauthenticated("Invalid or expired token"))
+    }
+}
+
+fn bearer_token<T>(request: &Request<T>) -> Result<&str, Status> {
+    request
+        .metadata()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(|| Status::unauthenticated("Missing bearer token"))
+}
+
+impl Claims {
+    pub fn user_uuid(&self) -> Result<Uuid, Status> {
+        Uuid::parse_str(&self.user_id).map_err(|_| Status::unauthenticated("Invalid token subject"))
+    }
+
+    pub fn org_uuid(&self) -> Result<Option<Uuid>, Status> {
+        self.org
+            .as_deref()
+            .map(|org| Uuid::parse_str(org).map_err(|_| Status::unauthenticated("Invalid token organization")))
+            .transpose()
+    }
+
+    /// Sessions can do anything their user can; access tokens only what they were scoped for.
+    pub fn require_scope(&self, scope: &str) -> Result<(), Status> {
+        match &self.scopes {
+            Some(scopes) if !scopes.iter().any(|s| s == scope) => {
+                Err(Status::permission_denied(format!("Access token is missing the '{}' scope", scope)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// For account security settings, which an access token must never be able to change.
+    pub fn require_session(&self) -> Result<(), Status> {
+        if self.scopes.is_some() {
+            return Err(Status::permission_denied("Sign in with your password to do this; access tokens can't"));
+        }
+        Ok(())
+    }
+}
+
+/// Authenticates the caller and makes sure the token hasn't been revoked: sessions by a
+/// password reset or change since they were issued, access tokens by their owner.
+pub async fn authenticate<T>(pool: &PgPool, keys: &JwtKeys, request: &Request<T>) -> Result<Claims, Status> {
+    authenticate_token(pool, keys, bearer_token(request)?, client_ip(request).as_deref()).await
+}
+
+pub async fn authenticate_token(pool: &PgPool, keys: &JwtKeys, token: &str, ip: Option<&str>) -> Result<Claims, Status> {
+    let token = token.trim();
+    if pat::is_pat(token) {
+        let resolved = pat::resolve(pool, token, ip)
+            .await?
+            .ok_or_else(|| Status::unauthenticated("Invalid, expired or revoked access token"))?;
+        return Ok(Claims {
+            sub: resolved.email,
+            exp: 0,
+            user_id: resolved.user_id.to_string(),
+            sv: 0,
+            scopes: Some(resolved.scopes),
+            org: resolved.org_id.map(|id| id.to_string()),
+        });
+    }
+
+    let mut claims = keys.decode_session(token)?;
+    let org_id = claims.org_uuid()?;
+    let current = sqlx::query(
+        "SELECT session_version, \
+         ($2::UUID IS NULL OR EXISTS (SELECT 1 FROM organization_members WHERE org_id = $2 AND user_id = $1)) AS in_org \
+         FROM users WHERE id = $1",
+    )
+    .bind(claims.user_uuid()?)
+    .bind(org_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+    match current {
+        Some(row) if row.get::<i32, _>("session_version") == claims.sv => {
+            // Removed from the organization since switching: account settings still work,
+            // in the personal space; svc-brain-core refuses the organization's data itself.
+            if !row.get::<bool, _>("in_org") {
+                claims.org = None;
+            }
+            Ok(claims)
+        }
+        _ => Err(Status::unauthenticated("Session expired, please log in again")),
+    }
+}
+
+/// Who is asking, for endpoints that also serve anonymous callers.
+#[derive(Debug, Clone, Copy)]
+pub struct Viewer {
+    pub user_id: Uuid,
+    pub is_admin: bool,
+}
+
+impl Viewer {
+    pub fn can_see_private(&self, user_id: Uuid) -> bool {
+        self.is_admin || self.user_id == user_id
+    }
+}
+
+/// `None` without a bearer token; a present but invalid token is still an error.
+pub async fn optional_viewer<T>(pool: &PgPool, keys: &JwtKeys, request: &Request<T>) -> Result<Option<Viewer>, Status> {
+    if request.metadata().get("authorization").is_none() {
+        return Ok(None);
+    }
+    let claims = authenticate(pool, keys, request).await?;
+    claims.require_scope(pat::SCOPE_PROFILE_READ)?;
+    let user_id = claims.user_uuid()?;
+    let is_admin = match require_admin(pool, &claims).await {
+        Ok(()) => true,
+        Err(e) if e.code() == tonic::Code::PermissionDenied => false,
+        Err(e) => return Err(e),
+    };
+    Ok(Some(Viewer { user_id, is_admin }))
+}
+
+/// Admin powers are never delegated to access tokens.
+pub async fn require_admin(pool: &PgPool, claims: &Claims) -> Result<(), Status> {
+    if claims.scopes.is_some() {
+        return Err(Status::permission_denied("Admin role required"));
+    }
+    let role: Option<String> = sqlx::query("SELECT role FROM users WHERE id = $1")
+        .bind(claims.user_uuid()?)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+        .and_then(|row| row.get("role"));
+
+    if role.as_deref() == Some("admin") {
+        Ok(())
+    } else {
+        Err(Status::permission_denied("Admin role required"))
+    }
+}
+
+/// Client IP as reported by the gateway, falling back to the peer address.
+/// Only the gateway can reach this service, so `x-forwarded-for` is trusted here.
+pub fn client_ip<T>(request: &Request<T>) -> Option<String> {
+    request
+        .metadata()
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .or_else(|| request.remote_addr().map(|addr: SocketAddr| addr.ip().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> usize {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as usize
+    }
+
+    #[test]
+    fn purpose_tokens_round_trip() {
+        let keys = JwtKeys::new("test-secret");
+        let user_id = Uuid::new_v4();
+        let token = keys.issue_purpose_token(user_id, "ada@example.com", PURPOSE_VERIFY_EMAIL, 3600).unwrap();
+
+        let claims = keys.decode_purpose_token(&token, PURPOSE_VERIFY_EMAIL).unwrap();
+        assert_eq!(claims.sub, user_id.to_string());
+        assert_eq!(claims.email, "ada@example.com");
+        // Links pasted with surrounding whitespace still work.
+        assert!(keys.decode_purpose_token(&format!(" {}\n", token), PURPOSE_VERIFY_EMAIL).is_ok());
+    }
+
+    #[test]
+    fn purpose_tokens_only_serve_their_purpose() {
+        let keys = JwtKeys::new("test-secret");
+        let token = keys.issue_purpose_token(Uuid::new_v4(), "ada@example.com", PURPOSE_VERIFY_EMAIL, 3600).unwrap();
+        let err = keys.decode_purpose_token(&token, PURPOSE_CHANGE_EMAIL).unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        assert!(keys.decode_purpose_token(&token, PURPOSE_MFA_LOGIN).is_err());
+
+        // A session token is no email link either.
+        let session = keys.issue("ada@example.com", Uuid::new_v4(), 0, None).unwrap();
+        assert!(keys.decode_purpose_token(&session, PURPOSE_VERIFY_EMAIL).is_err());
+    }
+
+    #[test]
+    fn expired_and_foreign_purpose_tokens_are_rejected() {
+        let keys = JwtKeys::new("test-secret");
+        let expired = PurposeClaims {
+            sub: Uuid::new_v4().to_string(),
+            email: "ada@example.com".to_string(),
+            purpose: PURPOSE_VERIFY_EMAIL.to_string(),
+            exp: now() - 3600,
+        };
+        let token = encode(&Header::default(), &expired, &keys.encoding).unwrap();
+        assert!(keys.decode_purpose_token(&token, PURPOSE_VERIFY_EMAIL).is_err());
+
+        let foreign = JwtKeys::new("another-secret").issue_purpose_token(Uuid::new_v4(), "ada@example.com", PURPOSE_VERIFY_EMAIL, 3600).unwrap();
+        assert!(keys.decode_purpose_token(&foreign, PURPOSE_VERIFY_EMAIL).is_err());
+    }
+
+    #[test]
+    fn private_profile_fields_are_for_the_user_and_admins() {
+        let (user, other) = (Uuid::new_v4(), Uuid::new_v4());
+        assert!(Viewer { user_id: user, is_admin: false }.can_see_private(user));
+        assert!(!Viewer { user_id: other, is_admin: false }.can_see_private(user));
+        assert!(Viewer { user_id: other, is_admin: true }.can_see_private(user));
+    }
+
+    #[test]
+    fn access_tokens_are_limited_to_their_scopes() {
+        let claims = |scopes: Option<Vec<String>>| Claims {
+            sub: "ada@example.com".to_string(),
+            exp: now() + 60,
+            user_id: Uuid::new_v4().to_string(),
+            sv: 0,
+            scopes,
+            org: None,
+        };
+        let session = claims(None);
+        assert!(session.require_scope(pat::SCOPE_PROFILE_WRITE).is_ok());
+        assert!(session.require_session().is_ok());
+
+        let token = claims(Some(vec![pat::SCOPE_PROFILE_READ.to_string()]));
+        assert!(token.require_scope(pat::SCOPE_PROFILE_READ).is_ok());
+        assert_eq!(token.require_scope(pat::SCOPE_PROFILE_WRITE).unwrap_err().code(), tonic::Code::PermissionDenied);
+        assert_eq!(token.require_session().unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+}
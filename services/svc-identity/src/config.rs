@@ -1,24 +1,190 @@
 use serde::Deserialize;
 use dotenvy::dotenv;
 use std::env;
+use std::str::FromStr;
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
     pub database_url: String,
     pub server_addr: String,
+    pub jwt_secret: String,
+    /// Public URL of the web app, used to build links in emails.
+    pub app_base_url: String,
+    /// Issuer label shown in authenticator apps.
+    pub totp_issuer: String,
+    pub lockout: LockoutConfig,
+    pub hashing: HashingConfig,
+    pub password_policy: PasswordPolicyConfig,
+    pub mail: MailConfig,
+    pub oidc_providers: Vec<OidcProviderConfig>,
+    pub data_requests: DataRequestConfig,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LockoutConfig {
+    /// Failed attempts on one account before it is temporarily locked.
+    pub max_failures: i32,
+    /// Failures older than this no longer count towards a lockout.
+    pub failure_window_secs: i64,
+    pub lockout_secs: i64,
+    /// Failed attempts from one IP (across all accounts) within the window before it is throttled.
+    pub ip_max_failures: i64,
+    /// First failure is delayed by this much, doubling with every further failure.
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+/// Argon2id cost for new hashes. Raising it upgrades existing hashes as their users log in.
+#[derive(Deserialize, Debug, Clone)]
+pub struct HashingConfig {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    /// Hashes computed at once; further logins wait their turn.
+    pub max_concurrent: usize,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PasswordPolicyConfig {
+    pub min_length: usize,
+    pub max_length: usize,
+    /// Minimum estimated strength; see `password_policy::estimate_entropy_bits`.
+    pub min_entropy_bits: f64,
+    /// HIBP-style SHA-1 data: a `HASH:COUNT` file, or a directory of 5-character prefix range files.
+    pub breached_passwords_path: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MailConfig {
+    /// `smtp`, `file` (writes to `outbox_dir`) or `log` (prints to stdout).
+    pub transport: String,
+    pub from: String,
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub outbox_dir: Option<String>,
+}
+
+/// Background processing of data export and erasure requests.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DataRequestConfig {
+    /// svc-brain-core, which holds the ideas, projects and tasks side of a user's data.
+    pub brain_core_url: String,
+    /// How often the worker looks for new requests.
+    pub poll_secs: u64,
+    /// Failed attempts are retried with backoff up to this many times in total.
+    pub max_attempts: i32,
+    /// Finished export archives are deleted after this many days.
+    pub export_retention_days: i32,
+}
+
+/// One "Sign in with ..." provider. `OIDC_PROVIDERS` holds a JSON array of these.
+#[derive(Deserialize, Debug, Clone)]
+pub struct OidcProviderConfig {
+    /// Short id used in URLs, e.g. `google`.
+    pub name: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// Base URL serving `/.well-known/openid-configuration`.
+    pub issuer: String,
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    /// Must be registered with the provider. Defaults to `{APP_BASE_URL}/auth/callback/{name}`.
+    #[serde(default)]
+    pub redirect_uri: String,
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+}
+
+fn default_oidc_scopes() -> Vec<String> {
+    vec!["openid".into(), "email".into(), "profile".into()]
+}
+
+fn env_or<T: FromStr>(key: &str, default: T) -> Result<T, String> {
+    match env::var(key) {
+        Ok(raw) => raw.parse().map_err(|_| format!("{} has an invalid value", key)),
+        Err(_) => Ok(default),
+    }
 }
 
 impl Config {
     pub fn from_env() -> Result<Self, String> {
         dotenv().ok();
-        
+
         // Manual fallback or use config crate if preferred, but for now simple env var
         let database_url = env::var("DATABASE_URL").map_err(|_| "DATABASE_URL must be set".to_string())?;
         let server_addr = env::var("SERVER_ADDR").unwrap_or_else(|_| "0.0.0.0:50051".to_string());
-        
+        let jwt_secret = env::var("JWT_SECRET").unwrap_or_else(|_| "supersecretkey123".to_string());
+        let app_base_url = env::var("APP_BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+        let totp_issuer = env::var("TOTP_ISSUER").unwrap_or_else(|_| "Billion Brains".to_string());
+
+        let lockout = LockoutConfig {
+            max_failures: env_or("LOCKOUT_MAX_FAILURES", 5)?,
+            failure_window_secs: env_or("LOCKOUT_FAILURE_WINDOW_SECS", 900)?,
+            lockout_secs: env_or("LOCKOUT_DURATION_SECS", 900)?,
+            ip_max_failures: env_or("LOCKOUT_IP_MAX_FAILURES", 50)?,
+            base_delay_ms: env_or("LOCKOUT_BASE_DELAY_MS", 250)?,
+            max_delay_ms: env_or("LOCKOUT_MAX_DELAY_MS", 5000)?,
+        };
+
+        // Defaults are the argon2 crate's (OWASP's minimum recommendation), which all existing hashes use.
+        let hashing = HashingConfig {
+            memory_kib: env_or("ARGON2_MEMORY_KIB", 19 * 1024)?,
+            iterations: env_or("ARGON2_ITERATIONS", 2)?,
+            parallelism: env_or("ARGON2_PARALLELISM", 1)?,
+            max_concurrent: env_or("PASSWORD_HASH_CONCURRENCY", 4)?,
+        };
+
+        let password_policy = PasswordPolicyConfig {
+            min_length: env_or("PASSWORD_MIN_LENGTH", 10)?,
+            max_length: env_or("PASSWORD_MAX_LENGTH", 128)?,
+            min_entropy_bits: env_or("PASSWORD_MIN_ENTROPY_BITS", 40.0)?,
+            breached_passwords_path: env::var("BREACHED_PASSWORDS_PATH").ok().filter(|p| !p.trim().is_empty()),
+        };
+
+        let mail = MailConfig {
+            transport: env::var("MAIL_TRANSPORT").unwrap_or_else(|_| "log".to_string()),
+            from: env::var("MAIL_FROM").unwrap_or_else(|_| "Billion Brains <no-reply@billionbrains.local>".to_string()),
+            smtp_host: env::var("SMTP_HOST").ok(),
+            smtp_port: env_or("SMTP_PORT", 587)?,
+            smtp_username: env::var("SMTP_USERNAME").ok(),
+            smtp_password: env::var("SMTP_PASSWORD").ok(),
+            outbox_dir: env::var("MAIL_OUTBOX_DIR").ok(),
+        };
+
+        let data_requests = DataRequestConfig {
+            brain_core_url: env::var("BRAIN_CORE_URL").unwrap_or_else(|_| "http://svc-brain-core:50052".to_string()),
+            poll_secs: env_or("DATA_REQUEST_POLL_SECS", 10)?,
+            max_attempts: env_or("DATA_REQUEST_MAX_ATTEMPTS", 5)?,
+            export_retention_days: env_or("DATA_EXPORT_RETENTION_DAYS", 7)?,
+        };
+
+        let mut oidc_providers: Vec<OidcProviderConfig> = match env::var("OIDC_PROVIDERS") {
+            Ok(raw) if !raw.trim().is_empty() => {
+                serde_json::from_str(&raw).map_err(|e| format!("OIDC_PROVIDERS has an invalid value: {}", e))?
+            }
+            _ => Vec::new(),
+        };
+        for provider in &mut oidc_providers {
+            if provider.redirect_uri.is_empty() {
+                provider.redirect_uri = format!("{}/auth/callback/{}", app_base_url.trim_end_matches('/'), provider.name);
+            }
+        }
+
         Ok(Config {
             database_url,
             server_addr,
+            jwt_secret,
+            app_base_url,
+            totp_issuer,
+            lockout,
+            hashing,
+            password_policy,
+            mail,
+            oidc_providers,
+            data_requests,
         })
     }
 }
@@ -0,0 +1,494 @@
+//! Data export and erasure requests. Both touch data in two services, so they're queued in
+//! `data_requests` and worked off in the background: the user gets a request id to poll
+//! straight away, a call to svc-brain-core that fails is retried with backoff, and every
+//! replica can run the worker because jobs are claimed with `SKIP LOCKED`.
+
+use shared_proto::privacy::privacy_service_client::PrivacyServiceClient;
+use shared_proto::privacy::{EraseUserContentRequest, ExportUserContentRequest};
+use shared_proto::user::DataRequest;
+use shared_proto::validate;
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::transport::Channel;
+use tonic::Status;
+use uuid::Uuid;
+
+use crate::config::DataRequestConfig;
+use crate::mailer::{Email, Mailer};
+use shared_proto::tokens;
+
+pub const EXPORT: &str = "export";
+pub const ERASURE: &str = "erasure";
+
+/// A job still `processing` after this long is assumed to have died with its worker.
+const STALE_AFTER_SECS: f64 = 600.0;
+const BASE_RETRY_SECS: f64 = 30.0;
+
+/// Shown instead of the stored error, which is for operators.
+const FAILED_MESSAGE: &str = "We couldn't complete this request; please contact support";
+
+const COLUMNS: &str = "id, kind, status, COALESCE(format, '') AS format, \
+    created_at, completed_at, expires_at, COALESCE(size_bytes, 0) AS size_bytes";
+
+/// What this service contributes to an export. Secrets (password and TOTP hashes, token
+/// digests) are left out; everything else stored about the user is included.
+const EXPORTS: [(&str, &str); 7] = [
+    (
+        "account.json",
+        "SELECT to_jsonb(u) - ARRAY['password_hash', 'totp_secret', 'totp_last_used_step', 'session_version'] \
+         FROM users u WHERE u.id = $1",
+    ),
+    (
+        "skills.json",
+        "SELECT COALESCE(jsonb_agg(s.name ORDER BY us.position), '[]') \
+         FROM user_skills us JOIN skills s ON s.id = us.skill_id WHERE us.user_id = $1",
+    ),
+    (
+        "linked_accounts.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(i) ORDER BY i.created_at), '[]') FROM user_identities i WHERE i.user_id = $1",
+    ),
+    (
+        "access_tokens.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(t) - 'token_hash' ORDER BY t.created_at), '[]') \
+         FROM personal_access_tokens t WHERE t.user_id = $1",
+    ),
+    (
+        "login_attempts.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(a) ORDER BY a.created_at), '[]') FROM login_attempts a WHERE a.user_id = $1",
+    ),
+    (
+        "security_events.json",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(e) ORDER BY e.created_at), '[]') FROM auth_events e WHERE e.user_id = $1",
+    ),
+    (
+        "organizations.json",
+        "SELECT COALESCE(jsonb_agg(jsonb_build_object('id', o.id, 'name', o.name, 'slug', o.slug, 'role', m.role, 'joined_at', m.created_at) \
+         ORDER BY m.created_at), '[]') FROM organization_members m JOIN organizations o ON o.id = m.org_id WHERE m.user_id = $1",
+    ),
+];
+
+/// A finished export, ready to hand out.
+pub struct Archive {
+    pub filename: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+struct Job {
+    id: Uuid,
+    user_id: Uuid,
+    kind: String,
+    format: String,
+    delete_content: bool,
+    attempts: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct DataRequests {
+    pool: PgPool,
+    brain_core: PrivacyServiceClient<Channel>,
+    mailer: Arc<dyn Mailer>,
+    app_base_url: String,
+    config: DataRequestConfig,
+}
+
+impl DataRequests {
+    pub fn new(pool: PgPool, config: &DataRequestConfig, mailer: Arc<dyn Mailer>, app_base_url: &str) -> Result<Self, String> {
+        let channel = Channel::from_shared(config.brain_core_url.clone())
+            .map_err(|e| format!("Invalid BRAIN_CORE_URL: {}", e))?
+            .connect_lazy();
+        Ok(Self {
+            pool,
+            brain_core: PrivacyServiceClient::new(channel),
+            mailer,
+            app_base_url: app_base_url.trim_end_matches('/').to_string(),
+            config: config.clone(),
+        })
+    }
+
+    /// One export at a time per user; a finished one can be requested again.
+    pub async fn queue_export(&self, user_id: Uuid, format: &str) -> Result<DataRequest, Status> {
+        let format = if format.is_empty() { validate::EXPORT_FORMATS[0] } else { format };
+
+        // Locking the user row serializes concurrent requests from the same user.
+        let mut tx = self.pool.begin().await.map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        sqlx::query("SELECT 1 FROM users WHERE id = $1 FOR UPDATE")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        let in_flight: bool = sqlx::query(
+            "SELECT EXISTS (SELECT 1 FROM data_requests WHERE user_id = $1 AND kind = $2 AND status IN ('pending', 'processing'))",
+        )
+        .bind(user_id)
+        .bind(EXPORT)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+        .get(0);
+        if in_flight {
+            return Err(Status::failed_precondition("An export is already being prepared"));
+        }
+
+        let row = sqlx::query(&format!(
+            "INSERT INTO data_requests (id, user_id, kind, format) VALUES ($1, $2, $3, $4) RETURNING {}",
+            COLUMNS
+        ))
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(EXPORT)
+        .bind(format)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        tx.commit().await.map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        Ok(request_from_row(&row))
+    }
+
+    /// Queued in the transaction that anonymizes the account, so an erasure can't be half
+    /// started. Returns the request and the plain status token to poll it with.
+    pub async fn queue_erasure(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+        delete_content: bool,
+    ) -> Result<(DataRequest, String), sqlx::Error> {
+        let token = tokens::generate();
+        let row = sqlx::query(&format!(
+            "INSERT INTO data_requests (id, user_id, kind, delete_content, status_token_hash) VALUES ($1, $2, $3, $4, $5) RETURNING {}",
+            COLUMNS
+        ))
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(ERASURE)
+        .bind(delete_content)
+        .bind(&token.hash)
+        .fetch_one(&mut **tx)
+        .await?;
+        Ok((request_from_row(&row), token.plain))
+    }
+
+    pub async fn list(&self, user_id: Uuid) -> Result<Vec<DataRequest>, Status> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM data_requests WHERE user_id = $1 ORDER BY created_at DESC",
+            COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        Ok(rows.iter().map(request_from_row).collect())
+    }
+
+    /// Someone else's request looks the same as a missing one.
+    pub async fn get_for_user(&self, id: Uuid, user_id: Uuid) -> Result<DataRequest, Status> {
+        sqlx::query(&format!("SELECT {} FROM data_requests WHERE id = $1 AND user_id = $2", COLUMNS))
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+            .map(|row| request_from_row(&row))
+            .ok_or_else(|| Status::not_found("Request not found"))
+    }
+
+    pub async fn get_with_token(&self, id: Uuid, status_token: &str) -> Result<DataRequest, Status> {
+        sqlx::query(&format!("SELECT {} FROM data_requests WHERE id = $1 AND status_token_hash = $2", COLUMNS))
+            .bind(id)
+            .bind(tokens::hash(status_token))
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+            .map(|row| request_from_row(&row))
+            .ok_or_else(|| Status::not_found("Request not found"))
+    }
+
+    pub async fn download(&self, id: Uuid, user_id: Uuid) -> Result<Archive, Status> {
+        let row = sqlx::query(
+            "SELECT status, format, archive, created_at FROM data_requests WHERE id = $1 AND user_id = $2 AND kind = $3",
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(EXPORT)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+        .ok_or_else(|| Status::not_found("Export not found"))?;
+
+        let status: String = row.get("status");
+        let data: Option<Vec<u8>> = row.get("archive");
+        let Some(data) = data.filter(|_| status == "completed") else {
+            return Err(match status.as_str() {
+                "expired" => Status::not_found("This export has expired; request a new one"),
+                "failed" => Status::failed_precondition("This export failed; request a new one"),
+                _ => Status::failed_precondition("This export isn't ready yet"),
+            });
+        };
+        let format: String = row.get("format");
+        let date = row.get::<chrono::DateTime<chrono::Utc>, _>("created_at").format("%Y-%m-%d");
+        Ok(Archive {
+            filename: format!("billion-brains-export-{}.{}", date, format),
+            content_type: if format == "zip" { "application/zip" } else { "application/json" }.to_string(),
+            data,
+        })
+    }
+
+    pub fn spawn_worker(self) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.config.poll_secs.max(1)));
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.expire_archives().await {
+                    tracing::error!("Failed to expire data exports: {}", e);
+                }
+                // Drain the queue before sleeping again.
+                loop {
+                    match self.claim().await {
+                        Ok(Some(job)) => self.run(job).await,
+                        Ok(None) => break,
+                        Err(e) => {
+                            tracing::error!("Failed to claim a data request: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    async fn expire_archives(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE data_requests SET status = 'expired', archive = NULL WHERE status = 'completed' AND expires_at <= NOW()")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn claim(&self) -> Result<Option<Job>, sqlx::Error> {
+        let row = sqlx::query(
+            "UPDATE data_requests SET status = 'processing', attempts = attempts + 1, started_at = NOW() \
+             WHERE id = (SELECT id FROM data_requests \
+                 WHERE (status = 'pending' AND next_attempt_at <= NOW()) \
+                    OR (status = 'processing' AND started_at < NOW() - make_interval(secs => $1)) \
+                 ORDER BY next_attempt_at LIMIT 1 FOR UPDATE SKIP LOCKED) \
+             RETURNING id, user_id, kind, COALESCE(format, '') AS format, delete_content, attempts",
+        )
+        .bind(STALE_AFTER_SECS)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| Job {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            kind: row.get("kind"),
+            format: row.get("format"),
+            delete_content: row.get("delete_content"),
+            attempts: row.get("attempts"),
+        }))
+    }
+
+    async fn run(&self, job: Job) {
+        let result = match job.kind.as_str() {
+            EXPORT => self.export(&job).await,
+            ERASURE => self.erase(&job).await,
+            other => Err(format!("Unknown request kind '{}'", other)),
+        };
+        if let Err(e) = result {
+            tracing::error!("Data request {} ({}) attempt {} failed: {}", job.id, job.kind, job.attempts, e);
+            if let Err(e) = self.record_failure(&job, &e).await {
+                tracing::error!("Failed to record failure of data request {}: {}", job.id, e);
+            }
+        }
+    }
+
+    async fn record_failure(&self, job: &Job, error: &str) -> Result<(), sqlx::Error> {
+        if job.attempts >= self.config.max_attempts {
+            sqlx::query("UPDATE data_requests SET status = 'failed', error = $2, completed_at = NOW() WHERE id = $1")
+                .bind(job.id)
+                .bind(error)
+                .execute(&self.pool)
+                .await?;
+        } else {
+            let backoff = BASE_RETRY_SECS * 2f64.powi(job.attempts - 1);
+            sqlx::query(
+                "UPDATE data_requests SET status = 'pending', error = $2, next_attempt_at = NOW() + make_interval(secs => $3) WHERE id = $1",
+            )
+            .bind(job.id)
+            .bind(error)
+            .bind(backoff)
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn export(&self, job: &Job) -> Result<(), String> {
+        let mut files: Vec<(String, serde_json::Value)> = Vec::new();
+        for (name, query) in EXPORTS {
+            let document: serde_json::Value = sqlx::query(query)
+                .bind(job.user_id)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| format!("DB Error: {}", e))?
+                .get(0);
+            files.push((name.to_string(), document));
+        }
+
+        let content = self
+            .brain_core
+            .clone()
+            .export_user_content(ExportUserContentRequest { user_id: job.user_id.to_string() })
+            .await
+            .map_err(|e| format!("svc-brain-core export failed: {}", e.message()))?
+            .into_inner();
+        for file in content.files {
+            let document = serde_json::from_str(&file.content_json).map_err(|e| format!("Invalid {} from svc-brain-core: {}", file.name, e))?;
+            files.push((file.name, document));
+        }
+
+        let generated_at = chrono::Utc::now().to_rfc3339();
+        let data = if job.format == "json" {
+            let mut document = serde_json::Map::new();
+            document.insert("user_id".into(), job.user_id.to_string().into());
+            document.insert("generated_at".into(), generated_at.into());
+            for (name, content) in files {
+                document.insert(name.trim_end_matches(".json").to_string(), content);
+            }
+            serde_json::to_vec_pretty(&serde_json::Value::Object(document)).map_err(|e| e.to_string())?
+        } else {
+            build_zip(job.user_id, &generated_at, &files).map_err(|e| format!("Failed to build archive: {}", e))?
+        };
+
+        let email: Option<String> = sqlx::query(
+            "UPDATE data_requests SET status = 'completed', archive = $2, size_bytes = $3, error = NULL, completed_at = NOW(), \
+             expires_at = NOW() + make_interval(days => $4) WHERE id = $1 \
+             RETURNING (SELECT email FROM users WHERE id = user_id AND deleted_at IS NULL) AS email",
+        )
+        .bind(job.id)
+        .bind(&data)
+        .bind(data.len() as i64)
+        .bind(self.config.export_retention_days)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("DB Error: {}", e))?
+        .get("email");
+
+        tracing::info!("Data export {} ready ({} bytes)", job.id, data.len());
+        if let Some(email) = email {
+            let body = format!(
+                "Your Billion Brains data export is ready. Download it from {}/settings/privacy within {} days, after which it is deleted.",
+                self.app_base_url, self.config.export_retention_days
+            );
+            let email = Email { to: email, subject: "Your data export is ready".to_string(), body };
+            if let Err(e) = self.mailer.send(email).await {
+                tracing::error!("Failed to send data export notice: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// The account itself was anonymized when the request was made; this clears the
+    /// user's content on the svc-brain-core side.
+    async fn erase(&self, job: &Job) -> Result<(), String> {
+        let erased = self
+            .brain_core
+            .clone()
+            .erase_user_content(EraseUserContentRequest {
+                user_id: job.user_id.to_string(),
+                delete_content: job.delete_content,
+            })
+            .await
+            .map_err(|e| format!("svc-brain-core erasure failed: {}", e.message()))?
+            .into_inner();
+
+        sqlx::query("UPDATE data_requests SET status = 'completed', error = NULL, completed_at = NOW() WHERE id = $1")
+            .bind(job.id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("DB Error: {}", e))?;
+        tracing::info!(
+            "Erasure {} complete: {} ideas and {} projects deleted, {} tasks unassigned, {} notifications deleted",
+            job.id, erased.ideas_deleted, erased.projects_deleted, erased.tasks_unassigned, erased.notifications_deleted
+        );
+        Ok(())
+    }
+}
+
+fn build_zip(user_id: Uuid, generated_at: &str, files: &[(String, serde_json::Value)]) -> Result<Vec<u8>, String> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let manifest = serde_json::json!({
+        "user_id": user_id.to_string(),
+        "generated_at": generated_at,
+        "files": files.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+    });
+    for (name, content) in std::iter::once(("manifest.json".to_string(), manifest)).chain(files.iter().cloned()) {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        let bytes = serde_json::to_vec_pretty(&content).map_err(|e| e.to_string())?;
+        zip.write_all(&bytes).map_err(|e| e.to_string())?;
+    }
+    Ok(zip.finish().map_err(|e| e.to_string())?.into_inner())
+}
+
+fn request_from_row(row: &sqlx::postgres::PgRow) -> DataRequest {
+    let timestamp = |column: &str| {
+        row.get::<Option<chrono::DateTime<chrono::Utc>>, _>(column)
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default()
+    };
+    let status: String = row.get("status");
+    DataRequest {
+        id: row.get::<Uuid, _>("id").to_string(),
+        kind: row.get("kind"),
+        error: if status == "failed" { FAILED_MESSAGE.to_string() } else { String::new() },
+        status,
+        format: row.get("format"),
+        created_at: timestamp("created_at"),
+        completed_at: timestamp("completed_at"),
+        expires_at: timestamp("expires_at"),
+        size_bytes: row.get("size_bytes"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn zip_holds_a_manifest_and_every_file() {
+        let user_id = Uuid::new_v4();
+        let files = vec![
+            ("account.json".to_string(), serde_json::json!({ "email": "ada@example.com" })),
+            ("ideas.json".to_string(), serde_json::json!([{ "title": "Solar kettles" }])),
+        ];
+        let data = build_zip(user_id, "2026-01-01T00:00:00Z", &files).unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data)).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(names.len(), 3);
+        let read = |archive: &mut zip::ZipArchive<_>, name: &str| -> serde_json::Value {
+            let mut text = String::new();
+            archive.by_name(name).unwrap().read_to_string(&mut text).unwrap();
+            serde_json::from_str(&text).unwrap()
+        };
+
+        let manifest = read(&mut archive, "manifest.json");
+        assert_eq!(manifest["user_id"], user_id.to_string());
+        assert_eq!(manifest["generated_at"], "2026-01-01T00:00:00Z");
+        assert_eq!(manifest["files"], serde_json::json!(["account.json", "ideas.json"]));
+        assert_eq!(read(&mut archive, "ideas.json"), files[1].1);
+    }
+
+    #[test]
+    fn exports_leave_out_secrets() {
+        let account = EXPORTS.iter().find(|(name, _)| *name == "account.json").unwrap().1;
+        for secret in ["password_hash", "totp_secret", "totp_last_used_step", "session_version"] {
+            assert!(account.contains(&format!("'{}'", secret)), "{} is exported", secret);
+        }
+        let tokens = EXPORTS.iter().find(|(name, _)| *name == "access_tokens.json").unwrap().1;
+        assert!(tokens.contains("- 'token_hash'"));
+    }
+}
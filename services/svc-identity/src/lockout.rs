@@ -0,0 +1,167 @@
+use sqlx::{PgPool, Row};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::config::LockoutConfig;
+
+/// Tracks failed logins per account (on the `users` row) and per client IP
+/// (in `login_attempts`), and decides on delays and temporary lockouts.
+#[derive(Debug)]
+pub struct LockoutGuard {
+    pool: PgPool,
+    config: LockoutConfig,
+}
+
+impl LockoutGuard {
+    pub fn new(pool: PgPool, config: LockoutConfig) -> Self {
+        Self { pool, config }
+    }
+
+    pub async fn ip_throttled(&self, ip: Option<&str>) -> Result<bool, sqlx::Error> {
+        let Some(ip) = ip else { return Ok(false) };
+        let failures: i64 = sqlx::query(
+            "SELECT COUNT(*) AS failures FROM login_attempts \
+             WHERE ip = $1 AND NOT succeeded AND created_at > NOW() - make_interval(secs => $2)",
+        )
+        .bind(ip)
+        .bind(self.config.failure_window_secs as f64)
+        .fetch_one(&self.pool)
+        .await?
+        .get("failures");
+
+        Ok(failures >= self.config.ip_max_failures)
+    }
+
+    pub async fn is_locked(&self, user_id: Uuid) -> Result<bool, sqlx::Error> {
+        let row = sqlx::query("SELECT locked_until > NOW() AS locked FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.and_then(|r| r.get::<Option<bool>, _>("locked")).unwrap_or(false))
+    }
+
+    /// Records a failed attempt and returns how long the response should be delayed.
+    /// Locks the account once it reaches `max_failures` within the window.
+    ///
+    /// The delay follows the failures recorded for the email address, so known and unknown
+    /// addresses, locked or not, see the same curve and timing gives nothing away.
+    pub async fn record_failure(&self, user_id: Option<Uuid>, email: &str, ip: Option<&str>) -> Result<Duration, sqlx::Error> {
+        sqlx::query("INSERT INTO login_attempts (id, user_id, email, ip, succeeded) VALUES ($1, $2, $3, $4, FALSE)")
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(email)
+            .bind(ip)
+            .execute(&self.pool)
+            .await?;
+
+        if let Some(user_id) = user_id {
+            self.count_account_failure(user_id).await?;
+        }
+
+        let failures: i64 = sqlx::query(
+            "SELECT COUNT(*) AS failures FROM login_attempts \
+             WHERE email = $1 AND NOT succeeded AND created_at > NOW() - make_interval(secs => $2) \
+             AND created_at > COALESCE((SELECT MAX(created_at) FROM login_attempts WHERE email = $1 AND succeeded), '-infinity')",
+        )
+        .bind(email)
+        .bind(self.config.failure_window_secs as f64)
+        .fetch_one(&self.pool)
+        .await?
+        .get("failures");
+
+        Ok(delay_for(&self.config, failures))
+    }
+
+    /// Failures while the account is locked don't count and never extend the lock, so
+    /// nobody can keep someone else locked out by failing on their behalf. Locking starts
+    /// the count afresh: once the lock runs out it takes another full run to lock again.
+    async fn count_account_failure(&self, user_id: Uuid) -> Result<(), sqlx::Error> {
+        let failures: Option<i32> = sqlx::query(
+            "UPDATE users SET \
+                failed_login_attempts = CASE \
+                    WHEN last_failed_login_at > NOW() - make_interval(secs => $2) THEN failed_login_attempts + 1 \
+                    ELSE 1 END, \
+                last_failed_login_at = NOW() \
+             WHERE id = $1 AND (locked_until IS NULL OR locked_until <= NOW()) RETURNING failed_login_attempts",
+        )
+        .bind(user_id)
+        .bind(self.config.failure_window_secs as f64)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(|row| row.get("failed_login_attempts"));
+
+        if let Some(failures) = failures.filter(|f| *f >= self.config.max_failures) {
+            sqlx::query("UPDATE users SET locked_until = NOW() + make_interval(secs => $2), failed_login_attempts = 0 WHERE id = $1")
+                .bind(user_id)
+                .bind(self.config.lockout_secs as f64)
+                .execute(&self.pool)
+                .await?;
+            tracing::warn!("Account {} locked after {} failed logins", user_id, failures);
+        }
+        Ok(())
+    }
+
+    pub async fn record_success(&self, user_id: Uuid, email: &str, ip: Option<&str>) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE users SET failed_login_attempts = 0, last_failed_login_at = NULL, locked_until = NULL WHERE id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("INSERT INTO login_attempts (id, user_id, email, ip, succeeded) VALUES ($1, $2, $3, $4, TRUE)")
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(email)
+            .bind(ip)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Clears the lock and failure counter. Returns false if the user does not exist.
+    pub async fn unlock(&self, user_id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE users SET failed_login_attempts = 0, last_failed_login_at = NULL, locked_until = NULL WHERE id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// `base_delay_ms` for the first failure, doubling with every further one up to `max_delay_ms`.
+fn delay_for(config: &LockoutConfig, failures: i64) -> Duration {
+    let exponent = failures.saturating_sub(1).clamp(0, 16) as u32;
+    let delay = config.base_delay_ms.saturating_mul(1u64 << exponent);
+    Duration::from_millis(delay.min(config.max_delay_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> LockoutConfig {
+        LockoutConfig {
+            max_failures: 5,
+            failure_window_secs: 900,
+            lockout_secs: 900,
+            ip_max_failures: 50,
+            base_delay_ms: 250,
+            max_delay_ms: 4_000,
+        }
+    }
+
+    #[test]
+    fn delay_doubles_with_each_failure() {
+        let delays: Vec<u128> = (1..=5).map(|f| delay_for(&config(), f).as_millis()).collect();
+        assert_eq!(delays, [250, 500, 1_000, 2_000, 4_000]);
+    }
+
+    #[test]
+    fn delay_is_capped() {
+        assert_eq!(delay_for(&config(), 6), Duration::from_millis(4_000));
+        assert_eq!(delay_for(&config(), i64::MAX), Duration::from_millis(4_000));
+    }
+
+    #[test]
+    fn no_failures_counts_as_the_first() {
+        assert_eq!(delay_for(&config(), 0), delay_for(&config(), 1));
+    }
+}
@@ -0,0 +1,161 @@
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::path::PathBuf;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::MailConfig;
+
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Outgoing mail transport. Implementations must be cheap to share across requests.
+#[tonic::async_trait]
+pub trait Mailer: Send + Sync + std::fmt::Debug {
+    async fn send(&self, email: Email) -> Result<(), String>;
+}
+
+/// Delivers through an SMTP relay (STARTTLS when credentials are configured).
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl std::fmt::Debug for SmtpMailer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SmtpMailer").field("from", &self.from).finish()
+    }
+}
+
+impl SmtpMailer {
+    pub fn new(config: &MailConfig) -> Result<Self, String> {
+        let host = config.smtp_host.as_deref().ok_or("SMTP_HOST must be set for the smtp mail transport")?;
+        let from = config.from.parse().map_err(|e| format!("Invalid MAIL_FROM: {}", e))?;
+
+        let builder = match (&config.smtp_username, &config.smtp_password) {
+            (Some(user), Some(pass)) => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+                .map_err(|e| format!("Invalid SMTP relay: {}", e))?
+                .credentials(Credentials::new(user.clone(), pass.clone())),
+            // Unauthenticated relays (e.g. a local MailHog/Mailpit) usually don't speak TLS.
+            _ => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+        };
+
+        Ok(Self {
+            transport: builder.port(config.smtp_port).build(),
+            from,
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: Email) -> Result<(), String> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(email.to.parse().map_err(|e| format!("Invalid recipient: {}", e))?)
+            .subject(email.subject)
+            .body(email.body)
+            .map_err(|e| format!("Failed to build email: {}", e))?;
+
+        self.transport
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("SMTP error: {}", e))
+    }
+}
+
+/// Local dev/test transport: writes each email to `<dir>/<uuid>.txt`, or just logs it
+/// when no directory is configured.
+#[derive(Debug)]
+pub struct FileMailer {
+    dir: Option<PathBuf>,
+}
+
+impl FileMailer {
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { dir }
+    }
+}
+
+#[tonic::async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: Email) -> Result<(), String> {
+        let rendered = format!("To: {}\nSubject: {}\n\n{}\n", email.to, email.subject, email.body);
+        match &self.dir {
+            Some(dir) => {
+                tokio::fs::create_dir_all(dir)
+                    .await
+                    .map_err(|e| format!("Failed to create outbox: {}", e))?;
+                let path = dir.join(format!("{}.txt", Uuid::new_v4()));
+                tokio::fs::write(&path, rendered)
+                    .await
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+                println!("Mail to {} written to {}", email.to, path.display());
+            }
+            None => println!("--- Outgoing mail ---\n{}", rendered),
+        }
+        Ok(())
+    }
+}
+
+pub fn from_config(config: &MailConfig) -> Result<Arc<dyn Mailer>, String> {
+    match config.transport.as_str() {
+        "smtp" => Ok(Arc::new(SmtpMailer::new(config)?)),
+        "file" => {
+            let dir = config.outbox_dir.as_deref().ok_or("MAIL_OUTBOX_DIR must be set for the file mail transport")?;
+            Ok(Arc::new(FileMailer::new(Some(PathBuf::from(dir)))))
+        }
+        "log" => Ok(Arc::new(FileMailer::new(None))),
+        other => Err(format!("Unknown MAIL_TRANSPORT '{}' (expected smtp, file or log)", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(transport: &str) -> MailConfig {
+        MailConfig {
+            transport: transport.to_string(),
+            from: "Billion Brains <no-reply@billionbrains.local>".to_string(),
+            smtp_host: None,
+            smtp_port: 587,
+            smtp_username: None,
+            smtp_password: None,
+            outbox_dir: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn file_transport_writes_one_file_per_email() {
+        let outbox = tempfile::tempdir().unwrap();
+        let mailer = FileMailer::new(Some(outbox.path().join("outbox")));
+        let email = Email {
+            to: "ada@example.com".to_string(),
+            subject: "Verify your email".to_string(),
+            body: "Open this link".to_string(),
+        };
+        mailer.send(email.clone()).await.unwrap();
+        mailer.send(email).await.unwrap();
+
+        let files: Vec<_> = std::fs::read_dir(outbox.path().join("outbox")).unwrap().map(|e| e.unwrap().path()).collect();
+        assert_eq!(files.len(), 2);
+        let written = std::fs::read_to_string(&files[0]).unwrap();
+        assert_eq!(written, "To: ada@example.com\nSubject: Verify your email\n\nOpen this link\n");
+    }
+
+    #[test]
+    fn transports_are_picked_from_config() {
+        assert!(from_config(&config("log")).is_ok());
+        assert!(from_config(&config("file")).unwrap_err().contains("MAIL_OUTBOX_DIR"));
+        assert!(from_config(&MailConfig { outbox_dir: Some("/tmp/outbox".into()), ..config("file") }).is_ok());
+        assert!(from_config(&config("smtp")).unwrap_err().contains("SMTP_HOST"));
+        assert!(from_config(&config("pigeon")).unwrap_err().contains("Unknown MAIL_TRANSPORT"));
+    }
+}
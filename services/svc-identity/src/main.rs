@@ -1,92 +1,456 @@
+// tonic::Status is large, but it is the error type every handler helper has to return.
+#![allow(clippy::result_large_err)]
+
+mod account;
+mod audit;
+mod auth;
 mod config;
+mod data_requests;
 mod db;
+mod lockout;
+mod mailer;
+mod oidc;
+mod organizations;
+mod password;
+mod password_policy;
+mod pat;
+mod profile;
+mod totp;
 
 use tonic::{transport::Server, Request, Response, Status};
 use tracing_subscriber::FmtSubscriber;
+use shared_proto::organization::organization_service_server::OrganizationServiceServer;
+use shared_proto::errors::{self, FieldViolation};
 use shared_proto::user::user_service_server::{UserService, UserServiceServer};
-use shared_proto::user::{User, GetUserRequest, CreateUserRequest, LoginRequest, LoginResponse};
-use jsonwebtoken::{encode, Header, EncodingKey};
-use serde::{Deserialize, Serialize};
-use std::time::{SystemTime, UNIX_EPOCH};
-use argon2::{
-    password_hash::{
-        rand_core::OsRng,
-        PasswordHash, PasswordHasher, PasswordVerifier, SaltString
-    },
-    Argon2
-};
+use shared_proto::user::{User, GetUserRequest, BatchGetUsersRequest, BatchGetUsersResponse, CreateUserRequest, LoginRequest, LoginResponse, UnlockAccountRequest, UnlockAccountResponse, VerifyEmailRequest, ResendVerificationEmailRequest, ResendVerificationEmailResponse, RequestPasswordResetRequest, RequestPasswordResetResponse, ResetPasswordRequest, ResetPasswordResponse, VerifyLoginChallengeRequest, EnrollTotpRequest, EnrollTotpResponse, ConfirmTotpRequest, ConfirmTotpResponse, DisableTotpRequest, DisableTotpResponse, ListOidcProvidersRequest, ListOidcProvidersResponse, OidcProvider, BeginOidcLoginRequest, BeginOidcLoginResponse, CompleteOidcLoginRequest, UpdateUserRequest, ChangePasswordRequest, ChangePasswordResponse, ChangeEmailRequest, ChangeEmailResponse, ConfirmEmailChangeRequest, DeleteAccountRequest, DeleteAccountResponse, PersonalAccessToken, CreatePersonalAccessTokenRequest, CreatePersonalAccessTokenResponse, ListPersonalAccessTokensRequest, ListPersonalAccessTokensResponse, RevokePersonalAccessTokenRequest, RevokePersonalAccessTokenResponse, IntrospectTokenRequest, IntrospectTokenResponse, ListSecurityEventsRequest, ListSecurityEventsResponse, AdminListSecurityEventsRequest, SetUserRoleRequest, DataRequest, RequestDataExportRequest, ListDataRequestsRequest, ListDataRequestsResponse, GetDataRequestRequest, DownloadDataExportRequest, DownloadDataExportResponse};
+use shared_proto::tokens;
+use shared_proto::validate::{self, Validate};
 use sqlx::{PgPool, Row};
-use sqlx::postgres::PgPoolOptions;
 use uuid::Uuid;
+use std::sync::Arc;
+use auth::JwtKeys;
+use lockout::LockoutGuard;
+use mailer::{Email, Mailer};
+use oidc::{OidcClient, PgLoginStates, VerifiedIdentity};
+
+/// Same message for unknown accounts, wrong passwords and locked accounts,
+/// so login responses can't be used to enumerate registered emails.
+const INVALID_CREDENTIALS: &str = "Invalid email or password";
+
+const VERIFICATION_TTL_SECS: u64 = 3600 * 48;
+const PASSWORD_RESET_TTL_SECS: i64 = 3600;
+const MFA_CHALLENGE_TTL_SECS: u64 = 300;
+const EMAIL_CHANGE_TTL_SECS: u64 = 3600 * 24;
+const RECOVERY_CODE_COUNT: usize = 10;
+/// Upper bound on how long a revoked access token may keep working through the gateway.
+const INTROSPECTION_CACHE_TTL_SECS: i64 = 30;
+
+const PAT_COLUMNS: &str = "id, name, token_prefix, scopes, expires_at, last_used_at, created_at, revoked_at IS NOT NULL AS revoked";
 
 #[derive(Debug)]
 pub struct MyUserService {
     pool: PgPool,
+    jwt: JwtKeys,
+    lockout: LockoutGuard,
+    mailer: Arc<dyn Mailer>,
+    app_base_url: String,
+    totp_issuer: String,
+    clock: Arc<dyn totp::Clock>,
+    oidc: OidcClient,
+    audit: audit::AuditLog,
+    data_requests: data_requests::DataRequests,
+    hasher: password::Hasher,
+    password_policy: password_policy::PasswordPolicy,
+    // Verified against when the email is unknown, so those logins cost as much as real ones.
+    dummy_hash: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: String,
-    exp: usize,
-    user_id: String,
+impl MyUserService {
+    fn new(pool: PgPool, config: &config::Config, mailer: Arc<dyn Mailer>, clock: Arc<dyn totp::Clock>) -> Result<Self, Status> {
+        let hasher = password::Hasher::new(&config.hashing).map_err(Status::invalid_argument)?;
+        let password_policy = password_policy::PasswordPolicy::load(&config.password_policy).map_err(Status::invalid_argument)?;
+        let data_requests = data_requests::DataRequests::new(pool.clone(), &config.data_requests, mailer.clone(), &config.app_base_url)
+            .map_err(Status::invalid_argument)?;
+        Ok(Self {
+            totp_issuer: config.totp_issuer.clone(),
+            clock,
+            oidc: OidcClient::new(Arc::new(PgLoginStates::new(pool.clone())), config.oidc_providers.clone()),
+            audit: audit::AuditLog::new(pool.clone()),
+            data_requests,
+            lockout: LockoutGuard::new(pool.clone(), config.lockout.clone()),
+            jwt: JwtKeys::new(&config.jwt_secret),
+            mailer,
+            app_base_url: config.app_base_url.trim_end_matches('/').to_string(),
+            dummy_hash: hasher.hash_blocking(&Uuid::new_v4().to_string())?,
+            hasher,
+            password_policy,
+            pool,
+        })
+    }
+
+    /// Re-hashes with the current cost after a successful login. Best effort: the login
+    /// goes ahead either way, and a hash changed concurrently (e.g. a reset) is left alone.
+    async fn upgrade_password_hash(&self, user_id: Uuid, password: &str, old_hash: &str) {
+        let new_hash = match self.hasher.hash(password).await {
+            Ok(hash) => hash,
+            Err(e) => {
+                tracing::error!("Failed to rehash password for {}: {}", user_id, e.message());
+                return;
+            }
+        };
+        let result = sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2 AND password_hash = $3")
+            .bind(&new_hash)
+            .bind(user_id)
+            .bind(old_hash)
+            .execute(&self.pool)
+            .await;
+        match result {
+            Ok(_) => tracing::info!("Upgraded password hash for {}", user_id),
+            Err(e) => tracing::error!("Failed to store upgraded password hash for {}: {}", user_id, e),
+        }
+    }
+
+    /// Sends the signed verification link. Failures are logged rather than returned,
+    /// the user can ask for a new link with `ResendVerificationEmail`.
+    async fn send_verification_email(&self, user_id: Uuid, email: &str) {
+        let token = match self.jwt.issue_purpose_token(user_id, email, auth::PURPOSE_VERIFY_EMAIL, VERIFICATION_TTL_SECS) {
+            Ok(token) => token,
+            Err(e) => {
+                tracing::error!("Failed to sign verification token for {}: {}", user_id, e.message());
+                return;
+            }
+        };
+
+        let link = format!("{}/verify-email?token={}", self.app_base_url, token);
+        let email = Email {
+            to: email.to_string(),
+            subject: "Verify your Billion Brains email".to_string(),
+            body: format!(
+                "Welcome to Billion Brains!\n\nConfirm your email address by opening this link within 48 hours:\n\n{}\n\nIf you didn't sign up, you can ignore this message.",
+                link
+            ),
+        };
+
+        if let Err(e) = self.mailer.send(email).await {
+            tracing::error!("Failed to send verification email for {}: {}", user_id, e);
+        }
+    }
+}
+
+/// Stores a fresh reset token for the account and mails its link.
+async fn send_password_reset(pool: &PgPool, mailer: &dyn Mailer, app_base_url: &str, user_id: Uuid, email: String) -> Result<(), String> {
+    let token = tokens::generate();
+    sqlx::query("INSERT INTO password_reset_tokens (id, user_id, token_hash, expires_at) VALUES ($1, $2, $3, NOW() + make_interval(secs => $4))")
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(&token.hash)
+        .bind(PASSWORD_RESET_TTL_SECS as f64)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let link = format!("{}/reset-password?token={}", app_base_url, token.plain);
+    let message = Email {
+        to: email,
+        subject: "Reset your Billion Brains password".to_string(),
+        body: format!(
+            "Someone asked to reset the password for your account.\n\nChoose a new password within the next hour:\n\n{}\n\nIf this wasn't you, ignore this email; your password stays the same.",
+            link
+        ),
+    };
+    mailer.send(message).await
 }
 
 impl MyUserService {
-    fn hash_password(password: &str) -> Result<String, Status> {
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2.hash_password(password.as_bytes(), &salt)
-            .map_err(|e| Status::internal(format!("Hashing error: {}", e)))?
-            .to_string();
-        Ok(password_hash)
+    /// Final step of every login path: clears failure counters and mints the session token.
+    async fn finish_login(&self, user_id: Uuid, ctx: &audit::Context, method: &str) -> Result<LoginResponse, Status> {
+        let row = sqlx::query("SELECT email, session_version FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        let email: String = row.get("email");
+
+        self.lockout.record_success(user_id, &email, ctx.ip.as_deref()).await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        self.audit.record(ctx, audit::Entry::success(audit::LOGIN, user_id).detail("method", method)).await;
+
+        let token = self.jwt.issue(&email, user_id, row.get("session_version"), None)?;
+
+        Ok(LoginResponse {
+            token,
+            user: Some(self.load_user(user_id).await?),
+            mfa_required: false,
+            mfa_token: String::new(),
+        })
+    }
+
+    /// The caller's own profile, as returned after login and profile edits.
+    async fn load_user(&self, user_id: Uuid) -> Result<User, Status> {
+        let viewer = auth::Viewer { user_id, is_admin: false };
+        self.load_users(&[user_id], Some(viewer))
+            .await?
+            .pop()
+            .ok_or_else(|| Status::not_found("User not found"))
+    }
+
+    /// Profiles in the order of `ids`, unknown ids skipped. Email and private project counts
+    /// are only included where the viewer is the user or an admin.
+    async fn load_users(&self, ids: &[Uuid], viewer: Option<auth::Viewer>) -> Result<Vec<User>, Status> {
+        let rows = sqlx::query(
+            "SELECT u.id, u.email, COALESCE(u.full_name, '') AS full_name, COALESCE(u.bio, '') AS bio, \
+             COALESCE(u.avatar_url, '') AS avatar_url, u.links, COALESCE(u.role, 'creator') AS role, \
+             COALESCE(u.reputation_score, 0) AS reputation_score, u.created_at, u.deleted_at IS NOT NULL AS deleted, \
+             u.open_to_collaborate, COALESCE(u.hours_per_week, 0) AS hours_per_week, \
+             ARRAY(SELECT s.name FROM user_skills us JOIN skills s ON s.id = us.skill_id \
+                   WHERE us.user_id = u.id ORDER BY us.position)::TEXT[] AS skills, \
+             (SELECT COUNT(*) FROM ideas WHERE creator_id = u.id) AS idea_count, \
+             (SELECT COUNT(*) FROM projects WHERE owner_id = u.id) AS project_count, \
+             (SELECT COUNT(*) FROM projects WHERE owner_id = u.id AND is_public) AS public_project_count \
+             FROM UNNEST($1::UUID[]) WITH ORDINALITY AS req(id, ord) JOIN users u ON u.id = req.id \
+             ORDER BY req.ord",
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let id: Uuid = row.get("id");
+                if row.get::<bool, _>("deleted") {
+                    return User {
+                        id: id.to_string(),
+                        full_name: account::DELETED_USER_NAME.to_string(),
+                        deleted: true,
+                        ..Default::default()
+                    };
+                }
+
+                let private = viewer.is_some_and(|v| v.can_see_private(id));
+                let email: String = if private { row.get("email") } else { String::new() };
+                let project_count: i64 = row.get(if private { "project_count" } else { "public_project_count" });
+                User {
+                    id: id.to_string(),
+                    username: email.clone(),
+                    full_name: row.get("full_name"),
+                    bio: row.get("bio"),
+                    avatar_url: row.get("avatar_url"),
+                    skills: row.get("skills"),
+                    links: row.get("links"),
+                    role: row.get("role"),
+                    reputation_score: row.get("reputation_score"),
+                    created_at: row
+                        .get::<Option<chrono::DateTime<chrono::Utc>>, _>("created_at")
+                        .map(|t| t.to_rfc3339())
+                        .unwrap_or_default(),
+                    idea_count: row.get::<i64, _>("idea_count") as i32,
+                    project_count: project_count as i32,
+                    email,
+                    deleted: false,
+                    open_to_collaborate: row.get("open_to_collaborate"),
+                    hours_per_week: row.get("hours_per_week"),
+                }
+            })
+            .collect())
+    }
+
+    /// Re-authentication for sensitive account changes.
+    async fn require_current_password(&self, ctx: &audit::Context, user_id: Uuid, password: &str) -> Result<String, Status> {
+        let row = sqlx::query("SELECT email, password_hash FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+            .ok_or_else(|| Status::not_found("User not found"))?;
+
+        let password_hash: String = row.get("password_hash");
+        if !self.hasher.verify(password, &password_hash).await?.valid {
+            self.audit.record(ctx, audit::Entry::failure(audit::REAUTHENTICATION, Some(user_id), "bad_password")).await;
+            return Err(Status::permission_denied("Current password is incorrect"));
+        }
+        Ok(row.get("email"))
+    }
+
+    /// Security notices are best effort, like the verification email.
+    async fn send_notice(&self, to: &str, subject: &str, body: String) {
+        let email = Email {
+            to: to.to_string(),
+            subject: subject.to_string(),
+            body,
+        };
+        if let Err(e) = self.mailer.send(email).await {
+            tracing::error!("Failed to send '{}' notice: {}", subject, e);
+        }
     }
 
-    fn verify_password(password: &str, hash: &str) -> Result<bool, Status> {
-        let parsed_hash = PasswordHash::new(hash)
-            .map_err(|e| Status::internal(format!("Hash parsing error: {}", e)))?;
-        Ok(Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok())
+    fn mfa_challenge(&self, user_id: Uuid, email: &str) -> Result<LoginResponse, Status> {
+        let mfa_token = self.jwt.issue_purpose_token(user_id, email, auth::PURPOSE_MFA_LOGIN, MFA_CHALLENGE_TTL_SECS)?;
+        Ok(LoginResponse {
+            token: String::new(),
+            user: None,
+            mfa_required: true,
+            mfa_token,
+        })
+    }
+
+    /// Maps a provider identity to a local account. Known identities log straight in; otherwise
+    /// an existing account is linked by email only when both the provider and we have verified
+    /// that address, so nobody can take over an account by registering its email elsewhere.
+    async fn resolve_oidc_user(&self, identity: &VerifiedIdentity) -> Result<Uuid, Status> {
+        let linked: Option<Uuid> = sqlx::query("SELECT user_id FROM user_identities WHERE provider = $1 AND subject = $2")
+            .bind(&identity.provider)
+            .bind(&identity.subject)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+            .map(|row| row.get("user_id"));
+        if let Some(user_id) = linked {
+            return Ok(user_id);
+        }
+
+        let email = identity
+            .email
+            .as_deref()
+            .map(str::trim)
+            .filter(|e| validate::is_plausible_email(e))
+            .ok_or_else(|| Status::failed_precondition("The provider did not share an email address"))?;
+
+        let mut tx = self.pool.begin().await.map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        let existing = sqlx::query("SELECT id, email_verified FROM users WHERE email = $1 FOR UPDATE")
+            .bind(email)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        let (user_id, created) = match existing {
+            Some(row) => {
+                if !identity.email_verified || !row.get::<bool, _>("email_verified") {
+                    return Err(Status::failed_precondition(
+                        "An account with this email already exists. Sign in with your password and verify your email first",
+                    ));
+                }
+                (row.get("id"), false)
+            }
+            None => {
+                let user_id = Uuid::new_v4();
+                // Social-only accounts get an unguessable password; a password reset can set a real one.
+                let password_hash = self.hasher.hash(&tokens::generate().plain).await?;
+                sqlx::query(
+                    "INSERT INTO users (id, email, password_hash, full_name, role, email_verified, email_verified_at) \
+                     VALUES ($1, $2, $3, $4, 'creator', $5, CASE WHEN $5 THEN NOW() END)",
+                )
+                .bind(user_id)
+                .bind(email)
+                .bind(password_hash)
+                .bind(&identity.name)
+                .bind(identity.email_verified)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+                (user_id, true)
+            }
+        };
+
+        sqlx::query("INSERT INTO user_identities (provider, subject, user_id, email) VALUES ($1, $2, $3, $4)")
+            .bind(&identity.provider)
+            .bind(&identity.subject)
+            .bind(user_id)
+            .bind(email)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        tx.commit().await.map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        if created && !identity.email_verified {
+            self.send_verification_email(user_id, email).await;
+        }
+        Ok(user_id)
+    }
+
+    /// Accepts either a current TOTP code or an unused recovery code, consuming
+    /// whichever matched so neither can be replayed.
+    async fn check_second_factor(&self, user_id: Uuid, secret: &str, code: &str) -> Result<bool, Status> {
+        let last_used_step: Option<i64> = sqlx::query_scalar("SELECT totp_last_used_step FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        if let Some(step) = totp::verify(secret, code, self.clock.now_unix(), last_used_step.map(|s| s as u64)) {
+            // Still guarded here: two logins racing with the same code must not both win.
+            let result = sqlx::query(
+                "UPDATE users SET totp_last_used_step = $2 \
+                 WHERE id = $1 AND (totp_last_used_step IS NULL OR totp_last_used_step < $2)",
+            )
+            .bind(user_id)
+            .bind(step as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+            return Ok(result.rows_affected() == 1);
+        }
+
+        let result = sqlx::query("UPDATE totp_recovery_codes SET used_at = NOW() WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL")
+            .bind(user_id)
+            .bind(tokens::hash(&totp::normalize_recovery_code(code)))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        Ok(result.rows_affected() == 1)
     }
 }
 
 #[tonic::async_trait]
 impl UserService for MyUserService {
     async fn get_user(&self, request: Request<GetUserRequest>) -> Result<Response<User>, Status> {
+        request.get_ref().validate()?;
+        let viewer = auth::optional_viewer(&self.pool, &self.jwt, &request).await?;
         let req = request.into_inner();
-        
-        // Fix: Use Uuid::parse_str properly for query
         let user_uuid = Uuid::parse_str(&req.id).map_err(|_| Status::invalid_argument("Invalid UUID"))?;
 
-        let row = sqlx::query("SELECT id, username, full_name, bio FROM users WHERE id = $1")
-            .bind(user_uuid)
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        self.load_users(&[user_uuid], viewer)
+            .await?
+            .pop()
+            .map(Response::new)
+            .ok_or_else(|| Status::not_found("User not found"))
+    }
 
-        if let Some(row) = row {
-            let user = User {
-                id: row.get::<Uuid, _>("id").to_string(),
-                username: "N/A".to_string(), 
-                full_name: row.get("full_name"),
-                bio: row.get("bio"),
-            };
-            Ok(Response::new(user))
-        } else {
-            Err(Status::not_found("User not found"))
+    async fn batch_get_users(&self, request: Request<BatchGetUsersRequest>) -> Result<Response<BatchGetUsersResponse>, Status> {
+        request.get_ref().validate()?;
+        let viewer = auth::optional_viewer(&self.pool, &self.jwt, &request).await?;
+        let req = request.into_inner();
+        let mut ids: Vec<Uuid> = Vec::with_capacity(req.ids.len());
+        for raw in &req.ids {
+            let id = Uuid::parse_str(raw).map_err(|_| Status::invalid_argument(format!("Invalid UUID: {}", raw)))?;
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
         }
+
+        let users = self.load_users(&ids, viewer).await?;
+        Ok(Response::new(BatchGetUsersResponse { users }))
     }
 
     async fn create_user(&self, request: Request<CreateUserRequest>) -> Result<Response<User>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
         let req = request.into_inner();
-        
-        let email = req.username; 
+
+        // `username` predates the dedicated email field and is still accepted from older clients.
+        let email = if req.email.is_empty() { req.username } else { req.email }.trim().to_string();
         let password_raw = req.password; // Now available
         let role = if req.role.is_empty() { "creator".to_string() } else { req.role }; // Default to creator
 
-        let password_hash = Self::hash_password(&password_raw)?; 
+        self.password_policy.check("password", &password_raw, &[&email, &req.full_name]).await?;
+
+        let password_hash = self.hasher.hash(&password_raw).await?;
         let user_id = Uuid::new_v4();
         
-        // Insert with Role and Password
-        sqlx::query("INSERT INTO users (id, email, password_hash, full_name, bio, role) VALUES ($1, $2, $3, $4, $5, $6)")
+        // Insert with Role and Password; the account stays unverified until the email link is opened
+        sqlx::query("INSERT INTO users (id, email, password_hash, full_name, bio, role, email_verified) VALUES ($1, $2, $3, $4, $5, $6, FALSE)")
             .bind(user_id)
             .bind(&email)
             .bind(password_hash)
@@ -97,59 +461,898 @@ impl UserService for MyUserService {
             .await
             .map_err(|e| Status::internal(format!("Failed to create user (Email might exist): {}", e)))?;
 
-        let reply = User {
-            id: user_id.to_string(),
-            username: email,
-            full_name: req.full_name,
-            bio: req.bio,
-        };
-        Ok(Response::new(reply))
+        self.audit.record(&ctx, audit::Entry::success(audit::SIGNUP, user_id).detail("role", role.as_str())).await;
+        self.send_verification_email(user_id, &email).await;
+
+        Ok(Response::new(self.load_user(user_id).await?))
     }
 
     async fn login(&self, request: Request<LoginRequest>) -> Result<Response<LoginResponse>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let ip = ctx.ip.clone();
         let req = request.into_inner();
 
-        let row = sqlx::query("SELECT id, password_hash, full_name, bio FROM users WHERE email = $1")
+        if self.lockout.ip_throttled(ip.as_deref()).await.map_err(|e| Status::internal(format!("DB Error: {}", e)))? {
+            self.audit.record(&ctx, audit::Entry::failure(audit::LOGIN, None, "ip_throttled").email(&req.email)).await;
+            return Err(Status::resource_exhausted("Too many failed login attempts, try again later"));
+        }
+
+        let row = sqlx::query("SELECT id, password_hash, totp_enabled FROM users WHERE email = $1")
             .bind(&req.email)
             .fetch_optional(&self.pool)
             .await
             .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
 
+        let Some(row) = row else {
+            self.hasher.verify(&req.password, &self.dummy_hash).await?;
+            self.audit.record(&ctx, audit::Entry::failure(audit::LOGIN, None, "unknown_account").email(&req.email)).await;
+            let delay = self.lockout.record_failure(None, &req.email, ip.as_deref()).await
+                .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+            tokio::time::sleep(delay).await;
+            return Err(Status::unauthenticated(INVALID_CREDENTIALS));
+        };
+
+        let stored_hash: String = row.get("password_hash");
+        let user_id: Uuid = row.get("id");
+
+        // Verify even when locked so timing doesn't reveal the lock.
+        let verification = self.hasher.verify(&req.password, &stored_hash).await?;
+        let password_ok = verification.valid;
+        let locked = self.lockout.is_locked(user_id).await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        if !password_ok || locked {
+            let reason = if password_ok { "locked" } else { "bad_password" };
+            self.audit.record(&ctx, audit::Entry::failure(audit::LOGIN, Some(user_id), reason).email(&req.email)).await;
+            let delay = self.lockout.record_failure(Some(user_id), &req.email, ip.as_deref()).await
+                .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+            tokio::time::sleep(delay).await;
+            return Err(Status::unauthenticated(INVALID_CREDENTIALS));
+        }
+
+        if verification.needs_rehash {
+            self.upgrade_password_hash(user_id, &req.password, &stored_hash).await;
+        }
+
+        // With 2FA on, the password only earns a short-lived challenge for VerifyLoginChallenge.
+        if row.get::<bool, _>("totp_enabled") {
+            self.audit.record(&ctx, audit::Entry::success(audit::MFA_CHALLENGE, user_id).detail("method", "password")).await;
+            return Ok(Response::new(self.mfa_challenge(user_id, &req.email)?));
+        }
+
+        Ok(Response::new(self.finish_login(user_id, &ctx, "password").await?))
+    }
+
+    async fn verify_login_challenge(&self, request: Request<VerifyLoginChallengeRequest>) -> Result<Response<LoginResponse>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let ip = ctx.ip.clone();
+        let req = request.into_inner();
+
+        let claims = self.jwt.decode_purpose_token(&req.mfa_token, auth::PURPOSE_MFA_LOGIN)
+            .map_err(|_| Status::unauthenticated("Login challenge expired, please sign in again"))?;
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| Status::unauthenticated("Invalid login challenge"))?;
+
+        let row = sqlx::query("SELECT totp_secret FROM users WHERE id = $1 AND email = $2 AND totp_enabled")
+            .bind(user_id)
+            .bind(&claims.email)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+            .ok_or_else(|| Status::unauthenticated("Login challenge expired, please sign in again"))?;
+        let secret: String = row.get("totp_secret");
+
+        let locked = self.lockout.is_locked(user_id).await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        if locked || !self.check_second_factor(user_id, &secret, &req.code).await? {
+            let reason = if locked { "locked" } else { "bad_code" };
+            self.audit.record(&ctx, audit::Entry::failure(audit::LOGIN, Some(user_id), reason).detail("method", "totp")).await;
+            let delay = self.lockout.record_failure(Some(user_id), &claims.email, ip.as_deref()).await
+                .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+            tokio::time::sleep(delay).await;
+            return Err(Status::unauthenticated("Invalid authentication code"));
+        }
+
+        Ok(Response::new(self.finish_login(user_id, &ctx, "totp").await?))
+    }
+
+    async fn enroll_totp(&self, request: Request<EnrollTotpRequest>) -> Result<Response<EnrollTotpResponse>, Status> {
+        request.get_ref().validate()?;
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_session()?;
+        let user_id = claims.user_uuid()?;
+
+        let secret = totp::generate_secret();
+        // Stored disabled until ConfirmTotp proves the authenticator app has it.
+        let row = sqlx::query("UPDATE users SET totp_secret = $2, totp_last_used_step = NULL WHERE id = $1 AND NOT totp_enabled RETURNING email")
+            .bind(user_id)
+            .bind(&secret)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+            .ok_or_else(|| Status::failed_precondition("Two-factor authentication is already enabled"))?;
+        let email: String = row.get("email");
+
+        Ok(Response::new(EnrollTotpResponse {
+            otpauth_uri: totp::otpauth_uri(&self.totp_issuer, &email, &secret),
+            secret,
+        }))
+    }
+
+    async fn confirm_totp(&self, request: Request<ConfirmTotpRequest>) -> Result<Response<ConfirmTotpResponse>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_session()?;
+        let user_id = claims.user_uuid()?;
+        let req = request.into_inner();
+
+        let row = sqlx::query("SELECT totp_secret, totp_enabled FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        if row.get::<bool, _>("totp_enabled") {
+            return Err(Status::failed_precondition("Two-factor authentication is already enabled"));
+        }
+        let secret: String = row
+            .get::<Option<String>, _>("totp_secret")
+            .ok_or_else(|| Status::failed_precondition("Start enrollment first"))?;
+
+        let step = totp::verify(&secret, &req.code, self.clock.now_unix(), None)
+            .ok_or_else(|| Status::invalid_argument("Invalid authentication code"))?;
+
+        let recovery_codes = totp::generate_recovery_codes(RECOVERY_CODE_COUNT);
+        let mut tx = self.pool.begin().await.map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        sqlx::query("UPDATE users SET totp_enabled = TRUE, totp_last_used_step = $2 WHERE id = $1")
+            .bind(user_id)
+            .bind(step as i64)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        for code in &recovery_codes {
+            sqlx::query("INSERT INTO totp_recovery_codes (id, user_id, code_hash) VALUES ($1, $2, $3)")
+                .bind(Uuid::new_v4())
+                .bind(user_id)
+                .bind(tokens::hash(&totp::normalize_recovery_code(code)))
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        }
+
+        tx.commit().await.map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        self.audit.record(&ctx, audit::Entry::success(audit::TOTP_ENABLED, user_id)).await;
+
+        // The only time the plain codes are ever shown.
+        Ok(Response::new(ConfirmTotpResponse { recovery_codes }))
+    }
+
+    async fn disable_totp(&self, request: Request<DisableTotpRequest>) -> Result<Response<DisableTotpResponse>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_session()?;
+        let user_id = claims.user_uuid()?;
+        let req = request.into_inner();
+
+        let row = sqlx::query("SELECT password_hash, totp_secret FROM users WHERE id = $1 AND totp_enabled")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+            .ok_or_else(|| Status::failed_precondition("Two-factor authentication is not enabled"))?;
+
+        let password_hash: String = row.get("password_hash");
+        let secret: String = row.get("totp_secret");
+        if !self.hasher.verify(&req.password, &password_hash).await?.valid || !self.check_second_factor(user_id, &secret, &req.code).await? {
+            self.audit.record(&ctx, audit::Entry::failure(audit::TOTP_DISABLED, Some(user_id), "bad_credentials")).await;
+            return Err(Status::permission_denied("Invalid password or authentication code"));
+        }
+
+        sqlx::query("UPDATE users SET totp_enabled = FALSE, totp_secret = NULL, totp_last_used_step = NULL WHERE id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        self.audit.record(&ctx, audit::Entry::success(audit::TOTP_DISABLED, user_id)).await;
+
+        Ok(Response::new(DisableTotpResponse {}))
+    }
+
+    async fn list_oidc_providers(&self, request: Request<ListOidcProvidersRequest>) -> Result<Response<ListOidcProvidersResponse>, Status> {
+        request.get_ref().validate()?;
+        let mut providers: Vec<OidcProvider> = self
+            .oidc
+            .providers()
+            .map(|p| OidcProvider {
+                name: p.name.clone(),
+                display_name: p.display_name.clone().unwrap_or_else(|| p.name.clone()),
+            })
+            .collect();
+        providers.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(Response::new(ListOidcProvidersResponse { providers }))
+    }
+
+    async fn begin_oidc_login(&self, request: Request<BeginOidcLoginRequest>) -> Result<Response<BeginOidcLoginResponse>, Status> {
+        request.get_ref().validate()?;
+        let req = request.into_inner();
+        let begin = self.oidc.begin(&req.provider).await?;
+        Ok(Response::new(BeginOidcLoginResponse {
+            authorization_url: begin.authorization_url,
+            state: begin.state,
+        }))
+    }
+
+    async fn complete_oidc_login(&self, request: Request<CompleteOidcLoginRequest>) -> Result<Response<LoginResponse>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let req = request.into_inner();
+        let method = format!("oidc:{}", req.provider);
+
+        let identity = self.oidc.complete(&req.provider, &req.state, &req.code).await?;
+        let user_id = self.resolve_oidc_user(&identity).await?;
+
+        let row = sqlx::query("SELECT email, totp_enabled FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        // A locked account stays locked whichever way the user signs in.
+        if self.lockout.is_locked(user_id).await.map_err(|e| Status::internal(format!("DB Error: {}", e)))? {
+            self.audit.record(&ctx, audit::Entry::failure(audit::LOGIN, Some(user_id), "locked").detail("method", method.as_str())).await;
+            return Err(Status::unauthenticated("Account is temporarily locked, try again later"));
+        }
+
+        if row.get::<bool, _>("totp_enabled") {
+            self.audit.record(&ctx, audit::Entry::success(audit::MFA_CHALLENGE, user_id).detail("method", method.as_str())).await;
+            return Ok(Response::new(self.mfa_challenge(user_id, row.get("email"))?));
+        }
+
+        Ok(Response::new(self.finish_login(user_id, &ctx, &method).await?))
+    }
+
+    async fn update_user(&self, request: Request<UpdateUserRequest>) -> Result<Response<User>, Status> {
+        request.get_ref().validate()?;
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_scope(pat::SCOPE_PROFILE_WRITE)?;
+        let user_id = claims.user_uuid()?;
+        let req = request.into_inner();
+
+        let full_name = req.full_name.as_deref().map(str::trim);
+        let bio = req.bio.as_deref().map(str::trim);
+        let avatar_url = req.avatar_url.as_deref().map(profile::avatar_url);
+        let links = req.links.map(|l| profile::links(&l.values));
+        let skills = req.skills.map(|s| profile::skills(&s.values));
+        let hours_per_week = req.hours_per_week.map(profile::hours_per_week);
+
+        let mut tx = self.pool.begin().await.map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        sqlx::query(
+            "UPDATE users SET \
+             full_name = CASE WHEN $2 THEN $3 ELSE full_name END, \
+             bio = CASE WHEN $4 THEN $5 ELSE bio END, \
+             avatar_url = CASE WHEN $6 THEN $7 ELSE avatar_url END, \
+             links = CASE WHEN $8 THEN $9 ELSE links END, \
+             open_to_collaborate = COALESCE($10, open_to_collaborate), \
+             hours_per_week = CASE WHEN $11 THEN $12 ELSE hours_per_week END \
+             WHERE id = $1",
+        )
+        .bind(user_id)
+        .bind(full_name.is_some())
+        .bind(full_name)
+        .bind(bio.is_some())
+        .bind(bio)
+        .bind(avatar_url.is_some())
+        .bind(avatar_url.flatten())
+        .bind(links.is_some())
+        .bind(links.unwrap_or_default())
+        .bind(req.open_to_collaborate)
+        .bind(hours_per_week.is_some())
+        .bind(hours_per_week.flatten())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        if let Some(skills) = skills {
+            sqlx::query("DELETE FROM user_skills WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+            sqlx::query(
+                "INSERT INTO user_skills (user_id, skill_id, position) \
+                 SELECT $1, skill_id, ord - 1 FROM resolve_skills($2::TEXT[])",
+            )
+            .bind(user_id)
+            .bind(&skills)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        }
+
+        tx.commit().await.map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        Ok(Response::new(self.load_user(user_id).await?))
+    }
+
+    async fn change_password(&self, request: Request<ChangePasswordRequest>) -> Result<Response<ChangePasswordResponse>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_session()?;
+        let user_id = claims.user_uuid()?;
+        let req = request.into_inner();
+
+        let email = self.require_current_password(&ctx, user_id, &req.current_password).await?;
+        let full_name = self.load_user(user_id).await?.full_name;
+        self.password_policy.check("new_password", &req.new_password, &[&email, &full_name]).await?;
+        let password_hash = self.hasher.hash(&req.new_password).await?;
+
+        // Signs out every other session; the caller gets a fresh token below.
+        let session_version: i32 = sqlx::query(
+            "UPDATE users SET password_hash = $1, session_version = session_version + 1 WHERE id = $2 RETURNING session_version",
+        )
+        .bind(&password_hash)
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+        .get("session_version");
+
+        // A reset link requested earlier shouldn't be able to undo this.
+        sqlx::query("UPDATE password_reset_tokens SET used_at = NOW() WHERE user_id = $1 AND used_at IS NULL")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        self.audit.record(&ctx, audit::Entry::success(audit::PASSWORD_CHANGED, user_id)).await;
+
+        self.send_notice(
+            &email,
+            "Your Billion Brains password was changed",
+            "The password for your Billion Brains account was just changed and all other sessions were signed out.\n\nIf this wasn't you, reset your password right away.".to_string(),
+        )
+        .await;
+
+        let token = self.jwt.issue(&email, user_id, session_version, claims.org_uuid()?)?;
+        Ok(Response::new(ChangePasswordResponse { token }))
+    }
+
+    async fn change_email(&self, request: Request<ChangeEmailRequest>) -> Result<Response<ChangeEmailResponse>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_session()?;
+        let user_id = claims.user_uuid()?;
+        let req = request.into_inner();
+
+        let new_email = req.new_email.trim().to_string();
+        let current_email = self.require_current_password(&ctx, user_id, &req.current_password).await?;
+        if new_email.eq_ignore_ascii_case(&current_email) {
+            let message = "That is already your email address";
+            return Err(errors::bad_request(message, vec![FieldViolation::new("new_email", message)]));
+        }
+
+        let taken = sqlx::query("SELECT 1 FROM users WHERE email = $1")
+            .bind(&new_email)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+            .is_some();
+        if taken {
+            return Err(Status::already_exists("That email address is already in use"));
+        }
+
+        // The current address stays in effect until the link sent to the new one is opened.
+        sqlx::query("UPDATE users SET pending_email = $1 WHERE id = $2")
+            .bind(&new_email)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        self.audit.record(&ctx, audit::Entry::success(audit::EMAIL_CHANGE_REQUESTED, user_id).detail("new_email", new_email.as_str())).await;
+
+        let token = self.jwt.issue_purpose_token(user_id, &new_email, auth::PURPOSE_CHANGE_EMAIL, EMAIL_CHANGE_TTL_SECS)?;
+        let link = format!("{}/confirm-email-change?token={}", self.app_base_url, token);
+        self.send_notice(
+            &new_email,
+            "Confirm your new Billion Brains email",
+            format!("Open this link within 24 hours to start using this address for your Billion Brains account:\n\n{}\n\nIf you didn't ask for this, you can ignore this message.", link),
+        )
+        .await;
+        self.send_notice(
+            &current_email,
+            "Your Billion Brains email is being changed",
+            format!("Someone signed in to your account asked to change its email address to {}.\n\nIf this wasn't you, change your password right away.", new_email),
+        )
+        .await;
+
+        Ok(Response::new(ChangeEmailResponse {}))
+    }
+
+    async fn confirm_email_change(&self, request: Request<ConfirmEmailChangeRequest>) -> Result<Response<User>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let req = request.into_inner();
+        let claims = self.jwt.decode_purpose_token(&req.token, auth::PURPOSE_CHANGE_EMAIL)?;
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| Status::invalid_argument("Invalid or expired link"))?;
+
+        // Only the most recently requested address can be confirmed. The email is part of
+        // every session token, so existing sessions are signed out.
+        let updated = sqlx::query(
+            "UPDATE users SET email = pending_email, pending_email = NULL, email_verified = TRUE, \
+             email_verified_at = NOW(), session_version = session_version + 1 \
+             WHERE id = $1 AND pending_email = $2 RETURNING id",
+        )
+        .bind(user_id)
+        .bind(&claims.email)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => Status::already_exists("That email address is already in use"),
+            e => Status::internal(format!("DB Error: {}", e)),
+        })?;
+        if updated.is_none() {
+            return Err(Status::invalid_argument("Invalid or expired link"));
+        }
+        self.audit.record(&ctx, audit::Entry::success(audit::EMAIL_CHANGED, user_id).detail("new_email", claims.email.as_str())).await;
+
+        Ok(Response::new(self.load_user(user_id).await?))
+    }
+
+    async fn delete_account(&self, request: Request<DeleteAccountRequest>) -> Result<Response<DeleteAccountResponse>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_session()?;
+        let user_id = claims.user_uuid()?;
+        let req = request.into_inner();
+
+        let email = self.require_current_password(&ctx, user_id, &req.current_password).await?;
+        let unusable_hash = self.hasher.hash(&tokens::generate().plain).await?;
+
+        let mut tx = self.pool.begin().await.map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        account::delete_account(&mut tx, user_id, &unusable_hash)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        let (erasure, status_token) = self.data_requests.queue_erasure(&mut tx, user_id, req.delete_content)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        tx.commit().await.map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        self.send_notice(
+            &email,
+            "Your Billion Brains account was deleted",
+            "Your Billion Brains account and personal data have been deleted. Thanks for being part of the community.".to_string(),
+        )
+        .await;
+
+        // Without IP or user agent, like the account's earlier entries now are.
+        self.audit.record(&audit::Context::default(), audit::Entry::success(audit::ACCOUNT_DELETED, user_id)
+            .detail("delete_content", req.delete_content)
+            .detail("erasure_id", erasure.id.as_str())).await;
+        tracing::info!("Account {} deleted, erasure {} queued", user_id, erasure.id);
+        Ok(Response::new(DeleteAccountResponse { erasure: Some(erasure), status_token }))
+    }
+
+    async fn unlock_account(&self, request: Request<UnlockAccountRequest>) -> Result<Response<UnlockAccountResponse>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        auth::require_admin(&self.pool, &claims).await?;
+
+        let req = request.into_inner();
+        let user_uuid = Uuid::parse_str(&req.user_id).map_err(|_| Status::invalid_argument("Invalid UUID"))?;
+
+        let unlocked = self.lockout.unlock(user_uuid).await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        if !unlocked {
+            return Err(Status::not_found("User not found"));
+        }
+
+        self.audit.record(&ctx, audit::Entry::success(audit::ACCOUNT_UNLOCKED, user_uuid).actor(claims.user_uuid()?)).await;
+        tracing::info!("Account {} unlocked by admin {}", user_uuid, claims.user_id);
+        Ok(Response::new(UnlockAccountResponse { unlocked }))
+    }
+
+    async fn verify_email(&self, request: Request<VerifyEmailRequest>) -> Result<Response<User>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let req = request.into_inner();
+        let claims = self.jwt.decode_purpose_token(&req.token, auth::PURPOSE_VERIFY_EMAIL)?;
+        let user_uuid = Uuid::parse_str(&claims.sub).map_err(|_| Status::invalid_argument("Invalid or expired link"))?;
+
+        // Matching on the email too makes links sent to a previous address useless.
+        let row = sqlx::query(
+            "UPDATE users SET email_verified = TRUE, email_verified_at = COALESCE(email_verified_at, NOW()) \
+             WHERE id = $1 AND email = $2 RETURNING id",
+        )
+        .bind(user_uuid)
+        .bind(&claims.email)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+        .ok_or_else(|| Status::invalid_argument("Invalid or expired link"))?;
+        self.audit.record(&ctx, audit::Entry::success(audit::EMAIL_VERIFIED, user_uuid)).await;
+
+        Ok(Response::new(self.load_user(row.get("id")).await?))
+    }
+
+    async fn resend_verification_email(&self, request: Request<ResendVerificationEmailRequest>) -> Result<Response<ResendVerificationEmailResponse>, Status> {
+        request.get_ref().validate()?;
+        let req = request.into_inner();
+
+        let row = sqlx::query("SELECT id, email FROM users WHERE email = $1 AND NOT email_verified")
+            .bind(req.email.trim())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
         if let Some(row) = row {
-            let stored_hash: String = row.get("password_hash");
-            let user_id: Uuid = row.get("id");
-            
-            if Self::verify_password(&req.password, &stored_hash)? {
-                 let expiration = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs() as usize + 3600 * 24; 
-
-                let claims = Claims {
-                    sub: req.email.clone(),
-                    exp: expiration,
-                    user_id: user_id.to_string(),
-                };
-
-                let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "supersecretkey123".to_string());
-                let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes()))
-                    .map_err(|e| Status::internal(format!("Token error: {}", e)))?;
-
-                Ok(Response::new(LoginResponse {
-                    token,
-                    user: Some(User {
-                        id: user_id.to_string(),
-                        username: req.email,
-                        full_name: row.get("full_name"),
-                        bio: row.get("bio"),
-                    }),
-                }))
-            } else {
-                 Err(Status::unauthenticated("Invalid password"))
-            }
-        } else {
-            Err(Status::unauthenticated("User not found"))
+            let email: String = row.get("email");
+            self.send_verification_email(row.get("id"), &email).await;
         }
+
+        // Same answer whether or not the address is registered.
+        Ok(Response::new(ResendVerificationEmailResponse {}))
+    }
+
+    async fn request_password_reset(&self, request: Request<RequestPasswordResetRequest>) -> Result<Response<RequestPasswordResetResponse>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let req = request.into_inner();
+
+        let row = sqlx::query("SELECT id, email FROM users WHERE email = $1")
+            .bind(req.email.trim())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        // Always answer the same way, and before the token is stored or the mail goes out, so a
+        // registered address takes no longer than an unknown one and can't be probed for.
+        if let Some(row) = row {
+            let (user_id, email): (Uuid, String) = (row.get("id"), row.get("email"));
+            let (pool, mailer, audit, app_base_url) = (self.pool.clone(), self.mailer.clone(), self.audit.clone(), self.app_base_url.clone());
+            tokio::spawn(async move {
+                audit.record(&ctx, audit::Entry::success(audit::PASSWORD_RESET_REQUESTED, user_id)).await;
+                if let Err(e) = send_password_reset(&pool, mailer.as_ref(), &app_base_url, user_id, email).await {
+                    tracing::error!("Failed to send password reset email for {}: {}", user_id, e);
+                }
+            });
+        }
+
+        Ok(Response::new(RequestPasswordResetResponse {}))
+    }
+
+    async fn reset_password(&self, request: Request<ResetPasswordRequest>) -> Result<Response<ResetPasswordResponse>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let req = request.into_inner();
+
+        // Looked up without consuming the token, so a rejected password doesn't burn the link.
+        let account = sqlx::query(
+            "SELECT u.email, COALESCE(u.full_name, '') AS full_name FROM password_reset_tokens t JOIN users u ON u.id = t.user_id \
+             WHERE t.token_hash = $1 AND t.used_at IS NULL AND t.expires_at > NOW()",
+        )
+        .bind(tokens::hash(&req.token))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+        .ok_or_else(|| Status::invalid_argument("Invalid or expired link"))?;
+        let (email, full_name): (String, String) = (account.get("email"), account.get("full_name"));
+        self.password_policy.check("new_password", &req.new_password, &[&email, &full_name]).await?;
+
+        let password_hash = self.hasher.hash(&req.new_password).await?;
+
+        let mut tx = self.pool.begin().await.map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        // Consuming the token in the same statement that finds it makes it single-use under concurrency.
+        let user_id: Uuid = sqlx::query(
+            "UPDATE password_reset_tokens SET used_at = NOW() \
+             WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW() RETURNING user_id",
+        )
+        .bind(tokens::hash(&req.token))
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+        .ok_or_else(|| Status::invalid_argument("Invalid or expired link"))?
+        .get("user_id");
+
+        // New password, every existing session revoked, lockout cleared.
+        sqlx::query(
+            "UPDATE users SET password_hash = $1, session_version = session_version + 1, \
+             failed_login_attempts = 0, last_failed_login_at = NULL, locked_until = NULL WHERE id = $2",
+        )
+        .bind(&password_hash)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        // Other outstanding links for the account die with this one.
+        sqlx::query("UPDATE password_reset_tokens SET used_at = NOW() WHERE user_id = $1 AND used_at IS NULL")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        // A reset is how people recover a compromised account, so access tokens go too.
+        sqlx::query("UPDATE personal_access_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        tx.commit().await.map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        self.audit.record(&ctx, audit::Entry::success(audit::PASSWORD_RESET, user_id)).await;
+        tracing::info!("Password reset completed for {}", user_id);
+        Ok(Response::new(ResetPasswordResponse {}))
+    }
+
+    async fn request_data_export(&self, request: Request<RequestDataExportRequest>) -> Result<Response<DataRequest>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_session()?;
+        let user_id = claims.user_uuid()?;
+
+        let export = self.data_requests.queue_export(user_id, request.get_ref().format.trim()).await?;
+        self.audit.record(&ctx, audit::Entry::success(audit::DATA_EXPORT_REQUESTED, user_id).detail("request_id", export.id.as_str())).await;
+        Ok(Response::new(export))
+    }
+
+    async fn list_data_requests(&self, request: Request<ListDataRequestsRequest>) -> Result<Response<ListDataRequestsResponse>, Status> {
+        request.get_ref().validate()?;
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_scope(pat::SCOPE_PROFILE_READ)?;
+        let requests = self.data_requests.list(claims.user_uuid()?).await?;
+        Ok(Response::new(ListDataRequestsResponse { requests }))
+    }
+
+    async fn get_data_request(&self, request: Request<GetDataRequestRequest>) -> Result<Response<DataRequest>, Status> {
+        request.get_ref().validate()?;
+        let id = Uuid::parse_str(&request.get_ref().id).map_err(|_| Status::invalid_argument("Invalid request id"))?;
+        // A deleted account can't sign in any more, so its erasure is polled with the token instead.
+        if !request.get_ref().status_token.is_empty() {
+            return Ok(Response::new(self.data_requests.get_with_token(id, &request.get_ref().status_token).await?));
+        }
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_scope(pat::SCOPE_PROFILE_READ)?;
+        Ok(Response::new(self.data_requests.get_for_user(id, claims.user_uuid()?).await?))
+    }
+
+    async fn download_data_export(&self, request: Request<DownloadDataExportRequest>) -> Result<Response<DownloadDataExportResponse>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_session()?;
+        let user_id = claims.user_uuid()?;
+        let id = Uuid::parse_str(&request.get_ref().id).map_err(|_| Status::invalid_argument("Invalid request id"))?;
+
+        let archive = self.data_requests.download(id, user_id).await?;
+        self.audit.record(&ctx, audit::Entry::success(audit::DATA_EXPORT_DOWNLOADED, user_id).detail("request_id", id.to_string())).await;
+        Ok(Response::new(DownloadDataExportResponse {
+            filename: archive.filename,
+            content_type: archive.content_type,
+            data: archive.data,
+        }))
+    }
+
+    async fn create_personal_access_token(&self, request: Request<CreatePersonalAccessTokenRequest>) -> Result<Response<CreatePersonalAccessTokenResponse>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_session()?;
+        let user_id = claims.user_uuid()?;
+        let req = request.into_inner();
+        let name = req.name.trim();
+        let scopes = pat::scopes(&req.scopes);
+        let ttl_days = pat::ttl_days(req.expires_in_days);
+
+        let active: i64 = sqlx::query("SELECT COUNT(*) AS active FROM personal_access_tokens WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()")
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+            .get("active");
+        if active >= pat::MAX_ACTIVE_TOKENS {
+            return Err(Status::resource_exhausted(format!("At most {} active tokens are allowed; revoke one first", pat::MAX_ACTIVE_TOKENS)));
+        }
+
+        let token = pat::generate();
+        let row = sqlx::query(&format!(
+            "INSERT INTO personal_access_tokens (id, user_id, name, token_hash, token_prefix, scopes, expires_at, org_id) \
+             VALUES ($1, $2, $3, $4, $5, $6, NOW() + make_interval(days => $7), $8) RETURNING {}",
+            PAT_COLUMNS
+        ))
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(name)
+        .bind(&token.hash)
+        .bind(&token.display_prefix)
+        .bind(&scopes)
+        .bind(ttl_days)
+        .bind(claims.org_uuid()?)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+        let details = pat_from_row(&row);
+        self.audit.record(&ctx, audit::Entry::success(audit::ACCESS_TOKEN_CREATED, user_id)
+            .detail("token_id", details.id.as_str())
+            .detail("scopes", details.scopes.clone())).await;
+
+        Ok(Response::new(CreatePersonalAccessTokenResponse {
+            token: token.plain,
+            details: Some(details),
+        }))
+    }
+
+    async fn list_personal_access_tokens(&self, request: Request<ListPersonalAccessTokensRequest>) -> Result<Response<ListPersonalAccessTokensResponse>, Status> {
+        request.get_ref().validate()?;
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_session()?;
+
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM personal_access_tokens WHERE user_id = $1 ORDER BY created_at DESC",
+            PAT_COLUMNS
+        ))
+        .bind(claims.user_uuid()?)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+        Ok(Response::new(ListPersonalAccessTokensResponse {
+            tokens: rows.iter().map(pat_from_row).collect(),
+        }))
+    }
+
+    async fn revoke_personal_access_token(&self, request: Request<RevokePersonalAccessTokenRequest>) -> Result<Response<RevokePersonalAccessTokenResponse>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_session()?;
+        let id = Uuid::parse_str(&request.get_ref().id).map_err(|_| Status::invalid_argument("Invalid token id"))?;
+
+        // Revoking twice is fine; someone else's token id looks the same as a missing one.
+        sqlx::query("UPDATE personal_access_tokens SET revoked_at = COALESCE(revoked_at, NOW()) WHERE id = $1 AND user_id = $2 RETURNING id")
+            .bind(id)
+            .bind(claims.user_uuid()?)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+            .ok_or_else(|| Status::not_found("Token not found"))?;
+        self.audit.record(&ctx, audit::Entry::success(audit::ACCESS_TOKEN_REVOKED, claims.user_uuid()?).detail("token_id", id.to_string())).await;
+
+        Ok(Response::new(RevokePersonalAccessTokenResponse {}))
+    }
+
+    async fn introspect_token(&self, request: Request<IntrospectTokenRequest>) -> Result<Response<IntrospectTokenResponse>, Status> {
+        request.get_ref().validate()?;
+        let ip = auth::client_ip(&request);
+        let claims = auth::authenticate_token(&self.pool, &self.jwt, &request.get_ref().token, ip.as_deref()).await?;
+
+        Ok(Response::new(IntrospectTokenResponse {
+            user_id: claims.user_id,
+            full_access: claims.scopes.is_none(),
+            scopes: claims.scopes.unwrap_or_default(),
+            cache_ttl_secs: INTROSPECTION_CACHE_TTL_SECS,
+            org_id: claims.org.unwrap_or_default(),
+        }))
+    }
+
+    async fn list_security_events(&self, request: Request<ListSecurityEventsRequest>) -> Result<Response<ListSecurityEventsResponse>, Status> {
+        request.get_ref().validate()?;
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_scope(pat::SCOPE_PROFILE_READ)?;
+        let req = request.into_inner();
+
+        let filter = audit::Filter {
+            user_id: Some(claims.user_uuid()?),
+            event_type: None,
+            success: None,
+            ip: None,
+            since: None,
+            until: None,
+        };
+        let (events, next_page_token) = self.audit.list(&filter, req.page_size, &req.page_token).await?;
+        Ok(Response::new(ListSecurityEventsResponse { events, next_page_token }))
+    }
+
+    async fn admin_list_security_events(&self, request: Request<AdminListSecurityEventsRequest>) -> Result<Response<ListSecurityEventsResponse>, Status> {
+        request.get_ref().validate()?;
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        auth::require_admin(&self.pool, &claims).await?;
+        let req = request.into_inner();
+
+        // The rules have already checked every filter's format.
+        let non_empty = |value: String| Some(value.trim().to_string()).filter(|v| !v.is_empty());
+        let timestamp = |value: &str| {
+            chrono::DateTime::parse_from_rfc3339(value).ok().map(|t| t.with_timezone(&chrono::Utc))
+        };
+
+        let filter = audit::Filter {
+            user_id: non_empty(req.user_id).and_then(|id| Uuid::parse_str(&id).ok()),
+            event_type: non_empty(req.event_type),
+            success: (!req.outcome.is_empty()).then(|| req.outcome == "success"),
+            ip: non_empty(req.ip),
+            since: timestamp(&req.since),
+            until: timestamp(&req.until),
+        };
+        let (events, next_page_token) = self.audit.list(&filter, req.page_size, &req.page_token).await?;
+        Ok(Response::new(ListSecurityEventsResponse { events, next_page_token }))
+    }
+
+    async fn set_user_role(&self, request: Request<SetUserRoleRequest>) -> Result<Response<User>, Status> {
+        request.get_ref().validate()?;
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        auth::require_admin(&self.pool, &claims).await?;
+        let admin_id = claims.user_uuid()?;
+
+        let req = request.into_inner();
+        let user_uuid = Uuid::parse_str(&req.user_id).map_err(|_| Status::invalid_argument("Invalid UUID"))?;
+        // Keeps at least one admin around; another admin has to demote you.
+        if user_uuid == admin_id {
+            return Err(Status::failed_precondition("You can't change your own role"));
+        }
+
+        let previous: String = sqlx::query(
+            "UPDATE users u SET role = $2 FROM (SELECT id, role FROM users WHERE id = $1 AND deleted_at IS NULL FOR UPDATE) old \
+             WHERE u.id = old.id RETURNING old.role",
+        )
+        .bind(user_uuid)
+        .bind(&req.role)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+        .ok_or_else(|| Status::not_found("User not found"))?
+        .get("role");
+
+        if previous != req.role {
+            self.audit.record(&ctx, audit::Entry::success(audit::ROLE_CHANGED, user_uuid)
+                .actor(admin_id)
+                .detail("from", previous.as_str())
+                .detail("to", req.role.as_str())).await;
+            tracing::info!("Role of {} changed from {} to {} by admin {}", user_uuid, previous, req.role, admin_id);
+        }
+
+        let viewer = auth::Viewer { user_id: admin_id, is_admin: true };
+        self.load_users(&[user_uuid], Some(viewer))
+            .await?
+            .pop()
+            .map(Response::new)
+            .ok_or_else(|| Status::not_found("User not found"))
+    }
+}
+
+fn pat_from_row(row: &sqlx::postgres::PgRow) -> PersonalAccessToken {
+    PersonalAccessToken {
+        id: row.get::<Uuid, _>("id").to_string(),
+        name: row.get("name"),
+        token_prefix: row.get("token_prefix"),
+        scopes: row.get("scopes"),
+        expires_at: row.get::<chrono::DateTime<chrono::Utc>, _>("expires_at").to_rfc3339(),
+        last_used_at: row
+            .get::<Option<chrono::DateTime<chrono::Utc>>, _>("last_used_at")
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default(),
+        created_at: row.get::<chrono::DateTime<chrono::Utc>, _>("created_at").to_rfc3339(),
+        revoked: row.get("revoked"),
     }
 }
 
@@ -159,18 +1362,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     let config = config::Config::from_env().expect("Failed to load config");
     
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&config.database_url)
-        .await?;
-        
+    let pool = db::init_pool(&config.database_url).await?;
+
     let addr = config.server_addr.parse()?;
-    let user_service = MyUserService { pool };
+    let mailer = mailer::from_config(&config.mail)?;
+    let user_service = MyUserService::new(pool, &config, mailer, Arc::new(totp::SystemClock))?;
+    user_service.data_requests.clone().spawn_worker();
+    let organization_service = organizations::MyOrganizationService::new(user_service.pool.clone(), &config.jwt_secret);
 
     println!("UserService listening on {}", addr);
 
     Server::builder()
         .add_service(UserServiceServer::new(user_service))
+        .add_service(OrganizationServiceServer::new(organization_service))
         .serve(addr)
         .await?;
 
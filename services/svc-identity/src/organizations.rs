@@ -0,0 +1,453 @@
+//! Organizations: shared workspaces that own ideas and projects in svc-brain-core.
+//! Membership and roles live here; the organization a session acts in travels as the
+//! `org` claim of its token, set by `SwitchOrganization`.
+
+use shared_proto::organization::organization_service_server::OrganizationService;
+use shared_proto::organization::{
+    AddOrganizationMemberRequest, CreateOrganizationRequest, GetOrganizationRequest, ListMyOrganizationsRequest,
+    ListMyOrganizationsResponse, ListOrganizationMembersRequest, ListOrganizationMembersResponse, Organization,
+    OrganizationMember, RemoveOrganizationMemberRequest, RemoveOrganizationMemberResponse, SwitchOrganizationRequest,
+    SwitchOrganizationResponse, UpdateOrganizationMemberRequest,
+};
+use sqlx::{PgPool, Row};
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use crate::audit;
+use crate::auth::{self, JwtKeys};
+use crate::pat::{SCOPE_PROFILE_READ, SCOPE_PROFILE_WRITE};
+
+pub const ROLE_OWNER: &str = "owner";
+pub const ROLE_ADMIN: &str = "admin";
+pub const ROLE_MEMBER: &str = "member";
+const ROLES: [&str; 3] = [ROLE_OWNER, ROLE_ADMIN, ROLE_MEMBER];
+
+const MAX_NAME_CHARS: usize = 100; // organizations.name is VARCHAR(100)
+const MIN_SLUG_CHARS: usize = 3;
+const MAX_SLUG_CHARS: usize = 50; // organizations.slug is VARCHAR(50)
+const MAX_ORGANIZATIONS_PER_USER: i64 = 50;
+
+const ORGANIZATION_COLUMNS: &str = "o.id, o.name, o.slug, o.created_at, m.role, \
+    (SELECT COUNT(*) FROM organization_members c WHERE c.org_id = o.id) AS member_count";
+
+const MEMBER_COLUMNS: &str = "m.user_id, m.role, m.created_at, COALESCE(u.full_name, '') AS full_name, u.email";
+
+#[derive(Debug)]
+pub struct MyOrganizationService {
+    pool: PgPool,
+    jwt: JwtKeys,
+    audit: audit::AuditLog,
+}
+
+impl MyOrganizationService {
+    pub fn new(pool: PgPool, jwt_secret: &str) -> Self {
+        Self { jwt: JwtKeys::new(jwt_secret), audit: audit::AuditLog::new(pool.clone()), pool }
+    }
+
+    /// The caller's role. Organizations the caller doesn't belong to are reported as
+    /// missing, so ids can't be probed.
+    async fn role_of(&self, org_id: Uuid, user_id: Uuid) -> Result<String, Status> {
+        sqlx::query_scalar("SELECT role FROM organization_members WHERE org_id = $1 AND user_id = $2")
+            .bind(org_id)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+            .ok_or_else(|| Status::not_found("Organization not found"))
+    }
+
+    async fn require_manager(&self, org_id: Uuid, user_id: Uuid) -> Result<String, Status> {
+        let role = self.role_of(org_id, user_id).await?;
+        if role == ROLE_MEMBER {
+            return Err(Status::permission_denied("Only owners and admins can manage members"));
+        }
+        Ok(role)
+    }
+
+    async fn load_organization(&self, org_id: Uuid, user_id: Uuid) -> Result<Organization, Status> {
+        sqlx::query(&format!(
+            "SELECT {} FROM organizations o JOIN organization_members m ON m.org_id = o.id AND m.user_id = $2 WHERE o.id = $1",
+            ORGANIZATION_COLUMNS
+        ))
+        .bind(org_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+        .map(|row| organization_from_row(&row))
+        .ok_or_else(|| Status::not_found("Organization not found"))
+    }
+
+    async fn load_member(&self, org_id: Uuid, user_id: Uuid) -> Result<OrganizationMember, Status> {
+        sqlx::query(&format!(
+            "SELECT {} FROM organization_members m JOIN users u ON u.id = m.user_id WHERE m.org_id = $1 AND m.user_id = $2",
+            MEMBER_COLUMNS
+        ))
+        .bind(org_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+        .map(|row| member_from_row(&row))
+        .ok_or_else(|| Status::not_found("Member not found"))
+    }
+}
+
+#[tonic::async_trait]
+impl OrganizationService for MyOrganizationService {
+    async fn create_organization(&self, request: Request<CreateOrganizationRequest>) -> Result<Response<Organization>, Status> {
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_scope(SCOPE_PROFILE_WRITE)?;
+        let user_id = claims.user_uuid()?;
+        let req = request.into_inner();
+
+        let name = name(&req.name)?;
+        let slug = if req.slug.trim().is_empty() { slugify(&name) } else { req.slug.trim().to_ascii_lowercase() };
+        validate_slug(&slug)?;
+
+        let db = |e: sqlx::Error| Status::internal(format!("DB Error: {}", e));
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        let owned: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM organization_members WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db)?;
+        if owned >= MAX_ORGANIZATIONS_PER_USER {
+            return Err(Status::resource_exhausted(format!("You can belong to at most {} organizations", MAX_ORGANIZATIONS_PER_USER)));
+        }
+
+        let org_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO organizations (id, name, slug, created_by) VALUES ($1, $2, $3, $4)")
+            .bind(org_id)
+            .bind(&name)
+            .bind(&slug)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::Database(db) if db.is_unique_violation() => {
+                    Status::already_exists(format!("The slug '{}' is already taken", slug))
+                }
+                e => Status::internal(format!("DB Error: {}", e)),
+            })?;
+        sqlx::query("INSERT INTO organization_members (org_id, user_id, role) VALUES ($1, $2, $3)")
+            .bind(org_id)
+            .bind(user_id)
+            .bind(ROLE_OWNER)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        tx.commit().await.map_err(db)?;
+
+        self.audit.record(&ctx, audit::Entry::success(audit::ORGANIZATION_CREATED, user_id)
+            .detail("org_id", org_id.to_string())
+            .detail("slug", slug.as_str())).await;
+        tracing::info!("Organization {} ({}) created by {}", slug, org_id, user_id);
+
+        self.load_organization(org_id, user_id).await.map(Response::new)
+    }
+
+    async fn list_my_organizations(&self, request: Request<ListMyOrganizationsRequest>) -> Result<Response<ListMyOrganizationsResponse>, Status> {
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_scope(SCOPE_PROFILE_READ)?;
+
+        let organizations = sqlx::query(&format!(
+            "SELECT {} FROM organizations o JOIN organization_members m ON m.org_id = o.id WHERE m.user_id = $1 ORDER BY o.name, o.id",
+            ORGANIZATION_COLUMNS
+        ))
+        .bind(claims.user_uuid()?)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+        .iter()
+        .map(organization_from_row)
+        .collect();
+
+        Ok(Response::new(ListMyOrganizationsResponse { organizations }))
+    }
+
+    async fn get_organization(&self, request: Request<GetOrganizationRequest>) -> Result<Response<Organization>, Status> {
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_scope(SCOPE_PROFILE_READ)?;
+        let org_id = parse_id(&request.get_ref().id)?;
+
+        self.load_organization(org_id, claims.user_uuid()?).await.map(Response::new)
+    }
+
+    async fn list_organization_members(&self, request: Request<ListOrganizationMembersRequest>) -> Result<Response<ListOrganizationMembersResponse>, Status> {
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_scope(SCOPE_PROFILE_READ)?;
+        let org_id = parse_id(&request.get_ref().org_id)?;
+        self.role_of(org_id, claims.user_uuid()?).await?;
+
+        let members = sqlx::query(&format!(
+            "SELECT {} FROM organization_members m JOIN users u ON u.id = m.user_id WHERE m.org_id = $1 ORDER BY m.created_at, m.user_id",
+            MEMBER_COLUMNS
+        ))
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+        .iter()
+        .map(member_from_row)
+        .collect();
+
+        Ok(Response::new(ListOrganizationMembersResponse { members }))
+    }
+
+    async fn add_organization_member(&self, request: Request<AddOrganizationMemberRequest>) -> Result<Response<OrganizationMember>, Status> {
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_scope(SCOPE_PROFILE_WRITE)?;
+        let caller_id = claims.user_uuid()?;
+        let req = request.into_inner();
+        let org_id = parse_id(&req.org_id)?;
+
+        let caller_role = self.require_manager(org_id, caller_id).await?;
+        let role = if req.role.is_empty() { ROLE_MEMBER.to_string() } else { role(&req.role)? };
+        if role == ROLE_OWNER && caller_role != ROLE_OWNER {
+            return Err(Status::permission_denied("Only owners can add owners"));
+        }
+
+        let user_id: Uuid = sqlx::query_scalar("SELECT id FROM users WHERE email = $1 AND deleted_at IS NULL")
+            .bind(req.email.trim())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+            .ok_or_else(|| Status::not_found("No account uses that email address"))?;
+
+        let inserted = sqlx::query("INSERT INTO organization_members (org_id, user_id, role) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING")
+            .bind(org_id)
+            .bind(user_id)
+            .bind(&role)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?
+            .rows_affected();
+        if inserted == 0 {
+            return Err(Status::already_exists("That user is already a member"));
+        }
+
+        self.audit.record(&ctx, audit::Entry::success(audit::ORGANIZATION_MEMBER_ADDED, user_id)
+            .actor(caller_id)
+            .detail("org_id", org_id.to_string())
+            .detail("role", role.as_str())).await;
+
+        self.load_member(org_id, user_id).await.map(Response::new)
+    }
+
+    async fn update_organization_member(&self, request: Request<UpdateOrganizationMemberRequest>) -> Result<Response<OrganizationMember>, Status> {
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_scope(SCOPE_PROFILE_WRITE)?;
+        let caller_id = claims.user_uuid()?;
+        let req = request.into_inner();
+        let org_id = parse_id(&req.org_id)?;
+        let user_id = parse_id(&req.user_id)?;
+        let role = role(&req.role)?;
+
+        let caller_role = self.require_manager(org_id, caller_id).await?;
+        let db = |e: sqlx::Error| Status::internal(format!("DB Error: {}", e));
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        // Locks the organization's roster so two demotions can't both pass the last-owner check.
+        let roster: Vec<(Uuid, String)> = sqlx::query("SELECT user_id, role FROM organization_members WHERE org_id = $1 FOR UPDATE")
+            .bind(org_id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db)?
+            .iter()
+            .map(|row| (row.get("user_id"), row.get("role")))
+            .collect();
+        let previous = roster
+            .iter()
+            .find(|(id, _)| *id == user_id)
+            .map(|(_, role)| role.clone())
+            .ok_or_else(|| Status::not_found("Member not found"))?;
+
+        if (previous == ROLE_OWNER || role == ROLE_OWNER) && caller_role != ROLE_OWNER {
+            return Err(Status::permission_denied("Only owners can grant or change the owner role"));
+        }
+        if previous == ROLE_OWNER && role != ROLE_OWNER && roster.iter().filter(|(_, r)| r == ROLE_OWNER).count() == 1 {
+            return Err(Status::failed_precondition("An organization needs at least one owner; make someone else owner first"));
+        }
+
+        if previous != role {
+            sqlx::query("UPDATE organization_members SET role = $3 WHERE org_id = $1 AND user_id = $2")
+                .bind(org_id)
+                .bind(user_id)
+                .bind(&role)
+                .execute(&mut *tx)
+                .await
+                .map_err(db)?;
+        }
+        tx.commit().await.map_err(db)?;
+
+        if previous != role {
+            self.audit.record(&ctx, audit::Entry::success(audit::ORGANIZATION_MEMBER_ROLE_CHANGED, user_id)
+                .actor(caller_id)
+                .detail("org_id", org_id.to_string())
+                .detail("from", previous.as_str())
+                .detail("to", role.as_str())).await;
+        }
+
+        self.load_member(org_id, user_id).await.map(Response::new)
+    }
+
+    async fn remove_organization_member(&self, request: Request<RemoveOrganizationMemberRequest>) -> Result<Response<RemoveOrganizationMemberResponse>, Status> {
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        claims.require_scope(SCOPE_PROFILE_WRITE)?;
+        let caller_id = claims.user_uuid()?;
+        let req = request.into_inner();
+        let org_id = parse_id(&req.org_id)?;
+        let user_id = parse_id(&req.user_id)?;
+
+        // Anyone may leave; removing someone else takes an owner or admin.
+        let caller_role = if user_id == caller_id {
+            self.role_of(org_id, caller_id).await?
+        } else {
+            self.require_manager(org_id, caller_id).await?
+        };
+
+        let db = |e: sqlx::Error| Status::internal(format!("DB Error: {}", e));
+        let mut tx = self.pool.begin().await.map_err(db)?;
+        let roster: Vec<(Uuid, String)> = sqlx::query("SELECT user_id, role FROM organization_members WHERE org_id = $1 FOR UPDATE")
+            .bind(org_id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db)?
+            .iter()
+            .map(|row| (row.get("user_id"), row.get("role")))
+            .collect();
+        let role = roster
+            .iter()
+            .find(|(id, _)| *id == user_id)
+            .map(|(_, role)| role.clone())
+            .ok_or_else(|| Status::not_found("Member not found"))?;
+
+        if role == ROLE_OWNER && user_id != caller_id && caller_role != ROLE_OWNER {
+            return Err(Status::permission_denied("Only owners can remove an owner"));
+        }
+        if role == ROLE_OWNER && roster.iter().filter(|(_, r)| r == ROLE_OWNER).count() == 1 {
+            return Err(Status::failed_precondition("An organization needs at least one owner; make someone else owner first"));
+        }
+
+        sqlx::query("DELETE FROM organization_members WHERE org_id = $1 AND user_id = $2")
+            .bind(org_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db)?;
+        tx.commit().await.map_err(db)?;
+
+        self.audit.record(&ctx, audit::Entry::success(audit::ORGANIZATION_MEMBER_REMOVED, user_id)
+            .actor(caller_id)
+            .detail("org_id", org_id.to_string())
+            .detail("role", role.as_str())).await;
+
+        Ok(Response::new(RemoveOrganizationMemberResponse {}))
+    }
+
+    async fn switch_organization(&self, request: Request<SwitchOrganizationRequest>) -> Result<Response<SwitchOrganizationResponse>, Status> {
+        let ctx = audit::Context::from_request(&request);
+        let claims = auth::authenticate(&self.pool, &self.jwt, &request).await?;
+        // An access token is bound to the workspace it was created in.
+        claims.require_session()?;
+        let user_id = claims.user_uuid()?;
+        let req = request.into_inner();
+
+        let organization = if req.org_id.is_empty() {
+            None
+        } else {
+            Some(self.load_organization(parse_id(&req.org_id)?, user_id).await?)
+        };
+        let org_id = organization.as_ref().map(|o| Uuid::parse_str(&o.id)).transpose().map_err(|_| Status::internal("Invalid organization id"))?;
+
+        // Same session, so the session version carries over and a password change still revokes it.
+        let token = self.jwt.issue(&claims.sub, user_id, claims.sv, org_id)?;
+
+        self.audit.record(&ctx, audit::Entry::success(audit::ORGANIZATION_SWITCHED, user_id)
+            .detail("org_id", org_id.map(|id| id.to_string()).unwrap_or_default())).await;
+
+        Ok(Response::new(SwitchOrganizationResponse { token, organization }))
+    }
+}
+
+fn parse_id(value: &str) -> Result<Uuid, Status> {
+    Uuid::parse_str(value).map_err(|_| Status::invalid_argument("Invalid UUID"))
+}
+
+fn name(value: &str) -> Result<String, Status> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(Status::invalid_argument("Organization name is required"));
+    }
+    if value.chars().count() > MAX_NAME_CHARS {
+        return Err(Status::invalid_argument(format!("Organization name must be at most {} characters", MAX_NAME_CHARS)));
+    }
+    Ok(value.to_string())
+}
+
+fn role(value: &str) -> Result<String, Status> {
+    let value = value.trim().to_ascii_lowercase();
+    if !ROLES.contains(&value.as_str()) {
+        return Err(Status::invalid_argument(format!("role must be one of: {}", ROLES.join(", "))));
+    }
+    Ok(value)
+}
+
+/// "Acme Labs, Inc." becomes "acme-labs-inc".
+fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(MAX_SLUG_CHARS);
+    slug.trim_end_matches('-').to_string()
+}
+
+fn validate_slug(slug: &str) -> Result<(), Status> {
+    let valid = (MIN_SLUG_CHARS..=MAX_SLUG_CHARS).contains(&slug.len())
+        && slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !slug.starts_with('-')
+        && !slug.ends_with('-');
+    if !valid {
+        return Err(Status::invalid_argument(format!(
+            "slug must be {} to {} lowercase letters, digits or dashes, not starting or ending with a dash",
+            MIN_SLUG_CHARS, MAX_SLUG_CHARS
+        )));
+    }
+    Ok(())
+}
+
+fn organization_from_row(row: &sqlx::postgres::PgRow) -> Organization {
+    Organization {
+        id: row.get::<Uuid, _>("id").to_string(),
+        name: row.get("name"),
+        slug: row.get("slug"),
+        role: row.get("role"),
+        member_count: row.get::<i64, _>("member_count") as i32,
+        created_at: row
+            .get::<Option<chrono::DateTime<chrono::Utc>>, _>("created_at")
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default(),
+    }
+}
+
+fn member_from_row(row: &sqlx::postgres::PgRow) -> OrganizationMember {
+    OrganizationMember {
+        user_id: row.get::<Uuid, _>("user_id").to_string(),
+        full_name: row.get("full_name"),
+        email: row.get("email"),
+        role: row.get("role"),
+        joined_at: row
+            .get::<Option<chrono::DateTime<chrono::Utc>>, _>("created_at")
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default(),
+    }
+}
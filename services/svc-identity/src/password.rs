@@ -0,0 +1,149 @@
+//! Argon2id password hashing with configurable cost. Hashing is deliberately slow and
+//! CPU/memory heavy, so it runs on the blocking pool behind a semaphore: a burst of
+//! logins queues up here instead of stalling the async workers or exhausting memory.
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher as _, PasswordVerifier as _, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tonic::Status;
+
+use crate::config::HashingConfig;
+
+pub struct Verification {
+    pub valid: bool,
+    /// The hash was made with a different algorithm or weaker parameters than the current config.
+    pub needs_rehash: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hasher {
+    params: Params,
+    permits: Arc<Semaphore>,
+}
+
+impl Hasher {
+    pub fn new(config: &HashingConfig) -> Result<Self, String> {
+        let params = Params::new(config.memory_kib, config.iterations, config.parallelism, None)
+            .map_err(|e| format!("Invalid Argon2 parameters: {}", e))?;
+        Ok(Self {
+            params,
+            permits: Arc::new(Semaphore::new(config.max_concurrent.max(1))),
+        })
+    }
+
+    fn argon2(params: Params) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+    }
+
+    /// Hashes on the calling thread; only for startup, before the runtime is busy.
+    pub fn hash_blocking(&self, password: &str) -> Result<String, Status> {
+        hash_with(self.params.clone(), password)
+    }
+
+    pub async fn hash(&self, password: &str) -> Result<String, Status> {
+        let params = self.params.clone();
+        let password = password.to_string();
+        self.run(move || hash_with(params, &password)).await?
+    }
+
+    pub async fn verify(&self, password: &str, hash: &str) -> Result<Verification, Status> {
+        let current = self.params.clone();
+        let password = password.to_string();
+        let hash = hash.to_string();
+        self.run(move || {
+            let parsed = PasswordHash::new(&hash).map_err(|e| Status::internal(format!("Hash parsing error: {}", e)))?;
+            // Verification uses the parameters embedded in the hash, whatever the current config.
+            let valid = Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok();
+            Ok(Verification {
+                valid,
+                needs_rehash: valid && is_weaker(&parsed, &current),
+            })
+        })
+        .await?
+    }
+
+    async fn run<T: Send + 'static>(&self, job: impl FnOnce() -> T + Send + 'static) -> Result<T, Status> {
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| Status::unavailable("Password hashing is shutting down"))?;
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            job()
+        })
+        .await
+        .map_err(|e| Status::internal(format!("Hashing task failed: {}", e)))
+    }
+}
+
+fn hash_with(params: Params, password: &str) -> Result<String, Status> {
+    let salt = SaltString::generate(&mut OsRng);
+    Hasher::argon2(params)
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| Status::internal(format!("Hashing error: {}", e)))
+}
+
+fn is_weaker(hash: &PasswordHash, current: &Params) -> bool {
+    if hash.algorithm != Algorithm::Argon2id.ident() || hash.version != Some(Version::V0x13.into()) {
+        return true;
+    }
+    match Params::try_from(hash) {
+        Ok(stored) => {
+            stored.m_cost() < current.m_cost() || stored.t_cost() < current.t_cost() || stored.p_cost() < current.p_cost()
+        }
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hasher(memory_kib: u32, iterations: u32) -> Hasher {
+        Hasher::new(&HashingConfig { memory_kib, iterations, parallelism: 1, max_concurrent: 2 }).unwrap()
+    }
+
+    #[tokio::test]
+    async fn hashes_verify_against_their_password_only() {
+        let hasher = hasher(64, 1);
+        let hash = hasher.hash("correct horse").await.unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+
+        let ok = hasher.verify("correct horse", &hash).await.unwrap();
+        assert!(ok.valid && !ok.needs_rehash);
+        let wrong = hasher.verify("battery staple", &hash).await.unwrap();
+        assert!(!wrong.valid && !wrong.needs_rehash);
+    }
+
+    #[tokio::test]
+    async fn raising_the_cost_upgrades_old_hashes() {
+        let old = hasher(64, 1).hash("correct horse").await.unwrap();
+        assert!(hasher(128, 1).verify("correct horse", &old).await.unwrap().needs_rehash);
+        assert!(hasher(64, 2).verify("correct horse", &old).await.unwrap().needs_rehash);
+        // Lowering it leaves stronger hashes alone.
+        assert!(!hasher(32, 1).verify("correct horse", &old).await.unwrap().needs_rehash);
+    }
+
+    #[tokio::test]
+    async fn other_argon2_variants_are_upgraded() {
+        let params = Params::new(64, 1, 1, None).unwrap();
+        let argon2i = Argon2::new(Algorithm::Argon2i, Version::V0x13, params)
+            .hash_password(b"correct horse", &SaltString::generate(&mut OsRng))
+            .unwrap()
+            .to_string();
+        let verification = hasher(64, 1).verify("correct horse", &argon2i).await.unwrap();
+        assert!(verification.valid && verification.needs_rehash);
+    }
+
+    #[test]
+    fn invalid_parameters_are_refused() {
+        let config = HashingConfig { memory_kib: 0, iterations: 1, parallelism: 1, max_concurrent: 1 };
+        assert!(Hasher::new(&config).unwrap_err().contains("Invalid Argon2 parameters"));
+    }
+}
@@ -0,0 +1,250 @@
+//! Rules for new passwords: length, a rough strength estimate and a check against a local
+//! copy of known breached passwords (Have I Been Pwned style SHA-1 data, so no password
+//! or hash prefix ever leaves the service).
+
+use sha1::{Digest, Sha1};
+use shared_proto::errors::{self, FieldViolation};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tonic::Status;
+
+use crate::config::PasswordPolicyConfig;
+
+/// Length of the SHA-1 hex prefix naming a range file.
+const RANGE_PREFIX_LEN: usize = 5;
+
+#[derive(Debug)]
+enum Breached {
+    None,
+    /// Full uppercase SHA-1 hex digests, loaded from one `HASH[:COUNT]` per line file.
+    Loaded(HashSet<String>),
+    /// A directory of range files named by the first 5 hex digits (`ABCDE` or `ABCDE.txt`),
+    /// each holding `SUFFIX[:COUNT]` lines, as the HIBP downloader writes them. Read on demand.
+    Ranges(PathBuf),
+}
+
+#[derive(Debug)]
+pub struct PasswordPolicy {
+    config: PasswordPolicyConfig,
+    breached: Breached,
+}
+
+impl PasswordPolicy {
+    pub fn load(config: &PasswordPolicyConfig) -> Result<Self, String> {
+        let breached = match config.breached_passwords_path.as_deref() {
+            None => Breached::None,
+            Some(path) if Path::new(path).is_dir() => Breached::Ranges(PathBuf::from(path)),
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read breached password list {}: {}", path, e))?;
+                let hashes: HashSet<String> = contents
+                    .lines()
+                    .filter_map(|line| line.split(':').next())
+                    .map(|hash| hash.trim().to_ascii_uppercase())
+                    .filter(|hash| hash.len() == 40)
+                    .collect();
+                tracing::info!("Loaded {} breached password hashes", hashes.len());
+                Breached::Loaded(hashes)
+            }
+        };
+        Ok(Self { config: config.clone(), breached })
+    }
+
+    /// Checks a new password. `personal` holds things the password must not contain,
+    /// like the account's email address. Every violation is reported against `field`.
+    pub async fn check(&self, field: &str, password: &str, personal: &[&str]) -> Result<(), Status> {
+        let mut problems: Vec<String> = Vec::new();
+        let length = password.chars().count();
+
+        if length < self.config.min_length {
+            problems.push(format!("Use at least {} characters", self.config.min_length));
+        }
+        if length > self.config.max_length {
+            problems.push(format!("Use at most {} characters", self.config.max_length));
+        }
+        let lowered = password.to_lowercase();
+        let contains_personal = personal
+            .iter()
+            .flat_map(|value| value.to_lowercase().split(['@', '.', ' ']).map(str::to_string).collect::<Vec<_>>())
+            .any(|part| part.chars().count() >= 4 && lowered.contains(&part));
+        if contains_personal {
+            problems.push("Don't include your name or email address".to_string());
+        }
+        if length >= self.config.min_length && estimate_entropy_bits(password) < self.config.min_entropy_bits {
+            problems.push("Too easy to guess; mix in more words, numbers or symbols, and avoid repeats and sequences".to_string());
+        }
+        // Skipped for passwords already rejected, so the breach list isn't consulted needlessly.
+        if problems.is_empty() && self.is_breached(password).await {
+            problems.push("This password has appeared in a data breach; choose another".to_string());
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let violations = problems.iter().map(|p| FieldViolation::new(field, p.clone())).collect();
+        Err(errors::bad_request("Password doesn't meet the requirements", violations))
+    }
+
+    async fn is_breached(&self, password: &str) -> bool {
+        let hash = hex::encode_upper(Sha1::digest(password.as_bytes()));
+        match &self.breached {
+            Breached::None => false,
+            Breached::Loaded(hashes) => hashes.contains(&hash),
+            Breached::Ranges(dir) => {
+                let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LEN);
+                let mut contents = None;
+                for name in [format!("{}.txt", prefix), prefix.to_string()] {
+                    if let Ok(text) = tokio::fs::read_to_string(dir.join(name)).await {
+                        contents = Some(text);
+                        break;
+                    }
+                }
+                // A missing range file just means no breached password has that prefix.
+                contents.is_some_and(|text| {
+                    text.lines().any(|line| line.split(':').next().is_some_and(|s| s.trim().eq_ignore_ascii_case(suffix)))
+                })
+            }
+        }
+    }
+}
+
+/// A deliberately simple strength estimate: log2 of the character pool per character,
+/// where characters repeating or continuing a run (`aaa`, `abc`, `321`) count for little.
+fn estimate_entropy_bits(password: &str) -> f64 {
+    let chars: Vec<char> = password.chars().collect();
+    let mut pool = 0u32;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' ') {
+        pool += 33;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        pool += 100;
+    }
+    if pool == 0 {
+        return 0.0;
+    }
+
+    let per_char = (pool as f64).log2();
+    let mut bits = 0.0;
+    for (i, c) in chars.iter().enumerate() {
+        let predictable = i > 0 && {
+            let step = *c as i64 - chars[i - 1] as i64;
+            step == 0 || step.abs() == 1
+        };
+        bits += if predictable { 1.0 } else { per_char };
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SHA-1 of "correct horse battery staple", uppercase hex.
+    const BREACHED_HASH: &str = "ABF7AAD6438836DBE526AA231ABDE2D0EEF74D42";
+    const BREACHED: &str = "correct horse battery staple";
+    const STRONG: &str = "Quilt-Lantern-48-Mosaic";
+
+    fn policy(breached_passwords_path: Option<&Path>) -> PasswordPolicy {
+        PasswordPolicy::load(&PasswordPolicyConfig {
+            min_length: 10,
+            max_length: 128,
+            min_entropy_bits: 40.0,
+            breached_passwords_path: breached_passwords_path.map(|p| p.to_string_lossy().into_owned()),
+        })
+        .unwrap()
+    }
+
+    async fn problems(policy: &PasswordPolicy, password: &str, personal: &[&str]) -> Vec<String> {
+        match policy.check("password", password, personal).await {
+            Ok(()) => Vec::new(),
+            Err(status) => errors::field_violations(&status).into_iter().map(|v| v.description).collect(),
+        }
+    }
+
+    #[test]
+    fn entropy_counts_the_character_pool() {
+        assert_eq!(estimate_entropy_bits(""), 0.0);
+        let lower = estimate_entropy_bits("qzmxkwpr");
+        assert!((lower - 8.0 * 26f64.log2()).abs() < 1e-9);
+        assert!(estimate_entropy_bits("qzMxkW4!") > lower);
+    }
+
+    #[test]
+    fn repeats_and_runs_count_for_little() {
+        assert_eq!(estimate_entropy_bits("aaaaaaaaaa"), 26f64.log2() + 9.0);
+        assert_eq!(estimate_entropy_bits("abcdefghij"), 26f64.log2() + 9.0);
+        assert_eq!(estimate_entropy_bits("9876543210"), 10f64.log2() + 9.0);
+        assert!(estimate_entropy_bits("abcdefghij") < estimate_entropy_bits("qzmxkwprtv"));
+    }
+
+    #[tokio::test]
+    async fn accepts_a_strong_password() {
+        assert!(problems(&policy(None), STRONG, &["ada@example.com", "Ada Lovelace"]).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reports_length_and_strength() {
+        let policy = policy(None);
+        assert_eq!(problems(&policy, "Sh0rt!", &[]).await, ["Use at least 10 characters"]);
+        assert_eq!(problems(&policy, &"Ab1!".repeat(40), &[]).await, ["Use at most 128 characters"]);
+        assert!(problems(&policy, "aaaaaaaaaaaa", &[]).await[0].starts_with("Too easy to guess"));
+    }
+
+    #[tokio::test]
+    async fn rejects_personal_information() {
+        let policy = policy(None);
+        let personal = ["ada.lovelace@example.com", "Ada Lovelace"];
+        let expected = ["Don't include your name or email address"];
+        assert_eq!(problems(&policy, "Lovelace-Quilt-48", &personal).await, expected);
+        assert_eq!(problems(&policy, "quilt-EXAMPLE-48!", &personal).await, expected);
+        // Parts shorter than four characters are too common to hold against anyone.
+        assert!(problems(&policy, "Ada-Quilt-Mosaic-48", &personal).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn breached_list_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("pwned.txt");
+        std::fs::write(&file, format!("{}:3861493\n{}:1\n", BREACHED_HASH.to_lowercase(), "0".repeat(40))).unwrap();
+        let policy = policy(Some(&file));
+
+        assert_eq!(problems(&policy, BREACHED, &[]).await, ["This password has appeared in a data breach; choose another"]);
+        assert!(problems(&policy, STRONG, &[]).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn breached_range_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let (prefix, suffix) = BREACHED_HASH.split_at(RANGE_PREFIX_LEN);
+        std::fs::write(dir.path().join(format!("{}.txt", prefix)), format!("0018A45C4D1DEF81644B54AB7F969B88D65:1\n{}:3861493\n", suffix)).unwrap();
+        let policy = policy(Some(dir.path()));
+
+        assert!(!problems(&policy, BREACHED, &[]).await.is_empty());
+        // No range file for its prefix: not breached.
+        assert!(problems(&policy, STRONG, &[]).await.is_empty());
+
+        // Range files may also be named without the extension.
+        std::fs::rename(dir.path().join(format!("{}.txt", prefix)), dir.path().join(prefix)).unwrap();
+        assert!(!problems(&policy, BREACHED, &[]).await.is_empty());
+    }
+
+    #[test]
+    fn missing_list_file_fails_to_load() {
+        let config = PasswordPolicyConfig {
+            min_length: 10,
+            max_length: 128,
+            min_entropy_bits: 40.0,
+            breached_passwords_path: Some("/nonexistent/pwned.txt".into()),
+        };
+        assert!(PasswordPolicy::load(&config).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Personal access tokens: long-lived, scoped bearer tokens for scripts and CI.
+//! Only the SHA-256 digest is stored; the token is shown to its owner once.
+
+use sqlx::{PgPool, Row};
+use tonic::Status;
+use uuid::Uuid;
+
+use shared_proto::tokens;
+
+/// Lets the gateway (and secret scanners) tell these apart from session JWTs at a glance.
+pub const TOKEN_PREFIX: &str = "bb_pat_";
+
+pub const SCOPE_PROFILE_READ: &str = "profile:read";
+pub const SCOPE_PROFILE_WRITE: &str = "profile:write";
+
+pub const DEFAULT_TTL_DAYS: i32 = 90;
+pub const MAX_ACTIVE_TOKENS: i64 = 50;
+/// Characters of the token kept in the clear for display.
+const DISPLAY_PREFIX_CHARS: usize = TOKEN_PREFIX.len() + 6;
+/// `last_used_at` is only written when older than this, so busy scripts don't turn every call into a write.
+const LAST_USED_RESOLUTION_SECS: f64 = 60.0;
+
+pub struct NewToken {
+    pub plain: String,
+    pub hash: String,
+    pub display_prefix: String,
+}
+
+pub fn generate() -> NewToken {
+    let secret = tokens::generate();
+    let plain = format!("{}{}", TOKEN_PREFIX, secret.plain);
+    NewToken {
+        hash: tokens::hash(&plain),
+        display_prefix: plain[..DISPLAY_PREFIX_CHARS].to_string(),
+        plain,
+    }
+}
+
+pub fn is_pat(token: &str) -> bool {
+    token.trim().starts_with(TOKEN_PREFIX)
+}
+
+/// Lowercased, deduplicated and sorted; the request's rules only let known scopes through.
+pub fn scopes(values: &[String]) -> Vec<String> {
+    let mut scopes: Vec<String> = values.iter().map(|v| v.trim().to_ascii_lowercase()).collect();
+    scopes.sort();
+    scopes.dedup();
+    scopes
+}
+
+/// 0 asks for the default.
+pub fn ttl_days(value: i32) -> i32 {
+    if value == 0 { DEFAULT_TTL_DAYS } else { value }
+}
+
+pub struct Resolved {
+    pub user_id: Uuid,
+    pub email: String,
+    pub scopes: Vec<String>,
+    pub org_id: Option<Uuid>,
+}
+
+/// Finds a live token (not revoked, not expired, owner not deleted and still in the token's
+/// organization) and records its use.
+pub async fn resolve(pool: &PgPool, token: &str, ip: Option<&str>) -> Result<Option<Resolved>, Status> {
+    let row = sqlx::query(
+        "SELECT t.id, t.user_id, t.scopes, t.org_id, u.email, \
+         (t.last_used_at IS NULL OR t.last_used_at < NOW() - make_interval(secs => $2)) AS stale \
+         FROM personal_access_tokens t JOIN users u ON u.id = t.user_id \
+         WHERE t.token_hash = $1 AND t.revoked_at IS NULL AND t.expires_at > NOW() AND u.deleted_at IS NULL \
+         AND (t.org_id IS NULL OR EXISTS (SELECT 1 FROM organization_members m WHERE m.org_id = t.org_id AND m.user_id = t.user_id))",
+    )
+    .bind(tokens::hash(token))
+    .bind(LAST_USED_RESOLUTION_SECS)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+
+    let Some(row) = row else { return Ok(None) };
+    if row.get::<bool, _>("stale") {
+        sqlx::query("UPDATE personal_access_tokens SET last_used_at = NOW(), last_used_ip = $2 WHERE id = $1")
+            .bind(row.get::<Uuid, _>("id"))
+            .bind(ip)
+            .execute(pool)
+            .await
+            .map_err(|e| Status::internal(format!("DB Error: {}", e)))?;
+    }
+
+    Ok(Some(Resolved {
+        user_id: row.get("user_id"),
+        email: row.get("email"),
+        scopes: row.get("scopes"),
+        org_id: row.get("org_id"),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_tokens_are_recognisable_and_stored_hashed() {
+        let token = generate();
+        assert!(is_pat(&token.plain));
+        assert!(token.plain.starts_with(&token.display_prefix));
+        assert_eq!(token.display_prefix.len(), DISPLAY_PREFIX_CHARS);
+        assert_eq!(token.hash, tokens::hash(&token.plain));
+        assert_ne!(generate().plain, token.plain);
+    }
+
+    #[test]
+    fn session_tokens_are_not_access_tokens() {
+        assert!(is_pat(" bb_pat_0123 "));
+        assert!(!is_pat("eyJhbGciOiJIUzI1NiJ9.e30.sig"));
+    }
+
+    #[test]
+    fn scopes_are_normalized() {
+        let scopes = scopes(&["Tasks:Write ".into(), "ideas:read".into(), "tasks:write".into()]);
+        assert_eq!(scopes, ["ideas:read", "tasks:write"]);
+    }
+
+    #[test]
+    fn zero_days_means_the_default_lifetime() {
+        assert_eq!(ttl_days(0), DEFAULT_TTL_DAYS);
+        assert_eq!(ttl_days(7), 7);
+    }
+}
@@ -0,0 +1,68 @@
+//! Normalization of user-editable profile fields; the limits are checked by the request's
+//! `Validate` rules before any of this runs.
+
+/// Empty string clears the avatar.
+pub fn avatar_url(value: &str) -> Option<String> {
+    Some(value.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Drops empty entries and duplicates.
+pub fn links(values: &[String]) -> Vec<String> {
+    let mut links: Vec<String> = Vec::new();
+    for link in values.iter().map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        if !links.iter().any(|l| l == link) {
+            links.push(link.to_string());
+        }
+    }
+    links
+}
+
+/// Trims, collapses inner whitespace and drops case-insensitive duplicates, keeping the first spelling.
+pub fn skills(values: &[String]) -> Vec<String> {
+    let mut skills: Vec<String> = Vec::new();
+    for value in values {
+        let skill = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !skill.is_empty() && !skills.iter().any(|s| s.eq_ignore_ascii_case(&skill)) {
+            skills.push(skill);
+        }
+    }
+    skills
+}
+
+/// 0 clears the stated availability.
+pub fn hours_per_week(value: i32) -> Option<i32> {
+    Some(value).filter(|v| *v > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn empty_avatar_clears_it() {
+        assert_eq!(avatar_url("  "), None);
+        assert_eq!(avatar_url(" https://example.com/a.png "), Some("https://example.com/a.png".to_string()));
+    }
+
+    #[test]
+    fn links_are_trimmed_and_deduplicated_in_order() {
+        let links = links(&strings(&["https://b.example", "", " https://a.example ", "https://b.example"]));
+        assert_eq!(links, ["https://b.example", "https://a.example"]);
+    }
+
+    #[test]
+    fn skills_collapse_whitespace_and_keep_the_first_spelling() {
+        let skills = skills(&strings(&["  Machine   learning ", "Rust", "machine learning", "RUST", " "]));
+        assert_eq!(skills, ["Machine learning", "Rust"]);
+    }
+
+    #[test]
+    fn zero_hours_clears_availability() {
+        assert_eq!(hours_per_week(0), None);
+        assert_eq!(hours_per_week(20), Some(20));
+    }
+}
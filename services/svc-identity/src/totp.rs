@@ -0,0 +1,199 @@
+//! RFC 6238 time-based one-time passwords (SHA-1, 6 digits, 30 second steps),
+//! the parameters every common authenticator app defaults to.
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const STEP_SECS: u64 = 30;
+const DIGITS: u32 = 6;
+/// Codes from one step before/after are accepted to absorb clock drift.
+const ALLOWED_SKEW_STEPS: u64 = 1;
+const SECRET_BYTES: usize = 20;
+
+/// Source of the current time. Injected so TOTP checks can run against a fixed clock.
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    fn now_unix(&self) -> u64;
+}
+
+#[derive(Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_unix(&self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+    }
+}
+
+/// Returns a new random secret, base32 encoded as authenticator apps expect.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; SECRET_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    BASE32_NOPAD.encode(&bytes)
+}
+
+/// `otpauth://` URI for QR codes (Key Uri Format).
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        percent_encode(issuer),
+        percent_encode(account),
+        secret,
+        percent_encode(issuer),
+        DIGITS,
+        STEP_SECS
+    )
+}
+
+/// HOTP value (RFC 4226) for a counter.
+pub fn code_at(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([digest[offset], digest[offset + 1], digest[offset + 2], digest[offset + 3]]) & 0x7fff_ffff;
+    binary % 10u32.pow(DIGITS)
+}
+
+/// Checks `code` against the steps around `now` and returns the matching step. Only steps
+/// after `last_used_step` count: callers persist the step they accepted, so a code can't
+/// be replayed within its validity window, nor an older code used after a newer one.
+pub fn verify(secret_b32: &str, code: &str, now_unix: u64, last_used_step: Option<u64>) -> Option<u64> {
+    let secret = BASE32_NOPAD.decode(secret_b32.as_bytes()).ok()?;
+    let code = code.trim().replace(' ', "");
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let expected: u32 = code.parse().ok()?;
+
+    let current = now_unix / STEP_SECS;
+    (current.saturating_sub(ALLOWED_SKEW_STEPS)..=current + ALLOWED_SKEW_STEPS)
+        .filter(|&step| last_used_step.is_none_or(|last| step > last))
+        .find(|&step| code_at(&secret, step) == expected)
+}
+
+/// Recovery codes look like `1f3a-9c0b-77de-24a1` (64 random bits each).
+pub fn generate_recovery_codes(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|_| {
+            let mut bytes = [0u8; 8];
+            OsRng.fill_bytes(&mut bytes);
+            let hex = hex::encode(bytes);
+            format!("{}-{}-{}-{}", &hex[0..4], &hex[4..8], &hex[8..12], &hex[12..16])
+        })
+        .collect()
+}
+
+/// Recovery codes are compared case- and dash-insensitively.
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The RFC 6238 appendix B seed for SHA-1, base32 encoded.
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[derive(Debug)]
+    struct FixedClock(u64);
+
+    impl Clock for FixedClock {
+        fn now_unix(&self) -> u64 {
+            self.0
+        }
+    }
+
+    fn code(now_unix: u64) -> String {
+        format!("{:06}", code_at(b"12345678901234567890", now_unix / STEP_SECS))
+    }
+
+    #[test]
+    fn rfc_6238_sha1_vectors() {
+        // The RFC lists 8 digits; 6-digit codes are their last six.
+        let vectors = [
+            (59, "287082"),
+            (1_111_111_109, "081804"),
+            (1_111_111_111, "050471"),
+            (1_234_567_890, "005924"),
+            (2_000_000_000, "279037"),
+            (20_000_000_000, "353130"),
+        ];
+        for (time, expected) in vectors {
+            assert_eq!(code(time), expected, "at {}", time);
+            assert_eq!(verify(RFC_SECRET, expected, time, None), Some(time / STEP_SECS), "at {}", time);
+        }
+    }
+
+    #[test]
+    fn one_step_of_skew_either_way() {
+        let now = 1_234_567_890;
+        let step = now / STEP_SECS;
+        let at_step = |s: u64| format!("{:06}", code_at(b"12345678901234567890", s));
+
+        assert_eq!(verify(RFC_SECRET, &at_step(step - 1), now, None), Some(step - 1));
+        assert_eq!(verify(RFC_SECRET, &at_step(step + 1), now, None), Some(step + 1));
+        assert_eq!(verify(RFC_SECRET, &at_step(step - 2), now, None), None);
+        assert_eq!(verify(RFC_SECRET, &at_step(step + 2), now, None), None);
+    }
+
+    #[test]
+    fn used_steps_are_rejected() {
+        let clock = FixedClock(1_111_111_111);
+        let step = clock.now_unix() / STEP_SECS;
+        let current = code(clock.now_unix());
+
+        let accepted = verify(RFC_SECRET, &current, clock.now_unix(), None).unwrap();
+        assert_eq!(accepted, step);
+        // The same code again, as `check_second_factor` sees it once `totp_last_used_step` is set.
+        assert_eq!(verify(RFC_SECRET, &current, clock.now_unix(), Some(accepted)), None);
+        // Nor can the previous step's code follow it, though it is within the skew.
+        assert_eq!(verify(RFC_SECRET, &code(clock.now_unix() - STEP_SECS), clock.now_unix(), Some(accepted)), None);
+        // The next step's code still works.
+        assert_eq!(verify(RFC_SECRET, &code(clock.now_unix() + STEP_SECS), clock.now_unix(), Some(accepted)), Some(step + 1));
+    }
+
+    #[test]
+    fn malformed_codes_are_rejected() {
+        let now = 59;
+        assert_eq!(verify(RFC_SECRET, "287 082", now, None), Some(1));
+        assert_eq!(verify(RFC_SECRET, "28708", now, None), None);
+        assert_eq!(verify(RFC_SECRET, "2870820", now, None), None);
+        assert_eq!(verify(RFC_SECRET, "28708a", now, None), None);
+        assert_eq!(verify("not base32!", "287082", now, None), None);
+    }
+
+    #[test]
+    fn recovery_codes_normalize() {
+        let codes = generate_recovery_codes(3);
+        assert_eq!(codes.len(), 3);
+        assert!(codes.iter().all(|c| c.len() == 19 && c.matches('-').count() == 3));
+        assert_eq!(normalize_recovery_code(" 1F3A-9c0b-77DE-24a1 "), "1f3a9c0b77de24a1");
+    }
+
+    #[test]
+    fn otpauth_uri_escapes_labels() {
+        let uri = otpauth_uri("Billion Brains", "a+b@x.io", RFC_SECRET);
+        assert_eq!(
+            uri,
+            "otpauth://totp/Billion%20Brains:a%2Bb%40x.io?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Billion%20Brains&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
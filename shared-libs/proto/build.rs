@@ -1,7 +1,7 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::configure()
         .compile_protos(
-            &["src/user.proto", "src/idea.proto", "src/team.proto", "src/task.proto"],
+            &["src/user.proto", "src/idea.proto", "src/team.proto", "src/task.proto", "src/reputation.proto", "src/privacy.proto", "src/organization.proto", "src/errors.proto"],
             &["src"],
         )?;
     Ok(())
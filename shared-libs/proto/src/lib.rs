@@ -13,3 +13,59 @@ pub mod team {
 pub mod task {
     tonic::include_proto!("task");
 }
+
+pub mod reputation {
+    tonic::include_proto!("reputation");
+}
+
+pub mod privacy {
+    tonic::include_proto!("privacy");
+}
+
+pub mod organization {
+    tonic::include_proto!("organization");
+}
+
+pub mod errors {
+    tonic::include_proto!("errors");
+
+    use prost::Message;
+
+    const BAD_REQUEST_TYPE_URL: &str = "type.googleapis.com/google.rpc.BadRequest";
+
+    impl FieldViolation {
+        pub fn new(field: &str, description: impl Into<String>) -> Self {
+            Self { field: field.to_string(), description: description.into() }
+        }
+    }
+
+    /// `invalid_argument` with per-field reasons attached, for forms to show next to each field.
+    pub fn bad_request(message: &str, violations: Vec<FieldViolation>) -> tonic::Status {
+        let details = StatusDetails {
+            code: tonic::Code::InvalidArgument as i32,
+            message: message.to_string(),
+            details: vec![AnyDetail {
+                type_url: BAD_REQUEST_TYPE_URL.to_string(),
+                value: BadRequest { field_violations: violations }.encode_to_vec(),
+            }],
+        };
+        tonic::Status::with_details(tonic::Code::InvalidArgument, message, details.encode_to_vec().into())
+    }
+
+    /// The field violations attached by [`bad_request`], if any.
+    pub fn field_violations(status: &tonic::Status) -> Vec<FieldViolation> {
+        let Ok(details) = StatusDetails::decode(status.details()) else {
+            return Vec::new();
+        };
+        details
+            .details
+            .into_iter()
+            .filter(|d| d.type_url == BAD_REQUEST_TYPE_URL)
+            .filter_map(|d| BadRequest::decode(d.value.as_slice()).ok())
+            .flat_map(|b| b.field_violations)
+            .collect()
+    }
+}
+
+pub mod tokens;
+pub mod validate;
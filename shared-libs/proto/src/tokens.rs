@@ -0,0 +1,41 @@
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// A random single-use secret and the SHA-256 digest that gets stored instead of it.
+/// The secrets carry 256 bits of entropy, so a fast hash is enough (unlike passwords).
+pub struct OpaqueToken {
+    pub plain: String,
+    pub hash: String,
+}
+
+pub fn generate() -> OpaqueToken {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let plain = hex::encode(bytes);
+    OpaqueToken { hash: hash(&plain), plain }
+}
+
+pub fn hash(plain: &str) -> String {
+    hex::encode(Sha256::digest(plain.trim().as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_are_random_and_stored_as_their_digest() {
+        let (a, b) = (generate(), generate());
+        assert_ne!(a.plain, b.plain);
+        assert_eq!(a.plain.len(), 64);
+        assert_eq!(a.hash, hash(&a.plain));
+        assert_ne!(a.hash, a.plain);
+    }
+
+    #[test]
+    fn hash_is_sha256_hex_of_the_trimmed_token() {
+        assert_eq!(hash("abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        // Reset links copied with a trailing newline still match.
+        assert_eq!(hash(" abc\n"), hash("abc"));
+    }
+}
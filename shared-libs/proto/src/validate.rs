@@ -0,0 +1,890 @@
+//! Declarative checks for incoming requests. Each request message lists its rules in a
+//! [`Validate`] impl below; handlers call `request.get_ref().validate()?` before anything
+//! else, and every broken rule comes back at once as `invalid_argument` with one
+//! [`FieldViolation`] per field.
+
+// tonic::Status is large, but it is what handlers return.
+#![allow(clippy::result_large_err)]
+
+use crate::errors::{self, FieldViolation};
+use crate::{idea, privacy, reputation, task, team, user};
+
+pub const MAX_TITLE_CHARS: usize = 255;
+pub const MAX_TEXT_CHARS: usize = 10_000;
+pub const MAX_COMMENT_CHARS: usize = 5_000;
+pub const MAX_INDUSTRY_CHARS: usize = 100;
+
+pub const IDEA_SORTS: [&str; 3] = ["new", "top", "trending"];
+pub const COMMENT_SORTS: [&str; 3] = ["oldest", "newest", "top"];
+pub const COMMENT_REACTIONS: [&str; 5] = ["like", "love", "insightful", "celebrate", "curious"];
+
+pub const MAX_EMAIL_CHARS: usize = 255;
+pub const MAX_FULL_NAME_CHARS: usize = 100; // users.full_name is VARCHAR(100)
+pub const MAX_BIO_CHARS: usize = 2000;
+pub const MAX_URL_CHARS: usize = 2048;
+pub const MAX_SKILLS: usize = 30;
+pub const MAX_SKILL_CHARS: usize = 50; // skills.slug is VARCHAR(50)
+pub const MAX_LINKS: usize = 10;
+pub const MAX_HOURS_PER_WEEK: i32 = 80;
+pub const MAX_BATCH_GET_USERS: usize = 100;
+pub const MAX_TOKEN_NAME_CHARS: usize = 100; // personal_access_tokens.name is VARCHAR(100)
+pub const MAX_TOKEN_TTL_DAYS: i32 = 365;
+
+/// Roles a user may pick at signup; anything else (e.g. admin) is granted out of band.
+pub const SIGNUP_ROLES: [&str; 2] = ["creator", "investor"];
+pub const ASSIGNABLE_ROLES: [&str; 3] = ["creator", "investor", "admin"];
+pub const TOKEN_SCOPES: [&str; 7] = [
+    "profile:read",
+    "profile:write",
+    "ideas:read",
+    "ideas:write",
+    "tasks:read",
+    "tasks:write",
+    "reputation:write",
+];
+/// The first is the default.
+pub const EXPORT_FORMATS: [&str; 2] = ["zip", "json"];
+pub const EVENT_OUTCOMES: [&str; 2] = ["success", "failure"];
+
+pub trait Validate {
+    fn rules(&self, rules: &mut Rules);
+
+    fn validate(&self) -> Result<(), tonic::Status> {
+        let mut rules = Rules::default();
+        self.rules(&mut rules);
+        rules.finish()
+    }
+}
+
+/// A proto enum sent by name as a string: lowercased and without its prefix, so
+/// `TASK_STATUS_IN_PROGRESS` is "in_progress". The zero value stands for "not set".
+pub trait WireEnum: Sized + Copy + TryFrom<i32> + Into<i32> {
+    const PREFIX: &'static str;
+
+    fn full_name(self) -> &'static str;
+    fn from_full_name(name: &str) -> Option<Self>;
+
+    fn name(self) -> String {
+        self.full_name().trim_start_matches(Self::PREFIX).to_lowercase()
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        if name.is_empty() || name != name.to_lowercase() {
+            return None;
+        }
+        Self::from_full_name(&format!("{}{}", Self::PREFIX, name.to_uppercase())).filter(|v| (*v).into() != 0)
+    }
+
+    /// Every name but the zero value's, in declaration order.
+    fn names() -> Vec<String> {
+        (1..).map_while(|i| Self::try_from(i).ok()).map(Self::name).collect()
+    }
+}
+
+macro_rules! wire_enum {
+    ($ty:ty, $prefix:literal) => {
+        impl WireEnum for $ty {
+            const PREFIX: &'static str = $prefix;
+
+            fn full_name(self) -> &'static str {
+                self.as_str_name()
+            }
+
+            fn from_full_name(name: &str) -> Option<Self> {
+                Self::from_str_name(name)
+            }
+        }
+    };
+}
+
+/// Something at something-dot-something, without whitespace.
+pub fn is_plausible_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && email.len() <= MAX_EMAIL_CHARS
+                && !email.chars().any(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+/// Only absolute http(s) URLs, so nothing like `javascript:` ends up in an `href`.
+pub fn is_http_url(value: &str) -> bool {
+    value.len() <= MAX_URL_CHARS
+        && url::Url::parse(value).is_ok_and(|u| matches!(u.scheme(), "http" | "https") && u.host().is_some())
+}
+
+wire_enum!(task::TaskStatus, "TASK_STATUS_");
+wire_enum!(task::TaskPriority, "TASK_PRIORITY_");
+
+/// Collects violations, keeping only the first for each field.
+#[derive(Debug, Default)]
+pub struct Rules {
+    violations: Vec<FieldViolation>,
+}
+
+impl Rules {
+    fn check(&mut self, field: &str, ok: bool, description: impl FnOnce() -> String) -> &mut Self {
+        if !ok && !self.violations.iter().any(|v| v.field == field) {
+            self.violations.push(FieldViolation::new(field, description()));
+        }
+        self
+    }
+
+    pub fn required(&mut self, field: &str, value: &str) -> &mut Self {
+        self.check(field, !value.trim().is_empty(), || "is required".into())
+    }
+
+    pub fn max_chars(&mut self, field: &str, value: &str, max: usize) -> &mut Self {
+        self.check(field, value.chars().count() <= max, || format!("must be at most {} characters", max))
+    }
+
+    pub fn uuid(&mut self, field: &str, value: &str) -> &mut Self {
+        self.required(field, value).optional_uuid(field, value)
+    }
+
+    /// Empty is allowed; anything else has to be a UUID.
+    pub fn optional_uuid(&mut self, field: &str, value: &str) -> &mut Self {
+        self.check(field, value.is_empty() || uuid::Uuid::parse_str(value).is_ok(), || "must be a UUID".into())
+    }
+
+    /// Empty is allowed; combine with `required` where it isn't.
+    pub fn one_of(&mut self, field: &str, value: &str, allowed: &[&str]) -> &mut Self {
+        self.check(field, value.is_empty() || allowed.contains(&value), || format!("must be one of {}", allowed.join(", ")))
+    }
+
+    /// Empty is allowed; anything else has to name one of `E`'s values.
+    pub fn wire_enum<E: WireEnum>(&mut self, field: &str, value: &str) -> &mut Self {
+        self.check(field, value.is_empty() || E::from_name(value).is_some(), || {
+            format!("must be one of {}", E::names().join(", "))
+        })
+    }
+
+    /// Empty is allowed; surrounding whitespace is ignored.
+    pub fn email(&mut self, field: &str, value: &str) -> &mut Self {
+        let value = value.trim();
+        self.check(field, value.is_empty() || is_plausible_email(value), || "must be an email address".into())
+    }
+
+    /// Empty is allowed; surrounding whitespace is ignored.
+    pub fn http_url(&mut self, field: &str, value: &str) -> &mut Self {
+        let value = value.trim();
+        self.check(field, value.is_empty() || is_http_url(value), || "must be an http(s) URL".into())
+    }
+
+    /// Empty is allowed; anything else has to be an RFC 3339 timestamp.
+    pub fn timestamp(&mut self, field: &str, value: &str) -> &mut Self {
+        self.check(field, value.is_empty() || chrono::DateTime::parse_from_rfc3339(value).is_ok(), || {
+            "must be an RFC 3339 timestamp".into()
+        })
+    }
+
+    pub fn max_items(&mut self, field: &str, count: usize, max: usize) -> &mut Self {
+        self.check(field, count <= max, || format!("must have at most {} entries", max))
+    }
+
+    pub fn range(&mut self, field: &str, value: i64, min: i64, max: i64) -> &mut Self {
+        self.check(field, (min..=max).contains(&value), || format!("must be between {} and {}", min, max))
+    }
+
+    pub fn at_least(&mut self, field: &str, value: f64, min: f64) -> &mut Self {
+        self.check(field, value >= min, || format!("must be at least {}", min))
+    }
+
+    pub fn finish(self) -> Result<(), tonic::Status> {
+        match self.violations.first() {
+            None => Ok(()),
+            Some(first) => {
+                let message = format!("Invalid request: {} {}", first.field, first.description);
+                Err(errors::bad_request(&message, self.violations))
+            }
+        }
+    }
+}
+
+macro_rules! no_rules {
+    ($($ty:ty),* $(,)?) => {
+        $(impl Validate for $ty {
+            fn rules(&self, _: &mut Rules) {}
+        })*
+    };
+}
+
+macro_rules! id_rules {
+    ($($ty:ty => $field:ident),* $(,)?) => {
+        $(impl Validate for $ty {
+            fn rules(&self, rules: &mut Rules) {
+                rules.uuid(stringify!($field), &self.$field);
+            }
+        })*
+    };
+}
+
+no_rules!(
+    team::ListMyInvitationsRequest,
+    team::ListMyOwnershipTransfersRequest,
+    user::EnrollTotpRequest,
+    user::ListOidcProvidersRequest,
+    user::ListDataRequestsRequest,
+    user::ListPersonalAccessTokensRequest,
+    // A missing token is answered like an invalid one.
+    user::IntrospectTokenRequest,
+);
+
+id_rules!(
+    idea::GetIdeaRequest => id,
+    idea::DeleteIdeaRequest => id,
+    idea::RestoreIdeaRequest => id,
+    idea::ListIdeaRevisionsRequest => idea_id,
+    idea::SetIdeaUpvoteRequest => id,
+    idea::SetIdeaBookmarkRequest => id,
+    idea::DeleteIdeaCommentRequest => id,
+    task::ListTasksRequest => project_id,
+    task::DeleteTaskRequest => id,
+    task::RestoreTaskRequest => id,
+    task::ArchiveProjectRequest => id,
+    task::RestoreProjectRequest => id,
+    team::GetTeamRequest => project_id,
+    team::ListInvitationsRequest => project_id,
+    team::RevokeInvitationRequest => id,
+    team::RespondToInvitationRequest => id,
+    team::RespondToOwnershipTransferRequest => id,
+    reputation::GetReputationBreakdownRequest => user_id,
+    privacy::ExportUserContentRequest => user_id,
+    privacy::EraseUserContentRequest => user_id,
+    user::GetUserRequest => id,
+    user::UnlockAccountRequest => user_id,
+    user::DownloadDataExportRequest => id,
+    user::RevokePersonalAccessTokenRequest => id,
+);
+
+impl Validate for idea::CreateIdeaRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules
+            .required("title", &self.title)
+            .max_chars("title", &self.title, MAX_TITLE_CHARS)
+            .required("problem", &self.problem)
+            .max_chars("problem", &self.problem, MAX_TEXT_CHARS)
+            .required("solution", &self.solution)
+            .max_chars("solution", &self.solution, MAX_TEXT_CHARS)
+            .optional_uuid("creator_id", &self.creator_id);
+    }
+}
+
+impl Validate for idea::ListIdeasRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.one_of("sort", &self.sort, &IDEA_SORTS).range("page_size", self.page_size.into(), 0, 100);
+    }
+}
+
+impl Validate for idea::UpdateIdeaRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.uuid("id", &self.id);
+        if let Some(title) = &self.title {
+            rules.required("title", title).max_chars("title", title, MAX_TITLE_CHARS);
+        }
+        for (field, value) in [("problem", &self.problem), ("solution", &self.solution)] {
+            if let Some(value) = value {
+                rules.required(field, value).max_chars(field, value, MAX_TEXT_CHARS);
+            }
+        }
+    }
+}
+
+impl Validate for idea::GetIdeaRevisionRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.uuid("idea_id", &self.idea_id).range("revision", self.revision.into(), 1, i32::MAX.into());
+    }
+}
+
+impl Validate for idea::RestoreIdeaRevisionRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.uuid("idea_id", &self.idea_id).range("revision", self.revision.into(), 1, i32::MAX.into());
+    }
+}
+
+impl Validate for idea::CreateIdeaCommentRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules
+            .uuid("idea_id", &self.idea_id)
+            .optional_uuid("parent_id", &self.parent_id)
+            .required("body", &self.body)
+            .max_chars("body", &self.body, MAX_COMMENT_CHARS);
+    }
+}
+
+impl Validate for idea::ListIdeaCommentsRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.uuid("idea_id", &self.idea_id).one_of("sort", &self.sort, &COMMENT_SORTS);
+    }
+}
+
+impl Validate for idea::UpdateIdeaCommentRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.uuid("id", &self.id).required("body", &self.body).max_chars("body", &self.body, MAX_COMMENT_CHARS);
+    }
+}
+
+impl Validate for idea::SetCommentReactionRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules
+            .uuid("comment_id", &self.comment_id)
+            .required("reaction", &self.reaction)
+            .one_of("reaction", &self.reaction, &COMMENT_REACTIONS);
+    }
+}
+
+impl Validate for idea::FindSimilarIdeasRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.uuid("idea_id", &self.idea_id).range("limit", self.limit.into(), 0, i32::MAX.into());
+    }
+}
+
+impl Validate for idea::SearchSkillsRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.max_chars("query", &self.query, MAX_TITLE_CHARS).range("limit", self.limit.into(), 0, i32::MAX.into());
+    }
+}
+
+impl Validate for idea::RecommendCollaboratorsRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.uuid("idea_id", &self.idea_id).range("limit", self.limit.into(), 0, i32::MAX.into());
+    }
+}
+
+impl Validate for task::CreateProjectRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules
+            .required("name", &self.name)
+            .max_chars("name", &self.name, MAX_TITLE_CHARS)
+            .max_chars("description", &self.description, MAX_TEXT_CHARS)
+            .optional_uuid("owner_id", &self.owner_id);
+    }
+}
+
+impl Validate for task::ListProjectsRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.optional_uuid("owner_id", &self.owner_id);
+    }
+}
+
+impl Validate for task::ListPublicProjectsRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.max_chars("industry_filter", &self.industry_filter, MAX_INDUSTRY_CHARS);
+    }
+}
+
+impl Validate for task::UpdateProjectRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules
+            .uuid("id", &self.id)
+            .max_chars("description", &self.description, MAX_TEXT_CHARS)
+            .at_least("funding_goal", self.funding_goal, 0.0)
+            .check("equity_offered", self.equity_offered <= 100.0, || "must be at most 100".into())
+            .max_chars("industry", &self.industry, MAX_INDUSTRY_CHARS);
+    }
+}
+
+impl Validate for task::LaunchProjectRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules
+            .uuid("idea_id", &self.idea_id)
+            .max_chars("title", &self.title, MAX_TITLE_CHARS)
+            .max_chars("description", &self.description, MAX_TEXT_CHARS)
+            .max_chars("industry", &self.industry, MAX_INDUSTRY_CHARS);
+    }
+}
+
+impl Validate for task::CreateTaskRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules
+            .uuid("project_id", &self.project_id)
+            .required("title", &self.title)
+            .max_chars("title", &self.title, MAX_TITLE_CHARS)
+            .max_chars("description", &self.description, MAX_TEXT_CHARS)
+            .wire_enum::<task::TaskPriority>("priority", &self.priority)
+            .optional_uuid("assignee_id", &self.assignee_id);
+    }
+}
+
+impl Validate for task::UpdateTaskRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules
+            .uuid("id", &self.id)
+            .wire_enum::<task::TaskStatus>("status", &self.status)
+            .wire_enum::<task::TaskPriority>("priority", &self.priority);
+    }
+}
+
+impl Validate for task::CreateNotificationRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules
+            .uuid("user_id", &self.user_id)
+            .required("type", &self.r#type)
+            .max_chars("type", &self.r#type, 50)
+            .max_chars("content", &self.content, MAX_TEXT_CHARS);
+    }
+}
+
+impl Validate for task::ListNotificationsRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.optional_uuid("user_id", &self.user_id);
+    }
+}
+
+impl Validate for team::UpdateMemberRoleRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.uuid("project_id", &self.project_id).uuid("user_id", &self.user_id).required("role", &self.role);
+    }
+}
+
+impl Validate for team::RemoveMemberRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.uuid("project_id", &self.project_id).uuid("user_id", &self.user_id);
+    }
+}
+
+impl Validate for team::InviteMemberRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules
+            .uuid("project_id", &self.project_id)
+            .required("email", &self.email)
+            .email("email", &self.email);
+    }
+}
+
+impl Validate for team::GetInvitationRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.required("token", &self.token);
+    }
+}
+
+impl Validate for team::TransferProjectOwnershipRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.uuid("project_id", &self.project_id).uuid("user_id", &self.user_id);
+    }
+}
+
+impl Validate for reputation::EndorseUserRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.optional_uuid("endorser_id", &self.endorser_id).uuid("user_id", &self.user_id).required("skill", &self.skill);
+    }
+}
+
+impl Validate for reputation::RateProjectRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules
+            .optional_uuid("investor_id", &self.investor_id)
+            .uuid("project_id", &self.project_id)
+            .range("rating", self.rating.into(), 1, 5);
+    }
+}
+
+impl Validate for user::BatchGetUsersRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.max_items("ids", self.ids.len(), MAX_BATCH_GET_USERS);
+        for id in &self.ids {
+            rules.uuid("ids", id);
+        }
+    }
+}
+
+impl Validate for user::CreateUserRequest {
+    fn rules(&self, rules: &mut Rules) {
+        // `username` predates the dedicated email field and is still accepted from older clients.
+        let email = if self.email.is_empty() { &self.username } else { &self.email };
+        rules
+            .required("email", email)
+            .email("email", email)
+            .required("password", &self.password)
+            .max_chars("full_name", self.full_name.trim(), MAX_FULL_NAME_CHARS)
+            .max_chars("bio", self.bio.trim(), MAX_BIO_CHARS)
+            .one_of("role", &self.role, &SIGNUP_ROLES);
+    }
+}
+
+impl Validate for user::LoginRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.required("email", &self.email).required("password", &self.password);
+    }
+}
+
+impl Validate for user::VerifyLoginChallengeRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.required("mfa_token", &self.mfa_token).required("code", &self.code);
+    }
+}
+
+impl Validate for user::VerifyEmailRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.required("token", &self.token);
+    }
+}
+
+impl Validate for user::ResendVerificationEmailRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.required("email", &self.email).email("email", &self.email);
+    }
+}
+
+impl Validate for user::RequestPasswordResetRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.required("email", &self.email).email("email", &self.email);
+    }
+}
+
+impl Validate for user::ResetPasswordRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.required("token", &self.token).required("new_password", &self.new_password);
+    }
+}
+
+impl Validate for user::ConfirmTotpRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.required("code", &self.code);
+    }
+}
+
+impl Validate for user::DisableTotpRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.required("password", &self.password).required("code", &self.code);
+    }
+}
+
+impl Validate for user::BeginOidcLoginRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.required("provider", &self.provider);
+    }
+}
+
+impl Validate for user::CompleteOidcLoginRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.required("provider", &self.provider).required("state", &self.state).required("code", &self.code);
+    }
+}
+
+impl Validate for user::UpdateUserRequest {
+    fn rules(&self, rules: &mut Rules) {
+        if let Some(full_name) = &self.full_name {
+            rules.max_chars("full_name", full_name.trim(), MAX_FULL_NAME_CHARS);
+        }
+        if let Some(bio) = &self.bio {
+            rules.max_chars("bio", bio.trim(), MAX_BIO_CHARS);
+        }
+        if let Some(avatar_url) = &self.avatar_url {
+            rules.http_url("avatar_url", avatar_url);
+        }
+        if let Some(links) = &self.links {
+            let links: Vec<&String> = links.values.iter().filter(|v| !v.trim().is_empty()).collect();
+            rules.max_items("links", links.len(), MAX_LINKS);
+            for link in links {
+                rules.http_url("links", link);
+            }
+        }
+        if let Some(skills) = &self.skills {
+            let skills: Vec<String> = skills
+                .values
+                .iter()
+                .map(|v| v.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|v| !v.is_empty())
+                .collect();
+            rules.max_items("skills", skills.len(), MAX_SKILLS);
+            for skill in &skills {
+                rules.max_chars("skills", skill, MAX_SKILL_CHARS);
+            }
+        }
+        if let Some(hours) = self.hours_per_week {
+            rules.range("hours_per_week", hours.into(), 0, MAX_HOURS_PER_WEEK.into());
+        }
+    }
+}
+
+impl Validate for user::ChangePasswordRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.required("current_password", &self.current_password).required("new_password", &self.new_password);
+    }
+}
+
+impl Validate for user::ChangeEmailRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules
+            .required("new_email", &self.new_email)
+            .email("new_email", &self.new_email)
+            .required("current_password", &self.current_password);
+    }
+}
+
+impl Validate for user::ConfirmEmailChangeRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.required("token", &self.token);
+    }
+}
+
+impl Validate for user::DeleteAccountRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.required("current_password", &self.current_password);
+    }
+}
+
+impl Validate for user::RequestDataExportRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.one_of("format", self.format.trim(), &EXPORT_FORMATS);
+    }
+}
+
+impl Validate for user::GetDataRequestRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.uuid("id", &self.id);
+    }
+}
+
+impl Validate for user::CreatePersonalAccessTokenRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules
+            .required("name", &self.name)
+            .max_chars("name", self.name.trim(), MAX_TOKEN_NAME_CHARS)
+            .check("scopes", !self.scopes.is_empty(), || "is required".into())
+            .range("expires_in_days", self.expires_in_days.into(), 0, MAX_TOKEN_TTL_DAYS.into());
+        for scope in &self.scopes {
+            rules.one_of("scopes", &scope.trim().to_ascii_lowercase(), &TOKEN_SCOPES);
+        }
+    }
+}
+
+impl Validate for user::ListSecurityEventsRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.optional_uuid("page_token", &self.page_token);
+    }
+}
+
+impl Validate for user::AdminListSecurityEventsRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules
+            .optional_uuid("user_id", self.user_id.trim())
+            .one_of("outcome", &self.outcome, &EVENT_OUTCOMES)
+            .timestamp("since", &self.since)
+            .timestamp("until", &self.until)
+            .optional_uuid("page_token", &self.page_token);
+    }
+}
+
+impl Validate for user::SetUserRoleRequest {
+    fn rules(&self, rules: &mut Rules) {
+        rules.uuid("user_id", &self.user_id).required("role", &self.role).one_of("role", &self.role, &ASSIGNABLE_ROLES);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn violations(result: Result<(), tonic::Status>) -> Vec<(String, String)> {
+        let status = result.expect_err("should be invalid");
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        errors::field_violations(&status).into_iter().map(|v| (v.field, v.description)).collect()
+    }
+
+    fn fields<T: Validate>(request: &T) -> Vec<String> {
+        match request.validate() {
+            Ok(()) => Vec::new(),
+            Err(status) => errors::field_violations(&status).into_iter().map(|v| v.field).collect(),
+        }
+    }
+
+    #[test]
+    fn wire_enum_names_drop_the_prefix_and_the_zero_value() {
+        assert_eq!(task::TaskStatus::names(), ["todo", "in_progress", "done"]);
+        assert_eq!(task::TaskStatus::InProgress.name(), "in_progress");
+        assert_eq!(task::TaskStatus::from_name("in_progress"), Some(task::TaskStatus::InProgress));
+        assert_eq!(task::TaskPriority::from_name("urgent"), Some(task::TaskPriority::Urgent));
+    }
+
+    #[test]
+    fn wire_enum_from_name_refuses_anything_else() {
+        assert_eq!(task::TaskStatus::from_name(""), None);
+        assert_eq!(task::TaskStatus::from_name("unspecified"), None);
+        assert_eq!(task::TaskStatus::from_name("DONE"), None);
+        assert_eq!(task::TaskStatus::from_name("TASK_STATUS_DONE"), None);
+        assert_eq!(task::TaskStatus::from_name("finished"), None);
+    }
+
+    #[test]
+    fn no_rules_broken_is_ok() {
+        let mut rules = Rules::default();
+        rules
+            .required("title", "Idea")
+            .max_chars("title", "Idea", 4)
+            .uuid("id", "8f1d2c7e-0b7a-4c55-9a51-3c2b1d0e9f10")
+            .optional_uuid("parent_id", "")
+            .one_of("sort", "", &IDEA_SORTS)
+            .wire_enum::<task::TaskStatus>("status", "done")
+            .email("email", " ada@example.com ")
+            .http_url("avatar_url", "https://example.com/a.png")
+            .timestamp("since", "2026-01-31T12:00:00Z")
+            .max_items("ids", 2, 2)
+            .range("page_size", 100, 0, 100)
+            .at_least("funding_goal", 0.0, 0.0);
+        assert!(rules.finish().is_ok());
+    }
+
+    #[test]
+    fn each_rule_reports_its_field() {
+        let mut rules = Rules::default();
+        rules
+            .required("title", "  ")
+            .max_chars("body", "ééé", 2)
+            .uuid("id", "42")
+            .one_of("sort", "oldest", &IDEA_SORTS)
+            .wire_enum::<task::TaskPriority>("priority", "critical")
+            .email("email", "ada@localhost")
+            .http_url("avatar_url", "javascript:alert(1)")
+            .timestamp("since", "yesterday")
+            .max_items("ids", 3, 2)
+            .range("rating", 6, 1, 5)
+            .at_least("funding_goal", -1.0, 0.0);
+        assert_eq!(
+            violations(rules.finish()),
+            [
+                ("title", "is required"),
+                ("body", "must be at most 2 characters"),
+                ("id", "must be a UUID"),
+                ("sort", "must be one of new, top, trending"),
+                ("priority", "must be one of low, medium, high, urgent"),
+                ("email", "must be an email address"),
+                ("avatar_url", "must be an http(s) URL"),
+                ("since", "must be an RFC 3339 timestamp"),
+                ("ids", "must have at most 2 entries"),
+                ("rating", "must be between 1 and 5"),
+                ("funding_goal", "must be at least 0"),
+            ]
+            .map(|(f, d)| (f.to_string(), d.to_string()))
+        );
+    }
+
+    #[test]
+    fn only_the_first_violation_per_field_is_kept() {
+        let mut rules = Rules::default();
+        rules.required("title", "").max_chars("title", "", 0).uuid("id", "").required("body", "");
+        assert_eq!(
+            violations(rules.finish()),
+            [("title", "is required"), ("id", "is required"), ("body", "is required")].map(|(f, d)| (f.to_string(), d.to_string()))
+        );
+    }
+
+    #[test]
+    fn the_message_names_the_first_violation() {
+        let mut rules = Rules::default();
+        rules.range("rating", 0, 1, 5).required("skill", "");
+        let status = rules.finish().unwrap_err();
+        assert_eq!(status.message(), "Invalid request: rating must be between 1 and 5");
+        assert_eq!(errors::field_violations(&status).len(), 2);
+        assert!(errors::field_violations(&tonic::Status::invalid_argument("plain")).is_empty());
+    }
+
+    #[test]
+    fn list_ideas_page_size_is_bounded() {
+        let request = |page_size| idea::ListIdeasRequest { page_size, ..Default::default() };
+        assert!(fields(&request(0)).is_empty());
+        assert!(fields(&request(100)).is_empty());
+        assert_eq!(fields(&request(101)), ["page_size"]);
+        assert_eq!(fields(&request(-1)), ["page_size"]);
+    }
+
+    #[test]
+    fn create_user_checks_email_password_and_role() {
+        let valid = user::CreateUserRequest {
+            email: "ada@example.com".into(),
+            password: "correct horse battery staple".into(),
+            ..Default::default()
+        };
+        assert!(fields(&valid).is_empty());
+        // Older clients send the address as the username.
+        let legacy = user::CreateUserRequest { email: String::new(), username: "ada@example.com".into(), ..valid.clone() };
+        assert!(fields(&legacy).is_empty());
+
+        let invalid = user::CreateUserRequest {
+            email: "not an email".into(),
+            password: String::new(),
+            full_name: "x".repeat(MAX_FULL_NAME_CHARS + 1),
+            role: "admin".into(),
+            ..Default::default()
+        };
+        assert_eq!(fields(&invalid), ["email", "password", "full_name", "role"]);
+    }
+
+    #[test]
+    fn update_user_checks_only_the_fields_sent() {
+        assert!(fields(&user::UpdateUserRequest::default()).is_empty());
+
+        let clearing = user::UpdateUserRequest {
+            avatar_url: Some(String::new()),
+            links: Some(user::StringList { values: vec![" ".into()] }),
+            hours_per_week: Some(0),
+            ..Default::default()
+        };
+        assert!(fields(&clearing).is_empty());
+
+        let invalid = user::UpdateUserRequest {
+            bio: Some("x".repeat(MAX_BIO_CHARS + 1)),
+            avatar_url: Some("ftp://example.com/a.png".into()),
+            links: Some(user::StringList { values: vec!["https://example.com".into(); MAX_LINKS + 1] }),
+            skills: Some(user::StringList { values: vec!["x".repeat(MAX_SKILL_CHARS + 1)] }),
+            hours_per_week: Some(MAX_HOURS_PER_WEEK + 1),
+            ..Default::default()
+        };
+        assert_eq!(fields(&invalid), ["bio", "avatar_url", "links", "skills", "hours_per_week"]);
+    }
+
+    #[test]
+    fn personal_access_tokens_need_a_name_and_known_scopes() {
+        let valid = user::CreatePersonalAccessTokenRequest {
+            name: "CI".into(),
+            scopes: vec![" Ideas:Read ".into(), "tasks:write".into()],
+            expires_in_days: 0,
+        };
+        assert!(fields(&valid).is_empty());
+
+        let unscoped = user::CreatePersonalAccessTokenRequest { name: " ".into(), scopes: vec![], expires_in_days: 366 };
+        assert_eq!(fields(&unscoped), ["name", "scopes", "expires_in_days"]);
+        let unknown = user::CreatePersonalAccessTokenRequest { scopes: vec!["admin".into()], ..valid };
+        assert_eq!(fields(&unknown), ["scopes"]);
+    }
+
+    #[test]
+    fn security_event_filters_are_checked() {
+        assert!(fields(&user::AdminListSecurityEventsRequest::default()).is_empty());
+        let invalid = user::AdminListSecurityEventsRequest {
+            user_id: "someone".into(),
+            outcome: "maybe".into(),
+            since: "2026-01-31".into(),
+            page_token: "next".into(),
+            ..Default::default()
+        };
+        assert_eq!(fields(&invalid), ["user_id", "outcome", "since", "page_token"]);
+    }
+
+    #[test]
+    fn batch_get_users_is_capped() {
+        let ids = vec!["8f1d2c7e-0b7a-4c55-9a51-3c2b1d0e9f10".to_string(); MAX_BATCH_GET_USERS + 1];
+        assert_eq!(fields(&user::BatchGetUsersRequest { ids }), ["ids"]);
+        assert_eq!(fields(&user::BatchGetUsersRequest { ids: vec!["42".into()] }), ["ids"]);
+    }
+
+    #[test]
+    fn invitations_need_a_plausible_email() {
+        let invite = |email: &str| team::InviteMemberRequest {
+            project_id: "8f1d2c7e-0b7a-4c55-9a51-3c2b1d0e9f10".into(),
+            email: email.into(),
+            ..Default::default()
+        };
+        assert!(fields(&invite("  Ada@Example.COM ")).is_empty());
+        for invalid in ["", "ada", "@example.com", "ada@localhost", "ada lovelace@example.com"] {
+            assert_eq!(fields(&invite(invalid)), ["email"], "{:?}", invalid);
+        }
+        assert_eq!(fields(&invite(&format!("{}@example.com", "a".repeat(MAX_EMAIL_CHARS)))), ["email"]);
+    }
+}